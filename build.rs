@@ -0,0 +1,18 @@
+use std::{env, path::PathBuf};
+
+/// Selects the file name list embedded into the binary, `MHWS_NAME_LIST` overrides the default.
+fn main() {
+    println!("cargo:rerun-if-env-changed=MHWS_NAME_LIST");
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let list_path = match env::var("MHWS_NAME_LIST") {
+        Ok(path) => manifest_dir.join(path),
+        Err(_) => manifest_dir.join("assets/MHWs_STM_Release.list.zst"),
+    };
+    if !list_path.is_file() {
+        panic!("file name list `{}` not found", list_path.display());
+    }
+
+    println!("cargo:rerun-if-changed={}", list_path.display());
+    println!("cargo:rustc-env=MHWS_NAME_LIST_PATH={}", list_path.display());
+}
@@ -0,0 +1,128 @@
+//! `--align`: entry data starting at multiples of a boundary, e.g. 4 KiB pages, which lets the
+//! game read uncompressed textures with fewer and aligned reads. The pak writer packs entries back
+//! to back, the data is moved into place once a part is written, back to front so it happens in
+//! place.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{
+    pak::PakFile,
+    pak_header::{ENTRY_RECORD_SIZE, PakHeader},
+    util,
+};
+
+/// Largest alignment accepted, beyond it the padding only wastes space.
+const MAX_ALIGNMENT: u64 = 1 << 20;
+const COPY_BUFFER_SIZE: usize = 8 << 20;
+
+/// Parses an alignment like `4K`, a power of two.
+pub fn parse_alignment(s: &str) -> Result<u64, String> {
+    let alignment = util::parse_size(s)?;
+    if !alignment.is_power_of_two() || alignment > MAX_ALIGNMENT {
+        return Err(format!(
+            "alignment `{s}` must be a power of two up to 1M, e.g. `4K`"
+        ));
+    }
+    Ok(alignment)
+}
+
+/// Moves the entry data of the pak at `path` to multiples of `alignment` and points the entry
+/// table at the new offsets.
+pub fn align_entries(path: &Path, alignment: u64) -> eyre::Result<()> {
+    if PakHeader::read(path)?.has_encrypted_toc() {
+        eyre::bail!("the entry table of the output is encrypted, its offsets can't be aligned.");
+    }
+    let pak = PakFile::open(path)?;
+    let mut entries: Vec<(u64, u64, u64)> = pak
+        .entries()
+        .iter()
+        .map(|entry| (entry.offset(), entry.compressed_size(), entry.hash()))
+        .collect();
+    entries.sort_unstable();
+    let Some(&(table_end, _, _)) = entries.first() else {
+        return Ok(());
+    };
+
+    // never before the old offset, so every move goes towards the end of the file
+    let mut new_offsets = HashMap::with_capacity(entries.len());
+    let mut moves = Vec::with_capacity(entries.len());
+    let mut end = 0;
+    for &(offset, size, hash) in &entries {
+        let new_offset = offset.max(end).next_multiple_of(alignment);
+        new_offsets.insert((hash, offset), new_offset);
+        moves.push((offset, new_offset, size));
+        end = new_offset + size;
+    }
+    drop(pak);
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut table = vec![0u8; table_end as usize];
+    file.read_exact(&mut table)?;
+    // before any data moves, a table that can't be patched leaves the file as it was
+    patch_table(&mut table, &new_offsets)?;
+    let file_end = file.seek(SeekFrom::End(0))?;
+    let (last_offset, _, last_size) = entries[entries.len() - 1];
+    // whatever follows the last entry moves along with it
+    let tail = file_end - (last_offset + last_size);
+    moves.push((last_offset + last_size, end, tail));
+
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    for &(offset, new_offset, size) in moves.iter().rev() {
+        if new_offset == offset {
+            continue;
+        }
+        // the ranges may overlap, copying from the end never overwrites what's still to be read
+        let mut remaining = size;
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len() as u64);
+            remaining -= chunk;
+            let chunk_buffer = &mut buffer[..chunk as usize];
+            file.seek(SeekFrom::Start(offset + remaining))?;
+            file.read_exact(chunk_buffer)?;
+            file.seek(SeekFrom::Start(new_offset + remaining))?;
+            file.write_all(chunk_buffer)?;
+        }
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&table)?;
+    Ok(())
+}
+
+/// Rewrites the offsets of the entry records, keyed by `(hash, old offset)`.
+fn patch_table(table: &mut [u8], new_offsets: &HashMap<(u64, u64), u64>) -> eyre::Result<()> {
+    let record = |table: &[u8], at: usize| {
+        let word =
+            |offset: usize| u32::from_le_bytes(table[offset..offset + 4].try_into().unwrap());
+        let hash = word(at) as u64 | ((word(at + 4) as u64) << 32);
+        let offset = u64::from_le_bytes(table[at + 8..at + 16].try_into().unwrap());
+        (hash, offset)
+    };
+    // the records follow the header back to back, the first one is the first match
+    let Some(start) = (0..table.len().saturating_sub(ENTRY_RECORD_SIZE - 1))
+        .step_by(4)
+        .find(|&at| new_offsets.contains_key(&record(table, at)))
+    else {
+        eyre::bail!("the entry table of the output couldn't be found.");
+    };
+    let mut patched = 0;
+    for at in (start..=table.len() - ENTRY_RECORD_SIZE).step_by(ENTRY_RECORD_SIZE) {
+        let Some(new_offset) = new_offsets.get(&record(table, at)) else {
+            break;
+        };
+        table[at + 8..at + 16].copy_from_slice(&new_offset.to_le_bytes());
+        patched += 1;
+    }
+    if patched != new_offsets.len() {
+        eyre::bail!(
+            "only {patched} of the {} entry records of the output were found.",
+            new_offsets.len()
+        );
+    }
+    Ok(())
+}
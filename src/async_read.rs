@@ -0,0 +1,105 @@
+//! Read-ahead on tokio for slow drives: several stored entries are read concurrently, each through
+//! its own file handle, so a drive with high latency (USB, network shares) is kept busy. The rayon
+//! workers decompress the entries while the next ones are read.
+//!
+//! Same contract as [`read_ahead::try_for_each`](crate::read_ahead::try_for_each), entries may
+//! reach the workers out of order.
+
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use ree_pak_core::pak::PakEntry;
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    sync::mpsc,
+    task::JoinSet,
+};
+
+use crate::{open_files, pak::PakFile};
+
+/// Reads in flight at once, enough to fill the queue of an external drive.
+const READS_IN_FLIGHT: usize = 8;
+
+/// Calls `f` on the current rayon pool for every entry of the pak at `path`, stopping at the
+/// first error. At most `depth` read entries wait for a worker.
+pub fn try_for_each<P, F>(
+    path: &Path,
+    source: &PakFile,
+    entries: &[&PakEntry],
+    depth: usize,
+    prefetch: P,
+    f: F,
+) -> eyre::Result<()>
+where
+    P: Fn(&PakEntry) -> bool + Send,
+    F: Fn(usize, &PakEntry, eyre::Result<Option<Vec<u8>>>) -> eyre::Result<()> + Sync + Send,
+{
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let (sender, mut receiver) = mpsc::channel(depth.max(1));
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            runtime.block_on(async {
+                let mut reads = JoinSet::new();
+                let mut pending = entries.iter().enumerate();
+                loop {
+                    while reads.len() < READS_IN_FLIGHT {
+                        let Some((index, &entry)) = pending.next() else {
+                            break;
+                        };
+                        if !prefetch(entry) {
+                            if sender.send((index, Ok(None))).await.is_err() {
+                                return;
+                            }
+                            continue;
+                        }
+                        let path = path.to_path_buf();
+                        let (offset, size) = (entry.offset(), entry.compressed_size());
+                        reads.spawn(async move {
+                            (index, read_stored(path, offset, size).await.map(Some))
+                        });
+                    }
+                    let Some(read) = reads.join_next().await else {
+                        return;
+                    };
+                    let read = read.unwrap_or_else(|e| (usize::MAX, Err(e.into())));
+                    // the workers stopped on an error and dropped the receiver
+                    if sender.send(read).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        });
+        // owned by the iterator, so it's dropped when the workers stop early and the reader
+        // waiting on a full channel returns
+        std::iter::from_fn(move || receiver.blocking_recv())
+            .par_bridge()
+            .try_for_each(|(index, stored)| {
+                let Some(&entry) = entries.get(index) else {
+                    return stored.map(|_| ());
+                };
+                let raw = stored.and_then(|stored| {
+                    stored
+                        .map(|stored| source.decode_stored(entry, stored))
+                        .transpose()
+                });
+                f(index, entry, raw)
+            })
+    })
+}
+
+/// The stored (compressed) bytes of an entry.
+async fn read_stored(path: PathBuf, offset: u64, size: u64) -> eyre::Result<Vec<u8>> {
+    // waiting for a permit would block the runtime, the reads holding the others included
+    let _permit = tokio::task::spawn_blocking(open_files::acquire).await?;
+    let mut file = tokio::fs::File::open(&path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut stored = vec![0; size as usize];
+    file.read_exact(&mut stored).await?;
+    Ok(stored)
+}
@@ -0,0 +1,59 @@
+//! Small JSON entry embedded in an output under a reserved path, so a user's patch pak can be
+//! traced back to the tool version, source pak and options that produced it.
+
+use std::{fs, io::Read, path::Path};
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::{manifest, pak::PakFile, path_hash, pipeline::Options};
+
+/// Not a game path, the game never looks it up.
+pub const PATH: &str = "natives/STM/_mhws-tex-decompressor/build_info.json";
+
+pub fn hash() -> u64 {
+    path_hash::hash_path(PATH)
+}
+
+/// The entry's data for an output of `source`.
+pub fn build(options: &Options, source: &PakFile) -> eyre::Result<Vec<u8>> {
+    let info = json!({
+        "tool": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "source": {
+            "file": manifest::file_name(&options.input_path),
+            "size": fs::metadata(&options.input_path)?.len(),
+            "toc_sha256": toc_sha256(&options.input_path, source)?,
+        },
+        "options": manifest::options_json(options)?,
+    });
+    Ok(serde_json::to_vec_pretty(&info)?)
+}
+
+/// The embedded build info of a pak, `None` if it has none.
+pub fn read(pak: &PakFile) -> eyre::Result<Option<Value>> {
+    let hash = hash();
+    match pak.entries().iter().find(|entry| entry.hash() == hash) {
+        Some(entry) => Ok(Some(serde_json::from_slice(&pak.read_entry(entry)?)?)),
+        None => Ok(None),
+    }
+}
+
+/// SHA-256 of the header and entry table, which identifies the pak without hashing GBs of data.
+pub fn toc_sha256(path: &Path, source: &PakFile) -> eyre::Result<String> {
+    // the table follows the header, it ends before the first entry's data
+    let table_end = source
+        .entries()
+        .iter()
+        .map(|entry| entry.offset())
+        .min()
+        .unwrap_or(0);
+    let mut table = vec![];
+    fs::File::open(path)?
+        .take(table_end)
+        .read_to_end(&mut table)?;
+    Ok(Sha256::digest(&table)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
@@ -0,0 +1,51 @@
+//! Cancellation of a running job by Ctrl+C or the GUI's cancel button. Workers stop picking up
+//! entries and the output is finalized with the entries written so far.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::exit_code::ExitCode;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Cancels the running job on Ctrl+C, a second Ctrl+C exits right away.
+pub fn install_ctrlc_handler() -> eyre::Result<()> {
+    ctrlc::set_handler(|| {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            ExitCode::Cancelled.exit();
+        }
+        eprintln!("Cancelling, finishing the output... Press Ctrl+C again to quit immediately.");
+    })?;
+    Ok(())
+}
+
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Clears a previous cancellation before a new job starts.
+pub fn reset() {
+    REQUESTED.store(false, Ordering::SeqCst);
+}
+
+/// Fails with [`Cancelled`] once cancellation was requested.
+pub fn check() -> eyre::Result<()> {
+    if REQUESTED.load(Ordering::SeqCst) {
+        return Err(Cancelled.into());
+    }
+    Ok(())
+}
+
+/// The job was cancelled by the user.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled by the user")
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -0,0 +1,91 @@
+use clap::ValueEnum;
+
+/// Rough texture categories, derived from the path under `natives/STM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Category {
+    Ui,
+    Environment,
+    Character,
+    Monster,
+    Item,
+    Effect,
+    Other,
+}
+
+/// Path prefixes (lowercase, relative to `natives/stm/`, streaming dir stripped) of each category.
+const CATEGORY_PREFIXES: &[(Category, &[&str])] = &[
+    (Category::Ui, &["gui/"]),
+    (
+        Category::Environment,
+        &[
+            "art/model/stagemodel/",
+            "art/stage/",
+            "art/library/",
+            "art/light/",
+            "gamedesign/stage/",
+        ],
+    ),
+    (Category::Character, &["art/model/character/"]),
+    (
+        Category::Monster,
+        &["art/model/enemy/", "art/model/animal/"],
+    ),
+    (Category::Item, &["art/model/item/"]),
+    (
+        Category::Effect,
+        &[
+            "art/vfx/",
+            "re_engine_library/vfx_library/",
+            "systems/effect/",
+        ],
+    ),
+];
+
+impl Category {
+    pub const ALL: [Category; 7] = [
+        Category::Ui,
+        Category::Environment,
+        Category::Character,
+        Category::Monster,
+        Category::Item,
+        Category::Effect,
+        Category::Other,
+    ];
+
+    pub fn of(path: &str) -> Category {
+        let path = path.to_ascii_lowercase();
+        let path = path.strip_prefix("natives/stm/").unwrap_or(&path);
+        let path = path.strip_prefix("streaming/").unwrap_or(path);
+
+        CATEGORY_PREFIXES
+            .iter()
+            .find(|(_, prefixes)| prefixes.iter().any(|prefix| path.starts_with(prefix)))
+            .map(|(category, _)| *category)
+            .unwrap_or(Category::Other)
+    }
+
+    /// Lowercase name as on the command line, used in file names.
+    pub fn id(self) -> &'static str {
+        match self {
+            Category::Ui => "ui",
+            Category::Environment => "environment",
+            Category::Character => "character",
+            Category::Monster => "monster",
+            Category::Item => "item",
+            Category::Effect => "effect",
+            Category::Other => "other",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Ui => "UI textures",
+            Category::Environment => "Environment",
+            Category::Character => "Character",
+            Category::Monster => "Monster",
+            Category::Item => "Item",
+            Category::Effect => "Effects",
+            Category::Other => "Other",
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Embedded changelog, shown once after updating to a new version.
+
+use std::fs;
+
+use colored::Colorize;
+
+use crate::util;
+
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+const LAST_VERSION_FILE: &str = "last_version";
+
+struct Release<'a> {
+    version: (u32, u32, u32),
+    title: &'a str,
+    changes: Vec<&'a str>,
+    migration: Vec<&'a str>,
+}
+
+pub fn parse_version(s: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = s.trim().trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts
+        .next()?
+        .trim_end_matches(|c: char| !c.is_ascii_digit());
+    Some((major, minor, patch.parse().ok()?))
+}
+
+/// Released sections of the changelog, `Unreleased` is skipped.
+fn releases() -> Vec<Release<'static>> {
+    let mut releases = vec![];
+    let mut in_migration = false;
+    for line in CHANGELOG.lines() {
+        if let Some(title) = line.strip_prefix("## ") {
+            in_migration = false;
+            let version = title.split_whitespace().next().and_then(parse_version);
+            if let Some(version) = version {
+                releases.push(Release {
+                    version,
+                    title,
+                    changes: vec![],
+                    migration: vec![],
+                });
+            }
+            continue;
+        }
+        if line.starts_with("### ") {
+            in_migration = line.trim_start_matches('#').trim() == "Migration";
+            continue;
+        }
+        // skip entries of `Unreleased`, which starts before any release
+        let (Some(release), Some(entry)) = (releases.last_mut(), line.strip_prefix("- ")) else {
+            continue;
+        };
+        if in_migration {
+            release.migration.push(entry);
+        } else {
+            release.changes.push(entry);
+        }
+    }
+    releases
+}
+
+/// Prints the changes since the last version that ran on this machine, if it was an older one.
+pub fn show_if_updated() {
+    let Some(current) = parse_version(env!("CARGO_PKG_VERSION")) else {
+        return;
+    };
+    let Some(config_dir) = util::config_dir() else {
+        return;
+    };
+    let last_version_path = config_dir.join(LAST_VERSION_FILE);
+    let last = fs::read_to_string(&last_version_path)
+        .ok()
+        .and_then(|s| parse_version(&s));
+
+    if let Some(last) = last.filter(|last| *last < current) {
+        let new_releases: Vec<_> = releases()
+            .into_iter()
+            .filter(|release| release.version > last && release.version <= current)
+            .collect();
+        if !new_releases.is_empty() {
+            println!("{}", "What's new since your last run:".cyan().bold());
+            print_releases(&new_releases);
+        }
+    }
+
+    if last != Some(current) {
+        let _ = fs::create_dir_all(&config_dir)
+            .and_then(|_| fs::write(&last_version_path, env!("CARGO_PKG_VERSION")));
+    }
+}
+
+/// Prints the whole released changelog.
+pub fn show_all() {
+    print_releases(&releases());
+}
+
+fn print_releases(releases: &[Release]) {
+    for release in releases {
+        println!("{}", release.title.bold());
+        for change in &release.changes {
+            println!("  - {change}");
+        }
+        if !release.migration.is_empty() {
+            println!("  {}", "Migration notes:".yellow().bold());
+            for note in &release.migration {
+                println!("  - {note}");
+            }
+        }
+    }
+    println!();
+}
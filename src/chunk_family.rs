@@ -0,0 +1,232 @@
+//! The game splits a chunk into `re_chunk_000.pak.sub_000.pak`, `sub_001` and so on. These run
+//! the parts of such a family one after another, into one merged output or an output per part.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    free_space,
+    incremental::SourceIndex,
+    merge::{self, Overlap},
+    names::NameTable,
+    pipeline::{self, Options, Summary},
+    progress::Progress,
+    session_lock::SessionLock,
+    stats::StatsSnapshot,
+    util,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChunkOutput {
+    /// One output with the entries of all parts.
+    Merged,
+    /// An output next to every part.
+    Parts,
+}
+
+/// `re_chunk_000.pak.sub_000.pak` -> (`re_chunk_000.pak`, 0).
+pub fn split_name(file_name: &str) -> Option<(&str, u32)> {
+    let (base, part) = file_name.strip_suffix(".pak")?.rsplit_once(".sub_")?;
+    if part.is_empty() || !part.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((base, part.parse().ok()?))
+}
+
+/// All parts of the chunk family `path` belongs to, in order.
+pub fn parts(path: &Path) -> eyre::Result<Vec<PathBuf>> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let Some((base, _)) = split_name(&file_name) else {
+        eyre::bail!(
+            "`{}` isn't part of a chunk family, expected a name like `re_chunk_000.pak.sub_000.pak`.",
+            util::display_path(path)
+        );
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut parts = vec![];
+    for dir_entry in fs::read_dir(dir)? {
+        let dir_entry = dir_entry?;
+        let name = dir_entry.file_name().to_string_lossy().to_string();
+        match split_name(&name) {
+            Some((other_base, part)) if other_base == base && dir_entry.path().is_file() => {
+                parts.push((part, dir_entry.path()));
+            }
+            _ => {}
+        }
+    }
+    parts.sort();
+    Ok(parts.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Default output of the merged family of `path`: `re_chunk_000.uncompressed.pak`.
+pub fn merged_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    match split_name(&file_name) {
+        Some((base, _)) => path.with_file_name(base).with_extension("uncompressed.pak"),
+        None => path.with_extension("uncompressed.pak"),
+    }
+}
+
+/// Processes every part of the family of `options.input_path`. Stops after the first part that
+/// stops early, a merged output then holds the parts processed so far.
+pub fn run(
+    options: &Options,
+    mode: ChunkOutput,
+    name_table: &NameTable,
+    progress: &dyn Progress,
+) -> eyre::Result<Summary> {
+    if options.previous_output.is_some() {
+        eyre::bail!(
+            "incremental updates work on a single pak, they can't be combined with `--chunk-family`."
+        );
+    }
+    let parts = parts(&options.input_path)?;
+    let merged_path = options.output_path();
+    let _lock = match mode {
+        ChunkOutput::Merged => Some(SessionLock::acquire(&merged_path)?),
+        ChunkOutput::Parts => None,
+    };
+
+    let mut part_outputs = vec![];
+    let mut summary = Summary {
+        output_paths: vec![],
+        entries_written: 0,
+        bytes_written: 0,
+        processing_error: None,
+    };
+    for (index, part) in parts.iter().enumerate() {
+        let output = match mode {
+            ChunkOutput::Merged => Some(merged_path.with_extension(format!("part{index:03}.pak"))),
+            ChunkOutput::Parts => options
+                .output
+                .as_ref()
+                .map(|output| output.with_extension(format!("sub_{index:03}.pak"))),
+        };
+        let part_options = Options {
+            input_path: part.clone(),
+            output,
+            // the part outputs of a merged run are ours
+            force: options.force || mode == ChunkOutput::Merged,
+            ..options.clone()
+        };
+        progress.info(&format!(
+            "Part {}/{}: {}",
+            index + 1,
+            parts.len(),
+            util::display_path(part)
+        ));
+        let part_progress = PartProgress {
+            inner: progress,
+            label: format!("[{}/{}]", index + 1, parts.len()),
+        };
+        let part_summary = match pipeline::run(&part_options, name_table, &part_progress) {
+            Ok(part_summary) => part_summary,
+            Err(e) => {
+                if mode == ChunkOutput::Merged {
+                    remove_part_outputs(&part_outputs);
+                }
+                return Err(e);
+            }
+        };
+        part_outputs.extend(part_summary.output_paths);
+        summary.entries_written += part_summary.entries_written;
+        summary.bytes_written += part_summary.bytes_written;
+        if part_summary.processing_error.is_some() {
+            summary.processing_error = part_summary.processing_error;
+            break;
+        }
+    }
+
+    match mode {
+        ChunkOutput::Parts => summary.output_paths = part_outputs,
+        ChunkOutput::Merged => {
+            let merged = check_merge_space(options, &merged_path, &part_outputs, progress)
+                .and_then(|()| {
+                    merge::merge(
+                        &merged_path,
+                        &part_outputs,
+                        Overlap::LaterWins,
+                        options.align,
+                        true,
+                    )
+                });
+            remove_part_outputs(&part_outputs);
+            let (entries, bytes) = merged?;
+            summary.output_paths = vec![merged_path];
+            summary.entries_written = entries;
+            summary.bytes_written = bytes;
+        }
+    }
+    Ok(summary)
+}
+
+/// The part outputs are removed as they're merged, at most the largest one is on the disk twice.
+fn check_merge_space(
+    options: &Options,
+    merged_path: &Path,
+    part_outputs: &[PathBuf],
+    progress: &dyn Progress,
+) -> eyre::Result<()> {
+    if !options.check_free_space {
+        return Ok(());
+    }
+    let largest = part_outputs
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .max()
+        .unwrap_or(0);
+    free_space::check(merged_path, largest, progress)
+}
+
+fn remove_part_outputs(paths: &[PathBuf]) {
+    for path in paths {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(SourceIndex::sidecar_path(path));
+    }
+}
+
+/// Labels the phases of a part with its position in the family.
+struct PartProgress<'a> {
+    inner: &'a dyn Progress,
+    label: String,
+}
+
+impl Progress for PartProgress<'_> {
+    fn info(&self, message: &str) {
+        self.inner.info(message);
+    }
+
+    fn warn(&self, message: &str) {
+        self.inner.warn(message);
+    }
+
+    fn wants_details(&self) -> bool {
+        self.inner.wants_details()
+    }
+
+    fn detail(&self, message: &str) {
+        self.inner.detail(message);
+    }
+
+    fn begin_phase(&self, message: &str, total: Option<u64>) {
+        self.inner
+            .begin_phase(&format!("{} {message}", self.label), total);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.inner.inc(delta);
+    }
+
+    fn update_stats(&self, stats: &StatsSnapshot) {
+        self.inner.update_stats(stats);
+    }
+
+    fn end_phase(&self) {
+        self.inner.end_phase();
+    }
+}
@@ -0,0 +1,482 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    alignment,
+    category::Category,
+    chunk_family::ChunkOutput,
+    compression::OutputCompression,
+    dds::DdsLayers,
+    estimate,
+    i18n::Lang,
+    naming, open_files,
+    pak_header::{self, PakVersion},
+    preset::Preset,
+    progress::Verbosity,
+    retry,
+    selector::EntrySelector,
+    shard::Shard,
+    stdio,
+    storage::StorageKind,
+    tex_format::TexFormat,
+    transcode::{TranscodeFormat, TranscodeQuality},
+    util,
+};
+
+/// Running without a subcommand starts the interactive decompression flow.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(flatten)]
+    pub names: NameTableArgs,
+
+    #[command(flatten)]
+    pub decompress: DecompressArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Only print errors and the final summary, without progress bars.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Print a line for every processed entry.
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+    /// Language of the prompts, defaults to the system language.
+    #[arg(long, global = true, value_enum)]
+    pub lang: Option<Lang>,
+    /// Check GitHub for a newer release before running, and offer to download it.
+    #[arg(long)]
+    pub check_update: bool,
+}
+
+impl Cli {
+    pub fn verbosity(&self) -> Verbosity {
+        // stdout is the pak
+        if self.quiet || self.decompress.writes_stdout() {
+            Verbosity::Quiet
+        } else if self.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct NameTableArgs {
+    /// Use an external file name list (.list.zst) instead of the embedded one.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub name_list: Option<PathBuf>,
+    /// Don't load any file name list, tex files are detected by their content instead.
+    #[arg(long, global = true, conflicts_with_all = ["name_list", "extra_list"])]
+    pub skip_name_table: bool,
+    /// Merge the paths of a plain text `.list` file (one per line) into the file name list,
+    /// e.g. newly discovered names. Can be repeated.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub extra_list: Vec<PathBuf>,
+}
+
+/// Options of the interactive decompression flow that aren't prompted for.
+#[derive(Debug, Clone, Args)]
+pub struct DecompressArgs {
+    /// Input .pak file, skips the input prompt (e.g. when a file is dropped onto the executable).
+    /// `-` reads it from stdin.
+    #[arg(value_name = "PAK")]
+    pub input: Option<PathBuf>,
+    /// Output .pak file, `-` writes it to stdout. Defaults to next to the input.
+    #[arg(short, long, value_name = "PAK")]
+    pub output: Option<PathBuf>,
+    /// File name of the output next to the input from a template, e.g.
+    /// `{input}.patch_042.{transform}.{mode}.pak`. Tokens: `{input}`, `{mode}`, `{transform}`,
+    /// `{filters_hash}` and `{game_build}`.
+    #[arg(long, value_name = "TEMPLATE", value_parser = naming::parse_template, conflicts_with = "output")]
+    pub name_template: Option<String>,
+    /// Don't prompt for anything and don't wait for Enter before exiting, for scripts.
+    /// Options not given on the command line take their defaults, requires the input PAK.
+    #[arg(long, requires = "input")]
+    pub no_pause: bool,
+    /// After writing, re-process a random PERCENT of the entries and compare them with the output.
+    #[arg(long, value_name = "PERCENT", default_value_t = 0.0)]
+    pub verify_sample: f64,
+    /// Seed (hex) picking the entries of `--verify-sample`, to repeat the verification of an
+    /// earlier run. It's printed after verifying and recorded in the manifest.
+    #[arg(long, value_name = "HEX", value_parser = util::parse_hex_u64)]
+    pub verify_seed: Option<u64>,
+    /// Before processing, decompress a random PERCENT of the textures (2 if not given), print the
+    /// output size and runtime extrapolated from them and ask whether to continue.
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        num_args = 0..=1,
+        default_missing_value = "2",
+        value_parser = estimate::parse_sample_percent
+    )]
+    pub estimate: Option<f64>,
+    /// Bundle of options: `fast`, `smallest` output or most `compat`ible with the source. Options
+    /// given on their own win over the preset's.
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+    /// Only include tex files of these categories, skips the category prompt.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub category: Vec<Category>,
+    /// Only include textures in these pixel formats, e.g. `bc7,bc6h`.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub format: Vec<TexFormat>,
+    /// Roll over to a new `*.patch_NNN.pak` part when the output exceeds SIZE (e.g. `20G`).
+    #[arg(long, value_name = "SIZE", value_parser = util::parse_size)]
+    pub split_size: Option<u64>,
+    /// Write an output per texture category (`*.ui.pak`, `*.environment.pak`, ...), to enable
+    /// them separately in a mod manager.
+    #[arg(long)]
+    pub split_by_category: bool,
+    /// Skip tex files larger than SIZE once decompressed (e.g. `64M`).
+    #[arg(long, value_name = "SIZE", value_parser = util::parse_size)]
+    pub skip_larger_than: Option<u64>,
+    /// Skip tex files smaller than SIZE once decompressed (e.g. `16K`).
+    #[arg(long, value_name = "SIZE", value_parser = util::parse_size)]
+    pub skip_smaller_than: Option<u64>,
+    /// Only select entries of at least SIZE as stored in the pak (e.g. `1M`), checked before
+    /// reading them.
+    #[arg(long, value_name = "SIZE", value_parser = util::parse_size)]
+    pub min_size: Option<u64>,
+    /// Only select entries of at most SIZE as stored in the pak, checked before reading them.
+    #[arg(long, value_name = "SIZE", value_parser = util::parse_size)]
+    pub max_size: Option<u64>,
+    /// Discard the N largest mip levels of every texture, for a low-res texture pack.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub drop_top_mips: u8,
+    /// Discard the mip levels whose larger side is below SIZE pixels (e.g. `64`).
+    #[arg(long, value_name = "SIZE", default_value_t = 0)]
+    pub min_mip_size: u16,
+    /// Re-encode BC7 textures to a cheaper block format, BC1 halves their size.
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub transcode: Option<TranscodeFormat>,
+    /// Encoder quality of `--transcode`.
+    #[arg(long, value_enum, default_value_t = TranscodeQuality::Normal)]
+    pub transcode_quality: TranscodeQuality,
+    /// Skip the decoder for textures whose mips are already stored uncompressed, only rewriting
+    /// their header. Much faster on full-package runs.
+    #[arg(long)]
+    pub header_fast_path: bool,
+    /// Keep the zstd/deflate compression of non-tex entries instead of storing them uncompressed.
+    #[arg(long)]
+    pub keep_compression: bool,
+    /// Codec of the output entries: `none`, `deflate` or `zstd` for all entries, or per class
+    /// like `tex=none,other=zstd`. Compressed outputs are smaller but slower to load.
+    #[arg(long, value_name = "CODECS", default_value = "")]
+    pub compression: OutputCompression,
+    /// Write a binary content index (`.cidx`) next to the output for other tools.
+    #[arg(long)]
+    pub content_index: bool,
+    /// Also copy the selected non-tex entries: a path, a directory ending with `/`
+    /// (e.g. `natives/STM/GUI/`), a path glob or a hex hash. Can be repeated.
+    #[arg(long, value_name = "ENTRY")]
+    pub copy_path: Vec<EntrySelector>,
+    /// Write the non-tex entries left out of the output to FILE.
+    #[arg(long, value_name = "FILE")]
+    pub list_non_tex: Option<PathBuf>,
+    /// Write a `.manifest.json` with SHA-256 of the output and CRC32 of every entry.
+    #[arg(long)]
+    pub manifest: bool,
+    /// Sign the manifest with the Ed25519 secret key in FILE (32 bytes in hex), check it with
+    /// `verify-signature`.
+    #[arg(long, value_name = "FILE", requires = "manifest")]
+    pub sign_key: Option<PathBuf>,
+    /// Also write a variant of the output per SIZE (e.g. `4096,2048,1024`) with the textures
+    /// capped to it, in the same pass. For comparing performance and quality in-game.
+    #[arg(long, value_name = "SIZES", value_delimiter = ',')]
+    pub variants: Vec<u16>,
+    /// Embed a small entry with the tool version, source pak and options in the output, so it
+    /// can be identified later with `build-info`.
+    #[arg(long)]
+    pub embed_info: bool,
+    /// Also pack the output into a zip that Fluffy Mod Manager installs in one click.
+    #[arg(long)]
+    pub package_fluffy: bool,
+    /// Mod name shown by the mod manager, defaults to the output file name.
+    #[arg(long, value_name = "NAME", requires = "package_fluffy")]
+    pub mod_name: Option<String>,
+    /// Author shown by the mod manager.
+    #[arg(long, value_name = "NAME", requires = "package_fluffy")]
+    pub mod_author: Option<String>,
+    /// Version shown by the mod manager.
+    #[arg(long, value_name = "VERSION", requires = "package_fluffy")]
+    pub mod_version: Option<String>,
+    /// Description shown by the mod manager.
+    #[arg(long, value_name = "TEXT", requires = "package_fluffy")]
+    pub mod_description: Option<String>,
+    /// Screenshot shown by the mod manager, a PNG or JPEG.
+    #[arg(long, value_name = "FILE", requires = "package_fluffy")]
+    pub mod_screenshot: Option<PathBuf>,
+    /// Only include the N textures with the highest streaming cost (size, format and category),
+    /// for the most stutter reduction per GB of output.
+    #[arg(long, value_name = "N")]
+    pub top_impact: Option<usize>,
+    /// Hand-pick the textures to include in a terminal UI.
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    pub pick: bool,
+    /// Header version of the output pak (e.g. `4.1`), defaults to the source's.
+    #[arg(long, value_name = "MAJOR.MINOR")]
+    pub pak_version: Option<PakVersion>,
+    /// Header feature flags of the output pak in hex, defaults to the source's.
+    #[arg(long, value_name = "HEX", value_parser = pak_header::parse_features)]
+    pub pak_features: Option<u16>,
+    /// Number of entries a dedicated reader thread reads ahead of the workers, 0 to let every
+    /// worker read on its own. Defaults to 4 per thread.
+    #[arg(long, value_name = "N")]
+    pub read_ahead: Option<usize>,
+    /// Memory-map the input pak, workers read entries straight from memory. Fastest on NVMe
+    /// drives, disables the read-ahead thread unless `--read-ahead` is given.
+    #[arg(long, conflicts_with = "storage")]
+    pub mmap: bool,
+    /// How the input pak is read: through file handles, memory-mapped or loaded into memory as
+    /// a whole. `--mmap` is short for `--storage mmap`.
+    #[arg(long, value_enum, value_name = "BACKEND")]
+    pub storage: Option<StorageKind>,
+    /// Read several entries concurrently, each through its own file handle, to keep slow
+    /// external drives busy. `--read-ahead` bounds the entries waiting for a worker.
+    #[cfg(feature = "async-io")]
+    #[arg(long)]
+    pub async_io: bool,
+    /// Report output entries with byte-identical data and how much sharing it would save,
+    /// `--verbose` lists them.
+    #[arg(long)]
+    pub report_duplicates: bool,
+    /// Maximum number of files held open at once, lower it if the run fails with
+    /// "too many open files".
+    #[arg(long, value_name = "N", default_value_t = open_files::DEFAULT_LIMIT)]
+    pub max_open_files: usize,
+    /// Start the data of every output entry at a multiple of SIZE (a power of two like `4K`),
+    /// which can speed up the game's reads of uncompressed textures.
+    #[arg(long, value_name = "SIZE", value_parser = alignment::parse_alignment)]
+    pub align: Option<u64>,
+    /// Write the entries in source order instead of as the workers finish them, so the same
+    /// input and options always give a byte-identical output. Entries finished ahead of their
+    /// turn wait in memory, up to 8 per thread, e.g. a few hundred MB for large textures.
+    #[arg(long)]
+    pub deterministic: bool,
+    /// Try a failed entry read again up to N times, waiting a bit longer each time. Helps with
+    /// network shares and external drives that fail reads now and then.
+    #[arg(long, value_name = "N", default_value_t = retry::DEFAULT_RETRIES)]
+    pub retries: u32,
+    /// Run at a lower priority, so the game and other programs stay responsive.
+    #[arg(long)]
+    pub low_priority: bool,
+    /// Cap the disk bandwidth of entry reads and writes to MB/S megabytes per second.
+    #[arg(long, value_name = "MB/S")]
+    pub io_throttle: Option<u64>,
+    /// Write the hash and size of the entries missing from the file name table to FILE.
+    #[arg(long, value_name = "FILE")]
+    pub list_unknown: Option<PathBuf>,
+    /// Write the names of all input entries to FILE, hashes for the unknown ones.
+    #[arg(long, value_name = "FILE")]
+    pub dump_names: Option<PathBuf>,
+    /// Keep FILE updated with the progress of the run as JSON, for launchers and overlays.
+    #[arg(long, value_name = "FILE")]
+    pub status_file: Option<PathBuf>,
+    /// Run even if the input looks like an output of this tool.
+    #[arg(long)]
+    pub allow_processed_input: bool,
+    /// Don't check that the estimated output size fits on the output drive.
+    #[arg(long)]
+    pub skip_space_check: bool,
+    /// JSON file with per-entry overrides: inclusion, compression and feature flags by path or
+    /// hash.
+    #[arg(long, value_name = "FILE")]
+    pub overrides: Option<PathBuf>,
+    /// Rhai script with a `filter(entry)` and/or `transform(entry, data)` function, called for
+    /// every entry to leave it out or change its data.
+    #[cfg(feature = "scripting")]
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+    /// Overwrite an existing output without asking.
+    #[arg(long)]
+    pub force: bool,
+    /// Only process the K-th of N shares of the entries (e.g. `2/8`), to split a run across
+    /// machines. Combine the outputs with `merge-shards`.
+    #[arg(long, value_name = "K/N")]
+    pub shard: Option<Shard>,
+    /// Process all `.sub_NNN` parts of the input's chunk family in sequence, into one merged
+    /// output or an output per part.
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub chunk_family: Option<ChunkOutput>,
+}
+
+impl DecompressArgs {
+    pub fn reads_stdin(&self) -> bool {
+        self.input.as_deref().is_some_and(stdio::is_stdio)
+    }
+
+    pub fn writes_stdout(&self) -> bool {
+        self.output.as_deref().is_some_and(stdio::is_stdio)
+    }
+
+    pub fn storage(&self) -> StorageKind {
+        match self.storage {
+            Some(kind) => kind,
+            None if self.mmap => StorageKind::Mmap,
+            None => StorageKind::Fs,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Compare two pak files and list added, removed and modified entries.
+    Diff {
+        /// The older pak, e.g. before a game patch.
+        old: PathBuf,
+        /// The newer pak, e.g. after a game patch.
+        new: PathBuf,
+        /// Only report tex files.
+        #[arg(long)]
+        tex_only: bool,
+        /// Also write a markdown report of the changes per texture category to FILE.
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+    /// Decompress an entry of the source pak and check that an output holds the same data, mip by
+    /// mip for textures.
+    Compare {
+        /// The game's pak the output was made from.
+        source: PathBuf,
+        /// The output pak.
+        output: PathBuf,
+        /// Entry path or hex hash, must select a single entry.
+        entry: EntrySelector,
+    },
+    /// Print the entries of a content index (`.cidx`), or only the selected ones.
+    ContentIndex {
+        index: PathBuf,
+        /// Entry path, path glob or hex hash.
+        entry: Option<EntrySelector>,
+    },
+    /// Write the data of a single entry to a file.
+    ExtractOne {
+        pak: PathBuf,
+        /// Entry path or hex hash, must select a single entry.
+        entry: EntrySelector,
+        /// Output file, or a directory to write the file under its own name.
+        out: PathBuf,
+        /// Extract all textures of the entry's family (albedo, normal...) into the OUT directory.
+        #[arg(long)]
+        family: bool,
+    },
+    /// Replace the data of a single entry with a file.
+    ///
+    /// The data is appended to the pak and the entry table patched in place when the table isn't
+    /// encrypted, otherwise the pak is rewritten.
+    ReplaceOne {
+        pak: PathBuf,
+        /// Entry path or hex hash, must select a single entry.
+        entry: EntrySelector,
+        file: PathBuf,
+        /// Replace all textures of the entry's family with the files of the same name in the
+        /// FILE directory.
+        #[arg(long)]
+        family: bool,
+    },
+    /// List the textures of a pak grouped by family (the maps of one material).
+    Families {
+        pak: PathBuf,
+        /// Only list textures matching this path, path glob or hex hash.
+        entry: Option<EntrySelector>,
+    },
+    /// Print the number and size of the textures per format, and optionally a row per texture.
+    Stats {
+        pak: PathBuf,
+        /// Write name, hash, format, dimensions, mips, array size and sizes of every texture to
+        /// FILE as CSV.
+        #[arg(long, value_name = "FILE")]
+        csv: Option<PathBuf>,
+    },
+    /// Print the hash of entry paths and the name of entry hashes.
+    Hashes {
+        /// Full entry paths (the `natives/STM/` root may be left out) or hex hashes.
+        #[arg(required = true)]
+        entries: Vec<EntrySelector>,
+    },
+    /// Check downloaded output paks against their `.manifest.json`.
+    CheckManifest { manifest: PathBuf },
+    /// Check a manifest signed with `--sign-key` against its `.sig` file.
+    VerifySignature {
+        /// The signed file, e.g. a `.manifest.json`.
+        file: PathBuf,
+        /// The public key printed when signing, in hex or a file holding it.
+        #[arg(long, value_name = "KEY")]
+        public_key: String,
+    },
+    /// Print the build info embedded in an output with `--embed-info`.
+    BuildInfo { pak: PathBuf },
+    /// Combine the outputs of `--shard` runs into one pak.
+    MergeShards {
+        /// The outputs of all shards.
+        #[arg(required = true, num_args = 2..)]
+        shards: Vec<PathBuf>,
+        /// The merged pak to write.
+        #[arg(long)]
+        out: PathBuf,
+        /// Overwrite an existing merged pak.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Write PNG thumbnails of the matching textures, to see which asset a path is.
+    Preview {
+        pak: PathBuf,
+        /// Entry path, path glob or hex hash.
+        entry: EntrySelector,
+        /// Directory the PNG files are written to.
+        #[arg(long, default_value = "previews")]
+        out: PathBuf,
+        /// Largest side of the thumbnails, the closest mip level is used.
+        #[arg(long, value_name = "PIXELS", default_value_t = 512)]
+        size: u32,
+    },
+    /// Export a texture as DDS, texture arrays and cubemaps as one layered file or a file per image.
+    ExportDds {
+        pak: PathBuf,
+        /// Entry path or hex hash.
+        entry: EntrySelector,
+        /// The DDS file to write, `--layers split` numbers the files after its name.
+        out: PathBuf,
+        /// Write all images of array and cubemap textures into one DDS, or a DDS per image.
+        #[arg(long, value_enum, default_value_t)]
+        layers: DdsLayers,
+    },
+    /// Pack a directory of loose files into a patch pak, and again whenever one of them changes.
+    Watch {
+        /// Directory mirroring the game's paths, e.g. `mymod/Art/Model/...`.
+        dir: PathBuf,
+        /// The patch pak to write, e.g. `re_chunk_000.pak.sub_000.pak.patch_010.pak`.
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Time decompression of sampled tex entries with different thread counts and read-ahead
+    /// depths, and save the fastest settings as defaults.
+    Bench {
+        pak: PathBuf,
+        /// Number of entries decompressed per setting.
+        #[arg(long, value_name = "N", default_value_t = 200)]
+        sample: usize,
+        /// Only print the recommendation, don't save it.
+        #[arg(long)]
+        no_save: bool,
+    },
+    /// Regenerate the output described by a `.manifest.json` from the game's pak.
+    Rebuild {
+        manifest: PathBuf,
+        /// The source pak the output was made from.
+        pak: PathBuf,
+        /// Overwrite an existing output.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Show the changelog and migration notes.
+    Changelog,
+    /// Open the graphical interface.
+    #[cfg(feature = "gui")]
+    Gui,
+}
@@ -0,0 +1,174 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use indicatif::HumanBytes;
+use rayon::{
+    ThreadPool, ThreadPoolBuilder,
+    iter::{IntoParallelRefIterator, ParallelIterator},
+};
+use ree_pak_core::pak::PakEntry;
+
+use crate::{
+    config::Config,
+    handler,
+    names::NameTable,
+    pak::PakFile,
+    process::{TexTransform, process_entry},
+    read_ahead,
+    util::{self, SplitMix64},
+};
+
+/// Read-ahead depths per thread tried, 0 lets every worker read on its own.
+const READ_AHEAD_PER_THREAD: [usize; 4] = [0, 2, 4, 8];
+/// Fewer threads win unless more are faster by this factor, they leave the machine usable.
+const SIGNIFICANT_SPEEDUP: f64 = 1.05;
+
+/// Decompresses samples of the pak's tex entries with different settings and saves the fastest
+/// ones to the config.
+pub fn run(pak_path: &Path, sample: usize, save: bool, names: &NameTable) -> eyre::Result<()> {
+    if sample == 0 {
+        eyre::bail!("`--sample` must be at least 1.");
+    }
+    let pak = PakFile::open(pak_path)?;
+    let mut candidates: Vec<&PakEntry> = pak
+        .entries()
+        .iter()
+        // unknown names are told apart by content when decompressing
+        .filter(|entry| names.is_tex_file(entry.hash()) != Some(false))
+        .collect();
+    if candidates.is_empty() {
+        eyre::bail!("the pak has no tex entries to benchmark with.");
+    }
+
+    // one sample for the thread counts and a fresh one per read-ahead depth, so those read
+    // entries that aren't in the OS cache yet
+    let mut rng = SplitMix64::new(SplitMix64::time_seed());
+    let sample_count = (sample * (READ_AHEAD_PER_THREAD.len() + 1)).min(candidates.len());
+    for i in 0..sample_count {
+        let j = i + (rng.next_u64() % (candidates.len() - i) as u64) as usize;
+        candidates.swap(i, j);
+    }
+    let samples: Vec<&[&PakEntry]> = candidates[..sample_count].chunks(sample).collect();
+
+    println!("Reading {} sampled entries...", samples[0].len());
+    let raws = samples[0]
+        .iter()
+        .map(|entry| Ok((entry.hash(), pak.read_entry(entry)?)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    println!("{}", "Decompression by thread count:".cyan().bold());
+    let mut best: Option<(usize, f64)> = None;
+    for threads in thread_counts() {
+        let pool = thread_pool(threads)?;
+        let (bytes, elapsed) = pool.install(|| -> eyre::Result<(u64, Duration)> {
+            let started = Instant::now();
+            let bytes = raws
+                .par_iter()
+                .map(|(hash, raw)| {
+                    let handler = handler::find(names, *hash, raw);
+                    process_entry(raw.clone(), handler, &TexTransform::default())
+                        .map(|data| data.len() as u64)
+                })
+                .sum::<eyre::Result<u64>>()?;
+            Ok((bytes, started.elapsed()))
+        })?;
+        let rate = bytes as f64 / elapsed.as_secs_f64();
+        println!("  {threads:>3} threads: {}/s", HumanBytes(rate as u64));
+        if best.is_none_or(|(_, best_rate)| rate > best_rate * SIGNIFICANT_SPEEDUP) {
+            best = Some((threads, rate));
+        }
+    }
+    let (thread_count, _) = best.expect("at least one thread count is tried");
+
+    println!(
+        "{}",
+        format!("Reading and decompressing with {thread_count} threads, by read-ahead:")
+            .cyan()
+            .bold()
+    );
+    let pool = thread_pool(thread_count)?;
+    let mut best: Option<(usize, f64)> = None;
+    for (per_thread, entries) in READ_AHEAD_PER_THREAD.iter().zip(&samples[1..]) {
+        let depth = per_thread * thread_count;
+        let started = Instant::now();
+        let bytes = AtomicU64::new(0);
+        let process = |entry: &PakEntry, raw: Vec<u8>| -> eyre::Result<()> {
+            let handler = handler::find(names, entry.hash(), &raw);
+            let data = process_entry(raw, handler, &TexTransform::default())?;
+            bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+            Ok(())
+        };
+        pool.install(|| {
+            if depth == 0 {
+                entries
+                    .par_iter()
+                    .try_for_each(|&entry| process(entry, pak.read_entry(entry)?))
+            } else {
+                read_ahead::try_for_each(
+                    &pak,
+                    entries,
+                    depth,
+                    None,
+                    |_| true,
+                    |_, entry, raw| {
+                        let raw = raw?.expect("every entry is prefetched");
+                        process(entry, raw)
+                    },
+                )
+            }
+        })?;
+        let rate = bytes.into_inner() as f64 / started.elapsed().as_secs_f64();
+        println!("  {depth:>3} entries: {}/s", HumanBytes(rate as u64));
+        if best.is_none_or(|(_, best_rate)| rate > best_rate) {
+            best = Some((depth, rate));
+        }
+    }
+    // too few sampled entries to try read-ahead, keep the default
+    let read_ahead = best.map(|(depth, _)| depth);
+
+    let config = Config {
+        thread_count: Some(thread_count),
+        read_ahead,
+    };
+    println!(
+        "Recommended: {thread_count} threads{}.",
+        read_ahead
+            .map(|depth| format!(", read-ahead {depth}"))
+            .unwrap_or_default()
+    );
+    if save {
+        let path = config.save()?;
+        println!(
+            "{}",
+            format!(
+                "Saved to {}, used when no other value is given.",
+                util::display_path(&path)
+            )
+            .green()
+            .bold()
+        );
+    }
+    Ok(())
+}
+
+/// 1, 2, 4, ... up to and including the number of CPUs.
+fn thread_counts() -> Vec<usize> {
+    let cpus = num_cpus::get();
+    let mut counts: Vec<usize> = (0..)
+        .map(|shift| 1 << shift)
+        .take_while(|&threads| threads < cpus)
+        .collect();
+    counts.push(cpus);
+    counts
+}
+
+fn thread_pool(threads: usize) -> eyre::Result<ThreadPool> {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| eyre::eyre!("Failed to configure thread pool: {}", e))
+}
@@ -0,0 +1,15 @@
+use std::path::Path;
+
+use crate::{build_info, pak::PakFile, util};
+
+pub fn run(pak_path: &Path) -> eyre::Result<()> {
+    let pak = PakFile::open(pak_path)?;
+    match build_info::read(&pak)? {
+        Some(info) => println!("{}", serde_json::to_string_pretty(&info)?),
+        None => eyre::bail!(
+            "`{}` has no build info, it wasn't written with `--embed-info`.",
+            util::display_path(pak_path)
+        ),
+    }
+    Ok(())
+}
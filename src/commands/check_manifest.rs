@@ -0,0 +1,20 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::manifest;
+
+pub fn run(manifest_path: &Path) -> eyre::Result<()> {
+    let failed = manifest::check(manifest_path)?;
+    if !failed.is_empty() {
+        for file in &failed {
+            eprintln!("{} {file}", "mismatch:".red().bold());
+        }
+        eyre::bail!(
+            "{} files are missing or don't match the manifest.",
+            failed.len()
+        );
+    }
+    println!("{}", "All files match the manifest.".green().bold());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use dialoguer::{Confirm, theme::ColorfulTheme};
+
+use crate::{i18n::Msg, update, util};
+
+/// Tells about a newer release and, if `interactive`, offers to download it.
+pub fn run(interactive: bool) -> eyre::Result<()> {
+    let spinner = util::spinner("Checking for updates...");
+    let release = update::latest_release();
+    spinner.finish_and_clear();
+    let release = release?;
+    if !release.is_newer() {
+        println!(
+            "{}",
+            Msg::UpToDate.with(&[("version", env!("CARGO_PKG_VERSION"))])
+        );
+        return Ok(());
+    }
+    println!(
+        "{}",
+        Msg::UpdateAvailable.with(&[("tag", &release.tag), ("url", &release.page_url)])
+    );
+    // the release page is all we have for platforms without a download
+    let Some(asset) = release.asset_for_platform() else {
+        return Ok(());
+    };
+    if !interactive {
+        return Ok(());
+    }
+    let download = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::DownloadUpdate.with(&[("name", &asset.name)]))
+        .default(true)
+        .interact()
+        .unwrap();
+    if !download {
+        return Ok(());
+    }
+
+    let exe_path = std::env::current_exe()?;
+    let dir = exe_path.parent().unwrap_or(Path::new("."));
+    let spinner = util::spinner(format!("Downloading {}...", asset.name));
+    let path = update::download(&release, asset, dir);
+    spinner.finish_and_clear();
+    println!(
+        "{}",
+        Msg::UpdateDownloaded.with(&[("path", &util::display_path(&path?))])
+    );
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::{
+    handler,
+    names::NameTable,
+    pak::PakFile,
+    process::{self, TexTransform},
+    selector::{self, EntrySelector},
+    tex_header::{TEX_MAGIC, TexHeader},
+};
+
+/// Decompresses the selected entry of the source pak and compares it with the same entry of an
+/// output, mip by mip for textures. Fails if any data differs.
+pub fn run(
+    source_path: &Path,
+    output_path: &Path,
+    selector: &EntrySelector,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    let source = PakFile::open(source_path)?;
+    let output = PakFile::open(output_path)?;
+    let source_entry = selector::find_one(selector, source.entries(), names)?;
+    let name = names.display_name(source_entry.hash());
+    let Some(output_entry) = output
+        .entries()
+        .iter()
+        .find(|entry| entry.hash() == source_entry.hash())
+    else {
+        eyre::bail!("{name} isn't in the output.");
+    };
+
+    let raw = source.read_entry(source_entry)?;
+    let is_tex = process::is_tex_entry(names, source_entry.hash(), &raw);
+    let handler = handler::find(names, source_entry.hash(), &raw);
+    // always the decoder, the header fast path is one of the things checked here
+    let expected = process::process_entry(raw, handler, &TexTransform::default())?;
+    let actual = output.read_entry(output_entry)?;
+    println!("{name}");
+
+    let differences = if is_tex && expected.starts_with(TEX_MAGIC) {
+        compare_tex(&expected, &actual)?
+    } else {
+        usize::from(!report("data", &expected, &actual))
+    };
+    if differences > 0 {
+        eyre::bail!("{differences} parts of the output differ from the source.");
+    }
+    println!(
+        "{}",
+        "The output matches the decompressed source bit for bit."
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+/// Compares the mips of two uncompressed tex files, returns the number of differing ones.
+/// Top mips dropped from the output are skipped.
+fn compare_tex(expected: &[u8], actual: &[u8]) -> eyre::Result<usize> {
+    let source = TexHeader::parse(expected)?;
+    let output = TexHeader::parse(actual)?;
+    if output.format != source.format {
+        eyre::bail!(
+            "the output is transcoded from DXGI format {} to {}, its pixel data can't match.",
+            source.format,
+            output.format
+        );
+    }
+    if output.image_count != source.image_count {
+        eyre::bail!(
+            "the output has {} images, the source {}.",
+            output.image_count,
+            source.image_count
+        );
+    }
+    // `--drop-top-mips` and the like shrink the output, its first mip is a smaller source mip
+    let Some(dropped) = (0..source.mip_count as u32).find(|&mip| {
+        (source.width >> mip).max(1) == output.width
+            && (source.height >> mip).max(1) == output.height
+    }) else {
+        eyre::bail!(
+            "the output is {}x{}, which isn't a mip size of the {}x{} source.",
+            output.width,
+            output.height,
+            source.width,
+            source.height
+        );
+    };
+    if dropped > 0 {
+        println!("  the output leaves out the {dropped} largest mips");
+    }
+
+    let mut differences = 0;
+    for image in 0..source.image_count as usize {
+        for mip in 0..output.mip_count as usize {
+            let source_mip = mip + dropped as usize;
+            let label = if source.image_count > 1 {
+                format!("image {image} mip {source_mip}")
+            } else {
+                format!("mip {source_mip}")
+            };
+            if source_mip >= source.mip_count as usize {
+                println!("  {label}: {}", "not in the source".red());
+                differences += 1;
+                continue;
+            }
+            let expected =
+                source.mip_data(expected, image * source.mip_count as usize + source_mip)?;
+            let actual = output.mip_data(actual, image * output.mip_count as usize + mip)?;
+            differences += usize::from(!report(&label, expected, actual));
+        }
+    }
+    Ok(differences)
+}
+
+/// Prints whether `actual` matches `expected`, with the first differing byte if not.
+fn report(label: &str, expected: &[u8], actual: &[u8]) -> bool {
+    if expected == actual {
+        println!(
+            "  {label}: {} ({} bytes)",
+            "identical".green(),
+            expected.len()
+        );
+        return true;
+    }
+    let first = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(expected.len().min(actual.len()));
+    let differing = expected
+        .iter()
+        .zip(actual)
+        .filter(|(expected, actual)| expected != actual)
+        .count()
+        + expected.len().abs_diff(actual.len());
+    println!(
+        "  {label}: {} ({differing} bytes differ, first at {first:#x}, {} vs {} bytes)",
+        "different".red(),
+        expected.len(),
+        actual.len()
+    );
+    false
+}
@@ -0,0 +1,45 @@
+use std::{path::Path, slice};
+
+use indicatif::HumanBytes;
+
+use crate::{
+    content_index::{ContentIndex, IndexEntry},
+    names::NameTable,
+    selector::{self, EntrySelector},
+};
+
+pub fn run(
+    index_path: &Path,
+    selector: Option<&EntrySelector>,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    selector::check_names(selector.map(slice::from_ref).unwrap_or_default(), names)?;
+    let index = ContentIndex::read(index_path)?;
+    let print_entry = |entry: &IndexEntry| {
+        println!(
+            "{:016x} {:>12} {:>10} {:<5} {}",
+            entry.hash,
+            entry.offset,
+            HumanBytes(entry.size).to_string(),
+            entry.kind.label(),
+            names.display_name(entry.hash)
+        );
+    };
+
+    match selector {
+        Some(EntrySelector::Hash(hash)) => match index.get(*hash) {
+            Some(entry) => print_entry(entry),
+            None => eyre::bail!("no entry with hash {hash:016X} in the content index."),
+        },
+        _ => {
+            let entries: Vec<&IndexEntry> = index
+                .entries
+                .iter()
+                .filter(|entry| selector.is_none_or(|selector| selector.matches(entry.hash, names)))
+                .collect();
+            entries.iter().for_each(|entry| print_entry(entry));
+            println!("{} entries.", entries.len());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,591 @@
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
+
+use colored::Colorize;
+use dialoguer::{Confirm, Input, MultiSelect, Select, theme::ColorfulTheme};
+use indicatif::HumanBytes;
+
+use crate::{
+    cancel::{self, Cancelled},
+    category::Category,
+    chunk_family::{self, ChunkOutput},
+    cli::{DecompressArgs, NameTableArgs},
+    config::Config,
+    diagnostics,
+    exit_code::{ExitCode, InputNotFound},
+    fluffy::ModInfo,
+    free_space::NotEnoughSpace,
+    i18n::Msg,
+    incremental::SourceIndex,
+    input_hints, names, output_location,
+    pipeline::{self, Options, Summary},
+    preset::Preset,
+    process::TexTransform,
+    progress::{ConsoleProgress, Progress, Verbosity},
+    status_file::StatusFile,
+    stdio, steam, util,
+};
+
+/// Returns [`ExitCode::PartialFailure`] if processing stopped early, [`ExitCode::Cancelled`] if it
+/// was cancelled with Ctrl+C.
+pub fn run(
+    args: &DecompressArgs,
+    name_args: &NameTableArgs,
+    verbosity: Verbosity,
+) -> eyre::Result<ExitCode> {
+    check_stdio(args)?;
+    check_chunk_family(args)?;
+    let mut args = args.clone();
+    if let Some(preset) = args.preset {
+        preset.apply(&mut args);
+    }
+    let args = &args;
+    let mut options = prompt_options(args)?;
+    if args.chunk_family == Some(ChunkOutput::Merged)
+        && options.output.is_none()
+        && options.name_template.is_none()
+    {
+        options.output = Some(chunk_family::merged_path(&options.input_path));
+    }
+    // without anyone to ask, the pipeline refuses and points to `--force`
+    let can_ask = !args.no_pause && !args.reads_stdin() && io::stdin().is_terminal();
+    if options.overwrites_output() && !options.force && can_ask {
+        options.force = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(
+                Msg::OverwriteOutput.with(&[("path", &util::display_path(&options.output_path()))]),
+            )
+            .default(true)
+            .interact()?;
+    }
+
+    let progress = ConsoleProgress::new(verbosity);
+    let name_table = names::load_table(name_args, &progress)?;
+    #[cfg(feature = "async-io")]
+    let options = Options {
+        async_io: args.async_io,
+        ..options
+    };
+    #[cfg(feature = "scripting")]
+    let options = Options {
+        script: args.script.clone(),
+        ..options
+    };
+    #[cfg(feature = "tui")]
+    let options = if args.pick {
+        Options {
+            picked_entries: Some(pick_entries(&options, &name_table)?),
+            ..options
+        }
+    } else {
+        options
+    };
+    // installed after the prompts, where Ctrl+C should still quit right away
+    cancel::install_ctrlc_handler()?;
+    let status_file = args
+        .status_file
+        .as_deref()
+        .map(|path| StatusFile::new(path, &progress, &name_table));
+    let run_progress: &dyn Progress = match &status_file {
+        Some(status_file) => status_file,
+        None => &progress,
+    };
+    let (options, summary) =
+        run_until_output_fits(args, options, |options| match args.chunk_family {
+            Some(mode) => chunk_family::run(options, mode, &name_table, run_progress),
+            None => pipeline::run(options, &name_table, run_progress),
+        });
+    if let Some(status_file) = &status_file {
+        status_file.finish(&summary);
+    }
+    // the temp files standing in for the streams
+    if args.reads_stdin() {
+        let _ = fs::remove_file(&options.input_path);
+    }
+    if args.writes_stdout() {
+        let _ = fs::remove_file(SourceIndex::sidecar_path(&options.output_path()));
+        match &summary {
+            Ok(_) => stdio::stream_to_stdout(&options.output_path())?,
+            Err(_) => {
+                let _ = fs::remove_file(options.output_path());
+            }
+        }
+    }
+    let summary = match summary {
+        Ok(summary) => summary,
+        Err(e) => {
+            offer_diagnostics(args, &options, &e, &progress);
+            return Err(e);
+        }
+    };
+
+    let cancelled = summary
+        .processing_error
+        .as_ref()
+        .is_some_and(|e| e.downcast_ref::<Cancelled>().is_some());
+    if cancelled {
+        eprintln!(
+            "Cancelled, the output holds the entries processed so far. To continue where it stopped, run again and enter `{}` as the previous output.",
+            util::display_path(&options.output_path())
+        );
+    } else if let Some(e) = &summary.processing_error {
+        eprintln!("Error occurred when processing tex: {e:#}");
+        eprintln!(
+            "The process terminated early, we'll save the current processed tex files to pak file."
+        );
+        offer_diagnostics(args, &options, e, &progress);
+    }
+    let exit_code = if cancelled {
+        ExitCode::Cancelled
+    } else if summary.processing_error.is_some() {
+        ExitCode::PartialFailure
+    } else {
+        ExitCode::Success
+    };
+    let wrote = Msg::Wrote.with(&[
+        ("count", &summary.entries_written.to_string()),
+        ("size", &HumanBytes(summary.bytes_written).to_string()),
+    ]);
+    if args.writes_stdout() {
+        eprintln!("{wrote}");
+        return Ok(exit_code);
+    }
+    println!("{wrote}");
+
+    if summary.output_paths.len() > 1 {
+        println!(
+            "{}",
+            Msg::SplitInto.with(&[("count", &summary.output_paths.len().to_string())])
+        );
+        for path in &summary.output_paths {
+            println!("  {}", util::display_path(path));
+        }
+    }
+    println!("{}", Msg::Done.text().cyan().bold());
+    if !options.full_package && verbosity != Verbosity::Quiet {
+        println!("{}", Msg::RenameHint.text());
+    }
+    Ok(exit_code)
+}
+
+/// Asks whether to write the diagnostics bundle of a failed run, only in interactive runs.
+fn offer_diagnostics(
+    args: &DecompressArgs,
+    options: &Options,
+    error: &eyre::Report,
+    progress: &ConsoleProgress,
+) {
+    if args.no_pause || args.reads_stdin() || args.writes_stdout() || !io::stdin().is_terminal() {
+        return;
+    }
+    // e.g. declined after `--estimate`, nothing to report
+    if error.downcast_ref::<Cancelled>().is_some() {
+        return;
+    }
+    let path = diagnostics::path(&options.output_path());
+    let write = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::WriteDiagnostics.with(&[("path", &util::display_path(&path))]))
+        .default(true)
+        .interact()
+        .unwrap();
+    if !write {
+        return;
+    }
+    match diagnostics::write(&path, error, &options.input_path, &progress.log()) {
+        Ok(()) => println!(
+            "{}",
+            Msg::DiagnosticsWritten.with(&[("path", &util::display_path(&path))])
+        ),
+        Err(e) => eprintln!("Failed to write {}: {e}", util::display_path(&path)),
+    }
+}
+
+/// Runs again with the output in the folder the user picks as long as it doesn't fit on its drive.
+/// The output size is only estimated once the entries are selected, so it's asked for after.
+fn run_until_output_fits(
+    args: &DecompressArgs,
+    mut options: Options,
+    run: impl Fn(&Options) -> eyre::Result<Summary>,
+) -> (Options, eyre::Result<Summary>) {
+    loop {
+        let summary = run(&options);
+        match summary
+            .as_ref()
+            .err()
+            .and_then(|e| prompt_other_output(args, &options, e))
+        {
+            Some(output) => options.output = Some(output),
+            None => return (options, summary),
+        }
+    }
+}
+
+/// Asks for a folder on another drive after the output didn't fit, only in interactive runs.
+/// `None` to give up.
+fn prompt_other_output(
+    args: &DecompressArgs,
+    options: &Options,
+    error: &eyre::Report,
+) -> Option<PathBuf> {
+    let not_enough_space = error.downcast_ref::<NotEnoughSpace>()?;
+    if args.no_pause || args.reads_stdin() || args.writes_stdout() || !io::stdin().is_terminal() {
+        return None;
+    }
+    let dir: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::OtherOutputFolder.with(&[
+            ("needed", &HumanBytes(not_enough_space.needed).to_string()),
+            (
+                "available",
+                &HumanBytes(not_enough_space.available).to_string(),
+            ),
+        ]))
+        .allow_empty(true)
+        .interact_text()
+        .unwrap();
+    if dir.trim().is_empty() {
+        return None;
+    }
+    let file_name = options.output_path().file_name()?.to_owned();
+    Some(util::long_path(&util::user_path(&dir)).join(file_name))
+}
+
+/// Fails for options that don't work with `--chunk-family`.
+fn check_chunk_family(args: &DecompressArgs) -> eyre::Result<()> {
+    let Some(mode) = args.chunk_family else {
+        return Ok(());
+    };
+    if args.reads_stdin() || args.writes_stdout() {
+        eyre::bail!(
+            "`--chunk-family` reads the parts next to the input, it can't be combined with stdin or stdout."
+        );
+    }
+    if mode == ChunkOutput::Parts {
+        return Ok(());
+    }
+    let conflicts = [
+        ("--split-size", args.split_size.is_some()),
+        ("--split-by-category", args.split_by_category),
+        ("--manifest", args.manifest),
+        ("--content-index", args.content_index),
+        ("--variants", !args.variants.is_empty()),
+        ("--package-fluffy", args.package_fluffy),
+    ];
+    match conflicts.iter().find(|(_, given)| *given) {
+        Some((option, _)) => eyre::bail!(
+            "`--chunk-family merged` writes the output by merging the parts, it can't be combined with `{option}`, use `--chunk-family parts`."
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Fails for options that don't work with the input or output streamed.
+fn check_stdio(args: &DecompressArgs) -> eyre::Result<()> {
+    if args.reads_stdin() && args.output.is_none() {
+        eyre::bail!("reading the input from stdin requires `--output`.");
+    }
+    if !args.writes_stdout() {
+        return Ok(());
+    }
+    let conflicts = [
+        ("--split-size", args.split_size.is_some()),
+        ("--split-by-category", args.split_by_category),
+        ("--manifest", args.manifest),
+        ("--content-index", args.content_index),
+        ("--variants", !args.variants.is_empty()),
+        ("--package-fluffy", args.package_fluffy),
+    ];
+    match conflicts.iter().find(|(_, given)| *given) {
+        Some((option, _)) => eyre::bail!(
+            "`--output -` writes a single pak to stdout, it can't be combined with `{option}`."
+        ),
+        None => Ok(()),
+    }
+}
+
+fn prompt_options(args: &DecompressArgs) -> eyre::Result<Options> {
+    let input_path = match &args.input {
+        Some(input) if stdio::is_stdio(input) => stdio::spool_stdin()?,
+        Some(input) => {
+            if !args.writes_stdout() {
+                println!(
+                    "{}",
+                    Msg::InputFile.with(&[("path", &util::display_path(input))])
+                );
+            }
+            util::long_path(input)
+        }
+        None => match select_detected_pak() {
+            Some(path) => util::long_path(&path),
+            None => {
+                let input: String = Input::with_theme(&ColorfulTheme::default())
+                    .show_default(true)
+                    .default("re_chunk_000.pak.sub_000.pak".to_string())
+                    .with_prompt(Msg::InputPakPath.text())
+                    .interact_text()
+                    .unwrap();
+                util::long_path(&util::user_path(&input))
+            }
+        },
+    };
+    if !input_path.is_file() {
+        return Err(InputNotFound::new("input file", &input_path)
+            .with_hints(input_hints::hints(&input_path))
+            .into());
+    }
+
+    // with a stream taken by the pak, nobody is there to answer
+    if args.no_pause || args.reads_stdin() || args.writes_stdout() {
+        return Ok(default_options(args, input_path));
+    }
+    // the most common reason to run the tool, one key away for users who don't know the options
+    if args.preset.is_none() && args.category.is_empty() {
+        let ui_only = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(Msg::UiTexturesOnly.text())
+            .default(false)
+            .interact()
+            .unwrap();
+        if ui_only {
+            let mut args = args.clone();
+            Preset::Ui.apply(&mut args);
+            return Ok(default_options(&args, input_path));
+        }
+    }
+    // launched by dropping a file onto the executable, don't make the user answer everything
+    if args.input.is_some() {
+        let use_defaults = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(Msg::UseDefaultOptions.text())
+            .default(true)
+            .interact()
+            .unwrap();
+        if use_defaults {
+            return Ok(default_options(args, input_path));
+        }
+    }
+
+    let false_true_selection = [Msg::False.text(), Msg::True.text()];
+
+    let use_full_package_mode = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::FullPackage.text())
+        .default(preset_full_package(args) as usize)
+        .items(&false_true_selection)
+        .interact()
+        .unwrap();
+    let use_full_package_mode = use_full_package_mode == 1;
+
+    let use_feature_clone = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::CloneFeatureFlags.text())
+        .default(1)
+        .items(&false_true_selection)
+        .interact()
+        .unwrap();
+    let use_feature_clone = use_feature_clone == 1;
+
+    let categories = if use_full_package_mode {
+        Category::ALL.to_vec()
+    } else if !args.category.is_empty() {
+        args.category.clone()
+    } else {
+        let labels: Vec<&str> = Category::ALL.iter().map(|c| c.label()).collect();
+        let selected = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(Msg::Categories.text())
+            .items(&labels)
+            .defaults(&[true; Category::ALL.len()])
+            .interact()
+            .unwrap();
+        selected.into_iter().map(|i| Category::ALL[i]).collect()
+    };
+
+    let previous_output: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::PreviousOutput.text())
+        .allow_empty(true)
+        .interact_text()
+        .unwrap();
+    let previous_output = (!previous_output.trim().is_empty())
+        .then(|| util::long_path(&util::user_path(&previous_output)));
+
+    // Get thread count from user
+    let config = Config::load();
+    let default_threads = config.default_thread_count();
+    let thread_count: usize = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::ThreadCount.with(&[("count", &default_threads.to_string())]))
+        .default(default_threads)
+        .interact()
+        .unwrap();
+
+    // everything not prompted for is taken from the command line, as without prompts
+    let mut options = default_options(args, input_path);
+    options.full_package = use_full_package_mode;
+    options.feature_clone = use_feature_clone;
+    options.categories = categories;
+    options.previous_output = previous_output;
+    options.thread_count = thread_count;
+    Ok(options)
+}
+
+/// Lets the user hand-pick the tex entries of the input.
+#[cfg(feature = "tui")]
+fn pick_entries(
+    options: &Options,
+    name_table: &names::NameTable,
+) -> eyre::Result<std::collections::HashSet<u64>> {
+    if !name_table.is_loaded() {
+        eyre::bail!("picking textures requires a file name table.");
+    }
+    let archive = util::read_archive_file(&options.input_path)?;
+    let entries = archive
+        .entries()
+        .iter()
+        .filter(|entry| name_table.is_tex_file(entry.hash()) == Some(true))
+        .map(|entry| {
+            (
+                entry.hash(),
+                name_table.display_name(entry.hash()).into_owned(),
+            )
+        })
+        .collect();
+    match crate::picker::pick(entries)? {
+        Some(picked) if !picked.is_empty() => {
+            println!("Picked {} textures.", picked.len());
+            Ok(picked)
+        }
+        _ => eyre::bail!("no textures picked."),
+    }
+}
+
+/// Offers the chunk paks of the detected game install, `None` to type the path instead.
+fn select_detected_pak() -> Option<PathBuf> {
+    let paks = steam::find_chunk_paks();
+    if paks.is_empty() {
+        return None;
+    }
+
+    let mut items: Vec<String> = paks
+        .iter()
+        .map(|path| match output_location::concern(path) {
+            Some(concern) => format!(
+                "{} ({})",
+                util::display_path(path),
+                Msg::OutputMayFail.with(&[("concern", concern.label())])
+            ),
+            None => util::display_path(path),
+        })
+        .collect();
+    items.push(Msg::EnterPathManually.text().to_string());
+    let selected = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::SelectDetectedPak.text())
+        .default(0)
+        .items(&items)
+        .interact()
+        .unwrap();
+    paks.into_iter().nth(selected)
+}
+
+fn default_options(args: &DecompressArgs, input_path: PathBuf) -> Options {
+    let config = Config::load();
+    Options {
+        input_path,
+        output: output_path(args),
+        name_template: args.name_template.clone(),
+        full_package: preset_full_package(args),
+        feature_clone: true,
+        keep_compression: args.keep_compression,
+        compression: args.compression,
+        categories: if preset_full_package(args) || args.category.is_empty() {
+            Category::ALL.to_vec()
+        } else {
+            args.category.clone()
+        },
+        formats: args.format.clone(),
+        previous_output: None,
+        thread_count: config.default_thread_count(),
+        split_size: args.split_size,
+        split_by_category: args.split_by_category,
+        verify_sample: args.verify_sample,
+        verify_seed: args.verify_seed,
+        estimate: args.estimate,
+        confirm_estimate: !args.no_pause
+            && !args.reads_stdin()
+            && !args.writes_stdout()
+            && io::stdin().is_terminal(),
+        skip_larger_than: args.skip_larger_than,
+        skip_smaller_than: args.skip_smaller_than,
+        min_entry_size: args.min_size,
+        max_entry_size: args.max_size,
+        tex_transform: tex_transform(args),
+        content_index: args.content_index,
+        copy_paths: args.copy_path.clone(),
+        non_tex_list: args.list_non_tex.clone(),
+        manifest: args.manifest,
+        sign_key: args.sign_key.clone(),
+        variants: args.variants.clone(),
+        embed_info: args.embed_info,
+        top_impact: args.top_impact,
+        pak_version: args.pak_version,
+        pak_features: args.pak_features,
+        read_ahead: configured_read_ahead(args, &config),
+        storage: args.storage(),
+        async_io: false,
+        report_duplicates: args.report_duplicates,
+        max_open_files: args.max_open_files,
+        align: args.align,
+        deterministic: args.deterministic,
+        retries: args.retries,
+        low_priority: args.low_priority,
+        io_throttle: args.io_throttle.map(|mb| mb.saturating_mul(1024 * 1024)),
+        unknown_list: args.list_unknown.clone(),
+        names_dump: args.dump_names.clone(),
+        allow_processed_input: args.allow_processed_input,
+        check_free_space: !args.skip_space_check,
+        overrides: args.overrides.clone(),
+        script: None,
+        force: args.force,
+        picked_entries: None,
+        only_entries: None,
+        shard: args.shard,
+        fluffy: fluffy_info(args),
+    }
+}
+
+fn preset_full_package(args: &DecompressArgs) -> bool {
+    args.preset.is_some_and(|preset| preset.full_package())
+}
+
+/// The `--output`, for `-` a temp file that is streamed to stdout when complete.
+fn output_path(args: &DecompressArgs) -> Option<PathBuf> {
+    args.output.as_deref().map(|output| {
+        if stdio::is_stdio(output) {
+            stdio::temp_path("stdout")
+        } else {
+            util::long_path(output)
+        }
+    })
+}
+
+/// Read-ahead from the command line, or else the one `bench` found fastest. A mapped pak is
+/// read without read-ahead unless asked for.
+fn configured_read_ahead(args: &DecompressArgs, config: &Config) -> Option<usize> {
+    args.read_ahead
+        .or(config.read_ahead.filter(|_| !args.storage().is_in_memory()))
+}
+
+fn fluffy_info(args: &DecompressArgs) -> Option<ModInfo> {
+    args.package_fluffy.then(|| ModInfo {
+        name: args.mod_name.clone().unwrap_or_default(),
+        author: args.mod_author.clone(),
+        version: args.mod_version.clone(),
+        description: args.mod_description.clone(),
+        screenshot: args.mod_screenshot.clone(),
+    })
+}
+
+fn tex_transform(args: &DecompressArgs) -> TexTransform {
+    TexTransform {
+        drop_top_mips: args.drop_top_mips,
+        min_mip_size: args.min_mip_size,
+        transcode: args.transcode,
+        transcode_quality: args.transcode_quality,
+        header_fast_path: args.header_fast_path,
+    }
+}
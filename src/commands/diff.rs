@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use colored::Colorize;
+use indicatif::HumanBytes;
+use ree_pak_core::pak::PakEntry;
+
+use crate::{category::Category, names::NameTable, util};
+
+pub fn run(
+    old_path: &Path,
+    new_path: &Path,
+    tex_only: bool,
+    report_path: Option<&Path>,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    if tex_only && !names.is_loaded() {
+        eyre::bail!("`--tex-only` requires a file name table.");
+    }
+    if report_path.is_some() && !names.is_loaded() {
+        eyre::bail!("`--report` requires a file name table to sort entries into categories.");
+    }
+
+    println!("Reading pak archives...");
+    let old_archive = util::read_archive_file(old_path)?;
+    let new_archive = util::read_archive_file(new_path)?;
+
+    let collect_entries = |entries: &'_ [PakEntry]| -> HashMap<u64, PakEntry> {
+        entries
+            .iter()
+            .filter(|entry| !tex_only || names.is_tex_file(entry.hash()) == Some(true))
+            .map(|entry| (entry.hash(), entry.clone()))
+            .collect()
+    };
+    let old_entries = collect_entries(old_archive.entries());
+    let new_entries = collect_entries(new_archive.entries());
+
+    let mut added = vec![];
+    let mut removed = vec![];
+    let mut modified = vec![];
+    for (hash, new_entry) in &new_entries {
+        match old_entries.get(hash) {
+            None => added.push(new_entry),
+            Some(old_entry) if is_modified(old_entry, new_entry) => {
+                modified.push((old_entry, new_entry))
+            }
+            Some(_) => {}
+        }
+    }
+    for (hash, old_entry) in &old_entries {
+        if !new_entries.contains_key(hash) {
+            removed.push(old_entry);
+        }
+    }
+
+    let name_of = |entry: &PakEntry| names.display_name(entry.hash());
+    let mut added: Vec<_> = added
+        .into_iter()
+        .map(|entry| (name_of(entry), entry))
+        .collect();
+    let mut removed: Vec<_> = removed
+        .into_iter()
+        .map(|entry| (name_of(entry), entry))
+        .collect();
+    let mut modified: Vec<_> = modified
+        .into_iter()
+        .map(|(old, new)| (name_of(new), old, new))
+        .collect();
+    added.sort_by(|a, b| a.0.cmp(&b.0));
+    removed.sort_by(|a, b| a.0.cmp(&b.0));
+    modified.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (name, entry) in &added {
+        println!(
+            "{} {} ({})",
+            "+".green().bold(),
+            name,
+            HumanBytes(entry.uncompressed_size())
+        );
+    }
+    for (name, entry) in &removed {
+        println!(
+            "{} {} ({})",
+            "-".red().bold(),
+            name,
+            HumanBytes(entry.uncompressed_size())
+        );
+    }
+    for (name, old, new) in &modified {
+        println!(
+            "{} {} ({} -> {})",
+            "~".yellow().bold(),
+            name,
+            HumanBytes(old.uncompressed_size()),
+            HumanBytes(new.uncompressed_size())
+        );
+    }
+
+    println!(
+        "{} added, {} removed, {} modified.",
+        added.len().to_string().green(),
+        removed.len().to_string().red(),
+        modified.len().to_string().yellow()
+    );
+
+    if let Some(report_path) = report_path {
+        let mut groups: BTreeMap<Group, Changes> = BTreeMap::new();
+        let group_of = |entry: &PakEntry| Group::of(entry.hash(), names);
+        for (name, entry) in &added {
+            let size = HumanBytes(entry.uncompressed_size());
+            let changes = groups.entry(group_of(entry)).or_default();
+            changes.added.push(format!("`{name}` ({size})"));
+        }
+        for (name, entry) in &removed {
+            let size = HumanBytes(entry.uncompressed_size());
+            let changes = groups.entry(group_of(entry)).or_default();
+            changes.removed.push(format!("`{name}` ({size})"));
+        }
+        for (name, old, new) in &modified {
+            let (old_size, new_size) = (
+                HumanBytes(old.uncompressed_size()),
+                HumanBytes(new.uncompressed_size()),
+            );
+            let changes = groups.entry(group_of(new)).or_default();
+            changes
+                .modified
+                .push(format!("`{name}` ({old_size} -> {new_size})"));
+        }
+        fs::write(report_path, report(old_path, new_path, &groups))?;
+        println!("Report: {}", util::display_path(report_path));
+    }
+
+    Ok(())
+}
+
+/// What the report groups changes by: textures by category, then all other files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Group {
+    Tex(Category),
+    OtherFiles,
+}
+
+impl Group {
+    fn of(hash: u64, names: &NameTable) -> Self {
+        match names.get_name(hash) {
+            Some(name) if names.is_tex_file(hash) == Some(true) => Self::Tex(Category::of(&name)),
+            _ => Self::OtherFiles,
+        }
+    }
+
+    fn label(self) -> String {
+        match self {
+            Self::Tex(Category::Ui) => Category::Ui.label().to_string(),
+            Self::Tex(category) => format!("{} textures", category.label()),
+            Self::OtherFiles => "Other files".to_string(),
+        }
+    }
+}
+
+/// Formatted entries of a group.
+#[derive(Debug, Default)]
+struct Changes {
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+}
+
+/// Markdown summary for mod authors: counts per group first, the entries in collapsed lists.
+fn report(old_path: &Path, new_path: &Path, groups: &BTreeMap<Group, Changes>) -> String {
+    let file_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let mut out = format!(
+        "# Changes from `{}` to `{}`\n\n",
+        file_name(old_path),
+        file_name(new_path)
+    );
+    if groups.is_empty() {
+        out.push_str("No entries changed.\n");
+        return out;
+    }
+
+    for (group, changes) in groups {
+        let counts: Vec<String> = [
+            (changes.added.len(), "added"),
+            (changes.removed.len(), "removed"),
+            (changes.modified.len(), "changed"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, verb)| format!("{count} {verb}"))
+        .collect();
+        out.push_str(&format!("- **{}**: {}\n", group.label(), counts.join(", ")));
+    }
+
+    for (group, changes) in groups {
+        out.push_str(&format!("\n## {}\n", group.label()));
+        for (entries, verb) in [
+            (&changes.added, "added"),
+            (&changes.removed, "removed"),
+            (&changes.modified, "changed"),
+        ] {
+            if entries.is_empty() {
+                continue;
+            }
+            out.push_str(&format!(
+                "\n<details><summary>{} {verb}</summary>\n\n",
+                entries.len()
+            ));
+            for entry in entries {
+                out.push_str(&format!("- {entry}\n"));
+            }
+            out.push_str("\n</details>\n");
+        }
+    }
+    out
+}
+
+fn is_modified(old: &PakEntry, new: &PakEntry) -> bool {
+    old.compressed_size() != new.compressed_size()
+        || old.uncompressed_size() != new.uncompressed_size()
+        || old.checksum() != new.checksum()
+}
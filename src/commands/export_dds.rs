@@ -0,0 +1,72 @@
+use std::{fs, path::Path};
+
+use indicatif::HumanBytes;
+
+use crate::{
+    dds::{self, DdsLayers},
+    handler::TexHandler,
+    names::NameTable,
+    pak::PakFile,
+    process::{TexTransform, process_entry},
+    selector::{self, EntrySelector},
+    tex_header::TexHeader,
+    util,
+};
+
+/// Exports the selected texture as DDS to `out_path`, split into `<stem>_NN.dds` files per image
+/// with [`DdsLayers::Split`].
+pub fn run(
+    pak_path: &Path,
+    selector: &EntrySelector,
+    out_path: &Path,
+    layers: DdsLayers,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    let pak = PakFile::open(pak_path)?;
+    let entry = selector::find_one(selector, pak.entries(), names)?;
+    let data = process_entry(
+        pak.read_entry(entry)?,
+        Some(&TexHandler),
+        &TexTransform::default(),
+    )?;
+    let header = TexHeader::parse(&data)?;
+    let kind = if dds::is_cubemap(&data, &header) {
+        format!("cubemap of {} faces", header.image_count)
+    } else {
+        format!("{} images", header.image_count)
+    };
+    println!(
+        "{}: {}x{}, format {}, {kind}, {} mips",
+        names.display_name(entry.hash()),
+        header.width,
+        header.height,
+        header.format,
+        header.mip_count
+    );
+
+    let files = match layers {
+        DdsLayers::Single => vec![(out_path.to_path_buf(), dds::export_layered(&data)?)],
+        DdsLayers::Split => {
+            let stem = out_path.file_stem().unwrap_or_default().to_string_lossy();
+            dds::export_split(&data)?
+                .into_iter()
+                .enumerate()
+                .map(|(image, dds)| {
+                    (
+                        out_path.with_file_name(format!("{stem}_{image:02}.dds")),
+                        dds,
+                    )
+                })
+                .collect()
+        }
+    };
+    for (path, dds) in &files {
+        fs::write(path, dds)?;
+        println!(
+            "Wrote {} ({})",
+            util::display_path(path),
+            HumanBytes(dds.len() as u64)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::{fs, path::Path};
+
+use indicatif::HumanBytes;
+
+use crate::{
+    family,
+    names::NameTable,
+    pak::PakFile,
+    selector::{self, EntrySelector},
+    util,
+};
+
+/// Extracts the selected entry, or with `whole_family` all textures of its family into the
+/// `out_path` directory.
+pub fn run(
+    pak_path: &Path,
+    selector: &EntrySelector,
+    out_path: &Path,
+    whole_family: bool,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    let pak = PakFile::open(pak_path)?;
+    let entry = selector::find_one(selector, pak.entries(), names)?;
+    let entries = if whole_family {
+        fs::create_dir_all(out_path)?;
+        family::members(pak.entries(), entry.hash(), names)
+    } else {
+        vec![entry]
+    };
+
+    for entry in entries {
+        let data = pak.read_entry(entry)?;
+        // a directory gets the file under the entry's own name
+        let entry_out_path = if out_path.is_dir() {
+            out_path.join(&*names.file_name(entry.hash()))
+        } else {
+            out_path.to_path_buf()
+        };
+        fs::write(&entry_out_path, &data)?;
+        println!(
+            "Extracted {} ({}) to {}",
+            names.display_name(entry.hash()),
+            HumanBytes(data.len() as u64),
+            util::display_path(&entry_out_path)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use std::{collections::HashMap, path::Path, slice};
+
+use indicatif::HumanBytes;
+
+use crate::{
+    family,
+    names::NameTable,
+    pak::PakFile,
+    selector::{self, EntrySelector},
+};
+
+/// Lists the tex entries of the pak grouped by texture family.
+pub fn run(
+    pak_path: &Path,
+    selector: Option<&EntrySelector>,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    if !names.is_loaded() {
+        eyre::bail!("grouping textures by family requires a file name table.");
+    }
+    selector::check_names(selector.map(slice::from_ref).unwrap_or_default(), names)?;
+    let pak = PakFile::open(pak_path)?;
+    let sizes: HashMap<u64, u64> = pak
+        .entries()
+        .iter()
+        .map(|entry| (entry.hash(), entry.uncompressed_size()))
+        .collect();
+
+    let textures = pak
+        .entries()
+        .iter()
+        .filter(|entry| names.is_tex_file(entry.hash()) == Some(true))
+        .filter(|entry| selector.is_none_or(|selector| selector.matches(entry.hash(), names)))
+        .map(|entry| (entry.hash(), names.display_name(entry.hash()).into_owned()));
+    let families = family::group(textures);
+
+    for (family_path, members) in &families {
+        let size: u64 = members.iter().map(|(hash, _)| sizes[hash]).sum();
+        println!("{family_path} ({})", HumanBytes(size));
+        for (hash, name) in members {
+            let map = family::split(name).map(|(_, suffix)| suffix).unwrap_or("-");
+            println!("  {map:<5} {:016X} {}", hash, HumanBytes(sizes[hash]));
+        }
+    }
+    println!(
+        "{} textures in {} families.",
+        families.values().map(Vec::len).sum::<usize>(),
+        families.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use crate::{names::NameTable, path_hash, selector::EntrySelector};
+
+/// Prints the hash of every path and the name of every hash.
+pub fn run(entries: &[EntrySelector], names: &NameTable) -> eyre::Result<()> {
+    for entry in entries {
+        match entry {
+            EntrySelector::Hash(hash) => match names.get_name(*hash) {
+                Some(name) => println!("{hash:016X} {name}"),
+                None => println!("{hash:016X} (unknown)"),
+            },
+            EntrySelector::Path(path) => {
+                let path = if path.starts_with("natives/") {
+                    path.clone()
+                } else {
+                    format!("natives/stm/{path}")
+                };
+                println!("{:016X} {path}", path_hash::hash_path(&path));
+            }
+            EntrySelector::Glob(pattern) => {
+                eyre::bail!("`{pattern}` is a glob, give a full path to hash.")
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use indicatif::HumanBytes;
+
+use crate::{
+    merge::{self, Overlap},
+    session_lock::SessionLock,
+    util,
+};
+
+/// Combines the outputs of `--shard` runs into one pak.
+pub fn run(out_path: &Path, shard_paths: &[PathBuf], force: bool) -> eyre::Result<()> {
+    if out_path.is_file() && !force {
+        eyre::bail!(
+            "`{}` already exists, use `--force` to overwrite it.",
+            util::display_path(out_path)
+        );
+    }
+    let _lock = SessionLock::acquire(out_path)?;
+    let (entries, bytes) = merge::merge(out_path, shard_paths, Overlap::Fail, None, false)?;
+
+    println!(
+        "Merged {} shards into {} ({} entries, {}).",
+        shard_paths.len(),
+        util::display_path(out_path),
+        entries,
+        HumanBytes(bytes)
+    );
+    println!("{}", "Done!".cyan().bold());
+    Ok(())
+}
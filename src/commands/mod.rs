@@ -0,0 +1,19 @@
+pub mod bench;
+pub mod build_info;
+pub mod check_manifest;
+pub mod check_update;
+pub mod compare;
+pub mod content_index;
+pub mod decompress;
+pub mod diff;
+pub mod export_dds;
+pub mod extract_one;
+pub mod families;
+pub mod hashes;
+pub mod merge_shards;
+pub mod preview;
+pub mod rebuild;
+pub mod replace_one;
+pub mod stats;
+pub mod verify_signature;
+pub mod watch;
@@ -0,0 +1,67 @@
+use std::{fs, io::BufWriter, path::Path, slice};
+
+use crate::{
+    handler::TexHandler,
+    names::NameTable,
+    pak::PakFile,
+    preview::{self, Image},
+    process::{TexTransform, process_entry},
+    selector::{self, EntrySelector},
+    util,
+};
+
+/// Writes a PNG thumbnail of every tex entry matching `selector` into `out_dir`.
+pub fn run(
+    pak_path: &Path,
+    selector: &EntrySelector,
+    out_dir: &Path,
+    max_size: u32,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    selector::check_names(slice::from_ref(selector), names)?;
+    let pak = PakFile::open(pak_path)?;
+    let entries: Vec<_> = pak
+        .entries()
+        .iter()
+        .filter(|entry| selector.matches(entry.hash(), names))
+        .filter(|entry| names.is_tex_file(entry.hash()) != Some(false))
+        .collect();
+    if entries.is_empty() {
+        eyre::bail!("no texture matches `{selector}`.");
+    }
+
+    fs::create_dir_all(out_dir)?;
+    let mut written = 0;
+    for entry in entries {
+        let name = names.display_name(entry.hash());
+        let data = process_entry(
+            pak.read_entry(entry)?,
+            Some(&TexHandler),
+            &TexTransform::default(),
+        )?;
+        let Some(image) = preview::decode_mip(&data, max_size)? else {
+            println!("{name}: format can't be previewed, skipped");
+            continue;
+        };
+        let png_path = out_dir.join(format!("{}.png", names.file_name(entry.hash())));
+        write_png(&png_path, &image)?;
+        println!(
+            "{name} -> {} ({}x{})",
+            util::display_path(&png_path),
+            image.width,
+            image.height
+        );
+        written += 1;
+    }
+    println!("Wrote {written} previews.");
+    Ok(())
+}
+
+fn write_png(path: &Path, image: &Image) -> eyre::Result<()> {
+    let file = BufWriter::new(fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&image.rgba)?;
+    Ok(())
+}
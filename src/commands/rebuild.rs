@@ -0,0 +1,219 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use colored::Colorize;
+use indicatif::HumanBytes;
+use serde_json::Value;
+
+use crate::{
+    category::Category,
+    manifest::{self, EntryChecksum, Recorded},
+    names::NameTable,
+    open_files,
+    pak::PakFile,
+    pak_header::{self, PakVersion},
+    pipeline::{self, Options},
+    progress::{ConsoleProgress, Verbosity},
+    retry,
+    selector::EntrySelector,
+    storage::StorageKind,
+    util,
+};
+
+/// Regenerates the output described by a manifest from the source pak, then checks it against the
+/// recorded checksums.
+pub fn run(
+    manifest_path: &Path,
+    pak_path: &Path,
+    force: bool,
+    names: &NameTable,
+    verbosity: Verbosity,
+) -> eyre::Result<()> {
+    let recorded = manifest::read(manifest_path)?;
+    let source_name = pak_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !recorded.source.is_empty() && recorded.source != source_name {
+        eprintln!(
+            "{} the manifest was written for `{}`, rebuilding from `{source_name}`.",
+            "Warning:".yellow().bold(),
+            recorded.source
+        );
+    }
+
+    let overrides_path = write_overrides(&recorded)?;
+    let options = recorded_options(&recorded, pak_path, overrides_path.clone(), force)
+        .map_err(|e| eyre::eyre!("`{}`: {e}", util::display_path(manifest_path)))?;
+    let progress = ConsoleProgress::new(verbosity);
+    let summary = pipeline::run(&options, names, &progress);
+    if let Some(path) = &overrides_path {
+        let _ = fs::remove_file(path);
+    }
+    let summary = summary?;
+    if let Some(e) = summary.processing_error {
+        return Err(e.wrap_err("rebuilding stopped early"));
+    }
+
+    let mut mismatched = 0;
+    let mut found = 0;
+    for path in &summary.output_paths {
+        let pak = PakFile::open(path)?;
+        for entry in pak.entries() {
+            let Some(expected) = recorded.entries.get(&entry.hash()) else {
+                continue;
+            };
+            found += 1;
+            let actual = EntryChecksum::of(&pak.read_entry(entry)?);
+            if actual.size != expected.size || actual.crc32 != expected.crc32 {
+                eprintln!(
+                    "{} {}",
+                    "mismatch:".red().bold(),
+                    names.display_name(entry.hash())
+                );
+                mismatched += 1;
+            }
+        }
+    }
+    let missing = recorded.entries.len() - found;
+    if mismatched > 0 || missing > 0 {
+        eyre::bail!(
+            "the rebuilt output differs from the manifest: {mismatched} entries mismatched, {missing} missing."
+        );
+    }
+
+    // entries are only written in a fixed order with a split size, otherwise the layout differs
+    let identical = summary.output_paths.len() == recorded.outputs.len()
+        && summary
+            .output_paths
+            .iter()
+            .zip(&recorded.outputs)
+            .map(|(path, (_, sha256))| Ok(manifest::sha256_file(path)? == *sha256))
+            .collect::<eyre::Result<Vec<bool>>>()?
+            .into_iter()
+            .all(|same| same);
+    println!(
+        "Rebuilt {} entries, {}.",
+        recorded.entries.len(),
+        HumanBytes(summary.bytes_written)
+    );
+    if identical {
+        println!(
+            "{}",
+            "The output is identical to the original.".green().bold()
+        );
+    } else {
+        println!(
+            "{}",
+            "All entries match the manifest, the file layout differs from the original.".green()
+        );
+    }
+    Ok(())
+}
+
+/// The embedded overrides written back to a file for the pipeline, `None` if there were none.
+fn write_overrides(recorded: &Recorded) -> eyre::Result<Option<PathBuf>> {
+    let overrides = &recorded.options["overrides"];
+    if overrides.is_null() {
+        return Ok(None);
+    }
+    let path = std::env::temp_dir().join(format!(
+        "{}-rebuild-{}.overrides.json",
+        env!("CARGO_PKG_NAME"),
+        std::process::id()
+    ));
+    fs::write(&path, serde_json::to_string(overrides)?)?;
+    Ok(Some(path))
+}
+
+/// Options of the recorded run, restricted to the entries it wrote. The selection options aren't
+/// needed, the recorded entries already are the result of them.
+fn recorded_options(
+    recorded: &Recorded,
+    input_path: &Path,
+    overrides: Option<PathBuf>,
+    force: bool,
+) -> Result<Options, String> {
+    let options = &recorded.options;
+    if !options["script"].is_null() {
+        return Err(
+            "the output was made with `--script`, which rebuilding can't repeat.".to_string(),
+        );
+    }
+    let flag = |key: &str| options[key].as_bool().unwrap_or(false);
+    let string = |key: &str| options[key].as_str().unwrap_or_default();
+    let copy_paths = match &options["copy_paths"] {
+        Value::Array(paths) => paths
+            .iter()
+            .map(|path| path.as_str().unwrap_or_default().parse::<EntrySelector>())
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => vec![],
+    };
+    let pak_version = match string("pak_version") {
+        "" => None,
+        version => Some(version.parse::<PakVersion>()?),
+    };
+    let pak_features = match string("pak_features") {
+        "" => None,
+        features => Some(pak_header::parse_features(features)?),
+    };
+
+    Ok(Options {
+        input_path: input_path.to_path_buf(),
+        output: None,
+        name_template: None,
+        full_package: flag("full_package"),
+        feature_clone: flag("feature_clone"),
+        keep_compression: flag("keep_compression"),
+        compression: string("compression").parse()?,
+        categories: Category::ALL.to_vec(),
+        formats: vec![],
+        previous_output: None,
+        thread_count: num_cpus::get(),
+        split_size: options["split_size"].as_u64(),
+        split_by_category: flag("split_by_category"),
+        verify_sample: 0.0,
+        verify_seed: None,
+        estimate: None,
+        confirm_estimate: false,
+        skip_larger_than: None,
+        skip_smaller_than: None,
+        min_entry_size: None,
+        max_entry_size: None,
+        tex_transform: string("tex_transform").parse()?,
+        content_index: false,
+        copy_paths,
+        non_tex_list: None,
+        manifest: false,
+        sign_key: None,
+        variants: vec![],
+        embed_info: false,
+        top_impact: None,
+        pak_version,
+        pak_features,
+        read_ahead: None,
+        storage: StorageKind::Fs,
+        async_io: false,
+        report_duplicates: false,
+        max_open_files: open_files::DEFAULT_LIMIT,
+        align: options["align"].as_u64(),
+        deterministic: options["deterministic"].as_bool().unwrap_or(false),
+        retries: retry::DEFAULT_RETRIES,
+        low_priority: false,
+        io_throttle: None,
+        unknown_list: None,
+        names_dump: None,
+        allow_processed_input: false,
+        check_free_space: true,
+        overrides,
+        script: None,
+        force,
+        picked_entries: None,
+        only_entries: Some(recorded.entries.keys().copied().collect::<HashSet<u64>>()),
+        shard: None,
+        fluffy: None,
+    })
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use indicatif::HumanBytes;
+use ree_pak_core::{
+    pak::{CompressionType, PakEntry},
+    write::{FileOptions, PakWriter},
+};
+
+use crate::{
+    family,
+    metadata::TrailingBlock,
+    names::NameTable,
+    pak::PakFile,
+    pak_header::{ENTRY_RECORD_SIZE, PakHeader},
+    process::{StoreOptions, write_to_pak},
+    selector::{self, EntrySelector},
+    util,
+};
+
+/// Compression type (low nibble) and encryption type of the attributes.
+const ATTR_COMPRESSION_MASK: u64 = 0x0000_000F;
+const ATTR_ENCRYPTION_MASK: u64 = 0x00FF_0000;
+
+/// Replaces the selected entry with a file, or with `whole_family` the textures of its family with
+/// the files of the same name in the `file_path` directory.
+pub fn run(
+    pak_path: &Path,
+    selector: &EntrySelector,
+    file_path: &Path,
+    whole_family: bool,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    let pak = PakFile::open(pak_path)?;
+    let entry = selector::find_one(selector, pak.entries(), names)?;
+    let mut replacements: HashMap<u64, Vec<u8>> = HashMap::new();
+    if whole_family {
+        if !file_path.is_dir() {
+            eyre::bail!(
+                "with `--family`, `{}` must be a directory holding the textures under their own names.",
+                util::display_path(file_path)
+            );
+        }
+        for member in family::members(pak.entries(), entry.hash(), names) {
+            let member_path = file_path.join(&*names.file_name(member.hash()));
+            if member_path.is_file() {
+                replacements.insert(member.hash(), fs::read(&member_path)?);
+            }
+        }
+        if replacements.is_empty() {
+            eyre::bail!(
+                "none of the family's textures are in `{}`.",
+                util::display_path(file_path)
+            );
+        }
+    } else {
+        replacements.insert(entry.hash(), fs::read(file_path)?);
+    }
+
+    let header = PakHeader::read(pak_path)?;
+    let mut appended = 0;
+    if !header.has_encrypted_toc() {
+        for entry in pak.entries() {
+            if let Some(data) = replacements.get(&entry.hash()) {
+                if append_in_place(pak_path, entry, data)? {
+                    appended += 1;
+                }
+            }
+        }
+    }
+    // rewrite with all replacements if any of them couldn't be appended
+    let rewritten = appended < replacements.len();
+    if rewritten {
+        rewrite(pak_path, &pak, &replacements)?;
+    }
+
+    for (hash, data) in &replacements {
+        println!(
+            "Replaced {} ({})",
+            names.display_name(*hash),
+            HumanBytes(data.len() as u64)
+        );
+    }
+    if rewritten {
+        println!("Rewrote {}.", util::display_path(pak_path));
+    } else {
+        println!("Appended to the end of the pak.");
+    }
+    Ok(())
+}
+
+/// Appends the new data after the entry data of the pak, moves the trailing block behind it and
+/// points the entry's record at it.
+///
+/// Returns `false` without touching the file if the record can't be found in the entry table.
+fn append_in_place(pak_path: &Path, entry: &PakEntry, data: &[u8]) -> eyre::Result<bool> {
+    // opened again, earlier replacements moved the end of the data
+    let pak = PakFile::open(pak_path)?;
+    // the table follows the header, it ends before the first entry's data
+    let table_end = pak
+        .entries()
+        .iter()
+        .map(|entry| entry.offset())
+        .min()
+        .unwrap_or(0);
+    let mut file = OpenOptions::new().read(true).write(true).open(pak_path)?;
+    let mut table = vec![0u8; table_end as usize];
+    file.read_exact(&mut table)?;
+
+    let mut key = Vec::with_capacity(40);
+    key.extend_from_slice(&(entry.hash() as u32).to_le_bytes());
+    key.extend_from_slice(&((entry.hash() >> 32) as u32).to_le_bytes());
+    key.extend_from_slice(&entry.offset().to_le_bytes());
+    key.extend_from_slice(&entry.compressed_size().to_le_bytes());
+    key.extend_from_slice(&entry.uncompressed_size().to_le_bytes());
+    let Some(record_offset) = (0..table.len().saturating_sub(ENTRY_RECORD_SIZE - 1))
+        .step_by(4)
+        .find(|&offset| table[offset..].starts_with(&key))
+    else {
+        return Ok(false);
+    };
+
+    // both read before anything is written, a failure leaves the file as it was
+    let trailing_block = TrailingBlock::read(pak_path, pak.archive())?;
+    let checksum = stored_checksum(pak_path, entry.hash(), data)?;
+    // over the trailing block, which goes after the new data
+    let data_offset = pak
+        .entries()
+        .iter()
+        .map(|entry| entry.offset() + entry.compressed_size())
+        .max()
+        .unwrap_or(table_end);
+    drop(pak);
+
+    file.seek(SeekFrom::Start(data_offset))?;
+    file.write_all(data)?;
+    file.set_len(data_offset + data.len() as u64)?;
+
+    let record = &table[record_offset..record_offset + ENTRY_RECORD_SIZE];
+    let attributes = u64::from_le_bytes(record[32..40].try_into().unwrap())
+        & !(ATTR_COMPRESSION_MASK | ATTR_ENCRYPTION_MASK);
+    file.seek(SeekFrom::Start(record_offset as u64 + 8))?;
+    file.write_all(&data_offset.to_le_bytes())?;
+    file.write_all(&(data.len() as u64).to_le_bytes())?;
+    file.write_all(&(data.len() as u64).to_le_bytes())?;
+    file.write_all(&attributes.to_le_bytes())?;
+    file.write_all(&checksum.to_le_bytes())?;
+    drop(file);
+
+    if let Some(trailing_block) = trailing_block {
+        trailing_block.append_to(pak_path)?;
+    }
+    Ok(true)
+}
+
+/// The checksum the pak writer records for `data` stored uncompressed, taken from a pak holding
+/// only that entry, so the patched record matches one of a rewritten pak.
+fn stored_checksum(pak_path: &Path, hash: u64, data: &[u8]) -> eyre::Result<u64> {
+    let temp_path = pak_path.with_extension("pak.entry.tmp");
+    let checksum = write_single_entry(&temp_path, hash, data)
+        .and_then(|()| Ok(PakFile::open(&temp_path)?.entries()[0].checksum()));
+    let _ = fs::remove_file(&temp_path);
+    checksum
+}
+
+fn write_single_entry(path: &Path, hash: u64, data: &[u8]) -> eyre::Result<()> {
+    let mut writer = PakWriter::new(fs::File::create(path)?, 1);
+    writer.start_file(
+        hash,
+        FileOptions::default().with_compression_type(CompressionType::None),
+    )?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes a new pak with the entries replaced and moves it over the old one.
+fn rewrite(
+    pak_path: &Path,
+    pak: &PakFile,
+    replacements: &HashMap<u64, Vec<u8>>,
+) -> eyre::Result<()> {
+    let temp_path = pak_path.with_extension("pak.tmp");
+    let mut writer = PakWriter::new(fs::File::create(&temp_path)?, pak.entries().len() as u64);
+    let store = StoreOptions {
+        feature_clone: true,
+        keep_compression: false,
+        codec: None,
+    };
+    for entry in pak.entries() {
+        if let Some(data) = replacements.get(&entry.hash()) {
+            write_to_pak(&mut writer, entry, entry.hash(), data, store)?;
+        } else {
+            let entry_data = pak.read_entry(entry)?;
+            write_to_pak(&mut writer, entry, entry.hash(), &entry_data, store)?;
+        }
+    }
+    writer.finish()?;
+
+    let source_header = PakHeader::read(pak_path)?;
+    PakHeader::for_output(PakHeader::read(&temp_path)?, source_header, None, None)
+        .write_to(&temp_path)?;
+    if let Some(trailing_block) = TrailingBlock::read(pak_path, pak.archive())? {
+        trailing_block.append_to(&temp_path)?;
+    }
+    fs::rename(&temp_path, pak_path)?;
+    Ok(())
+}
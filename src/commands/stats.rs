@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use indicatif::HumanBytes;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use ree_pak_core::pak::PakEntry;
+
+use crate::{
+    names::NameTable,
+    pak::PakFile,
+    progress::{ConsoleProgress, Progress, Verbosity},
+    tex_format::TexFormat,
+    tex_header::{TEX_MAGIC, TexHeader},
+    util,
+};
+
+/// A tex entry and its header.
+struct Row<'a> {
+    entry: &'a PakEntry,
+    header: TexHeader,
+}
+
+/// Prints the number and size of the pak's textures per format, and writes a row per texture to
+/// `csv_path` for spreadsheets.
+pub fn run(
+    pak_path: &Path,
+    csv_path: Option<&Path>,
+    names: &NameTable,
+    verbosity: Verbosity,
+) -> eyre::Result<()> {
+    let pak = PakFile::open(pak_path)?;
+    // unknown names are told apart by content
+    let candidates: Vec<&PakEntry> = pak
+        .entries()
+        .iter()
+        .filter(|entry| names.is_tex_file(entry.hash()) != Some(false))
+        .collect();
+
+    let progress = ConsoleProgress::new(verbosity);
+    progress.begin_phase("Reading tex headers...", Some(candidates.len() as u64));
+    let rows = candidates
+        .par_iter()
+        .map(|&entry| {
+            let data = pak.read_entry(entry)?;
+            progress.inc(1);
+            if !data.starts_with(TEX_MAGIC) {
+                return Ok(None);
+            }
+            match TexHeader::parse(&data) {
+                Ok(header) => Ok(Some(Row { entry, header })),
+                Err(e) => {
+                    progress.warn(&format!("{}: {e}", names.display_name(entry.hash())));
+                    Ok(None)
+                }
+            }
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    progress.end_phase();
+    let mut rows: Vec<Row> = rows.into_iter().flatten().collect();
+    rows.sort_by_cached_key(|row| names.display_name(row.entry.hash()).into_owned());
+
+    let mut formats: BTreeMap<String, (usize, u64, u64)> = BTreeMap::new();
+    for row in &rows {
+        let format = formats
+            .entry(TexFormat::of(row.header.format).to_string())
+            .or_default();
+        format.0 += 1;
+        format.1 += row.entry.compressed_size();
+        format.2 += row.entry.uncompressed_size();
+    }
+    println!(
+        "{:<8} {:>8} {:>12} {:>12}",
+        "format", "count", "in pak", "unpacked"
+    );
+    for (format, (count, compressed, uncompressed)) in &formats {
+        println!(
+            "{format:<8} {count:>8} {:>12} {:>12}",
+            HumanBytes(*compressed).to_string(),
+            HumanBytes(*uncompressed).to_string()
+        );
+    }
+    println!(
+        "{} textures, {} in the pak, {} unpacked.",
+        rows.len(),
+        HumanBytes(rows.iter().map(|row| row.entry.compressed_size()).sum()),
+        HumanBytes(rows.iter().map(|row| row.entry.uncompressed_size()).sum())
+    );
+
+    if let Some(csv_path) = csv_path {
+        fs::write(csv_path, csv(&rows, names))?;
+        println!("Wrote {}", util::display_path(csv_path));
+    }
+    Ok(())
+}
+
+fn csv(rows: &[Row], names: &NameTable) -> String {
+    let mut csv = String::from(
+        "name,hash,format,dxgi_format,width,height,mips,array_size,compressed_size,uncompressed_size\n",
+    );
+    for Row { entry, header } in rows {
+        csv.push_str(&format!(
+            "{},{:016X},{},{},{},{},{},{},{},{}\n",
+            csv_field(names.get_name(entry.hash()).unwrap_or_default()),
+            entry.hash(),
+            TexFormat::of(header.format),
+            header.format,
+            header.width,
+            header.height,
+            header.mip_count,
+            header.image_count,
+            entry.compressed_size(),
+            entry.uncompressed_size()
+        ));
+    }
+    csv
+}
+
+/// Quotes a field holding a separator or quote.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
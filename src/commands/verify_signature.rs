@@ -0,0 +1,17 @@
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::{signing, util};
+
+pub fn run(path: &Path, public_key: &str) -> eyre::Result<()> {
+    let key = signing::parse_public_key(public_key)?;
+    signing::verify_file(path, &key)?;
+    println!(
+        "{}",
+        format!("`{}` is signed by the key.", util::display_path(path))
+            .green()
+            .bold()
+    );
+    Ok(())
+}
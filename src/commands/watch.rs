@@ -0,0 +1,144 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use colored::Colorize;
+use indicatif::HumanBytes;
+use ree_pak_core::write::{FileOptions, PakWriter};
+
+use crate::{cancel, output, path_hash, session_lock::SessionLock, stdio, util};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const NATIVES_ROOT: &str = "natives/STM/";
+
+/// Modification time and size of every file under the watched directory.
+type Snapshot = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+/// Packs the loose files under `dir` into `out_path`, and again whenever one of them changes.
+///
+/// Files are stored under their path relative to `dir`, with the `natives/STM/` root added if
+/// it's not there.
+pub fn run(dir: &Path, out_path: &Path) -> eyre::Result<()> {
+    if !dir.is_dir() {
+        eyre::bail!("`{}` is not a directory.", util::display_path(dir));
+    }
+    if stdio::is_stdio(out_path) {
+        eyre::bail!("`watch` writes the pak again on every change, it can't write to stdout.");
+    }
+    // the output and its temp file would change the snapshot, repacking forever
+    let out_dir = out_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let watched = fs::canonicalize(dir)?;
+    if fs::canonicalize(out_dir).is_ok_and(|out_dir| out_dir.starts_with(&watched)) {
+        eyre::bail!(
+            "`{}` is inside the watched directory, it would be packed into itself. Write it somewhere else.",
+            util::display_path(out_path)
+        );
+    }
+    let _lock = SessionLock::acquire(out_path)?;
+    cancel::install_ctrlc_handler()?;
+
+    println!(
+        "Watching {}, press Ctrl+C to stop.",
+        util::display_path(dir)
+    );
+    let mut packed: Option<Snapshot> = None;
+    let mut previous = snapshot(dir)?;
+    while cancel::check().is_ok() {
+        // pack once edits settled, editors often write a file in several steps
+        let current = snapshot(dir)?;
+        if current == previous && packed.as_ref() != Some(&current) {
+            match pack(dir, &current, out_path) {
+                Ok(bytes) => println!(
+                    "{} {} with {} files, {}.",
+                    "Packed".green().bold(),
+                    util::display_path(out_path),
+                    current.len(),
+                    HumanBytes(bytes)
+                ),
+                // keep watching, the next save may fix it
+                Err(e) => eprintln!("{}: {e}", "Error".red().bold()),
+            }
+            packed = Some(current.clone());
+        }
+        previous = current;
+        thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
+
+fn snapshot(dir: &Path) -> eyre::Result<Snapshot> {
+    let mut files = Snapshot::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                files.insert(entry.path(), (metadata.modified()?, metadata.len()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Entry path of a file under the watched directory.
+fn entry_name(dir: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(dir).unwrap_or(path).to_string_lossy();
+    let name = path_hash::normalize_path(&relative);
+    if name.to_ascii_lowercase().starts_with("natives/") {
+        name
+    } else {
+        format!("{NATIVES_ROOT}{name}")
+    }
+}
+
+/// Writes all files into a new pak, moved over `out_path` once complete. Returns the bytes packed.
+fn pack(dir: &Path, files: &Snapshot, out_path: &Path) -> eyre::Result<u64> {
+    // paths differing only in case hash the same, the pak can only hold one of them
+    let mut entries: BTreeMap<u64, &Path> = BTreeMap::new();
+    for path in files.keys() {
+        let hash = path_hash::hash_path(&entry_name(dir, path));
+        if let Some(other) = entries.insert(hash, path) {
+            eyre::bail!(
+                "`{}` and `{}` are the same entry in the pak, rename or remove one of them.",
+                util::display_path(other),
+                util::display_path(path)
+            );
+        }
+    }
+
+    let temp_path = output::temp_path(out_path);
+    match write_pak(&entries, &temp_path) {
+        Ok(bytes) => {
+            fs::rename(&temp_path, out_path)?;
+            Ok(bytes)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+fn write_pak(entries: &BTreeMap<u64, &Path>, path: &Path) -> eyre::Result<u64> {
+    let mut writer = PakWriter::new(fs::File::create(path)?, entries.len() as u64);
+    let mut bytes = 0;
+    for (&hash, path) in entries {
+        let data = fs::read(path)?;
+        writer.start_file(hash, FileOptions::default())?;
+        writer.write_all(&data)?;
+        bytes += data.len() as u64;
+    }
+    writer.finish()?;
+    Ok(bytes)
+}
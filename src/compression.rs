@@ -0,0 +1,97 @@
+//! Codec of the output entries per entry class, `--compression`. Parsed from and written to the
+//! manifest as `tex=zstd,other=none`, a single codec applies to both classes.
+
+use std::{fmt, str::FromStr};
+
+use ree_pak_core::pak::CompressionType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    None,
+    Deflate,
+    Zstd,
+}
+
+impl Codec {
+    pub fn compression_type(self) -> CompressionType {
+        match self {
+            Self::None => CompressionType::None,
+            Self::Deflate => CompressionType::Deflate,
+            Self::Zstd => CompressionType::Zstd,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Deflate => "deflate",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "deflate" => Ok(Self::Deflate),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "unknown codec `{s}`, expected `none`, `deflate` or `zstd`"
+            )),
+        }
+    }
+}
+
+/// Codecs chosen for tex and other entries, `None` for the default of the class: tex entries
+/// uncompressed, others per `--keep-compression`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputCompression {
+    pub tex: Option<Codec>,
+    pub other: Option<Codec>,
+}
+
+impl OutputCompression {
+    pub fn codec(&self, is_tex: bool) -> Option<Codec> {
+        if is_tex { self.tex } else { self.other }
+    }
+}
+
+impl FromStr for OutputCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut compression = Self::default();
+        for setting in s.split(',').filter(|setting| !setting.trim().is_empty()) {
+            match setting.split_once('=') {
+                Some(("tex", codec)) => compression.tex = Some(codec.parse()?),
+                Some(("other", codec)) => compression.other = Some(codec.parse()?),
+                Some((class, _)) => {
+                    return Err(format!(
+                        "unknown entry class `{class}`, expected `tex` or `other`"
+                    ));
+                }
+                None => {
+                    let codec = Some(setting.parse()?);
+                    compression = Self {
+                        tex: codec,
+                        other: codec,
+                    };
+                }
+            }
+        }
+        Ok(compression)
+    }
+}
+
+impl fmt::Display for OutputCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let settings: Vec<String> = [("tex", self.tex), ("other", self.other)]
+            .into_iter()
+            .filter_map(|(class, codec)| Some(format!("{class}={}", codec?.label())))
+            .collect();
+        write!(f, "{}", settings.join(","))
+    }
+}
@@ -0,0 +1,58 @@
+//! `config.json` in the per-user config directory, defaults for options the user didn't give.
+//!
+//! Written by the `bench` command with the settings that were fastest on this machine.
+
+use std::{fs, path::PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::util;
+
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Config {
+    pub thread_count: Option<usize>,
+    /// Read-ahead depth, see [`crate::pipeline::Options::read_ahead`].
+    pub read_ahead: Option<usize>,
+}
+
+impl Config {
+    fn path() -> Option<PathBuf> {
+        Some(util::config_dir()?.join(CONFIG_FILE))
+    }
+
+    /// The saved config, empty if there is none or it can't be read.
+    pub fn load() -> Self {
+        let Some(config) = Self::path()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|data| serde_json::from_slice::<Value>(&data).ok())
+        else {
+            return Self::default();
+        };
+        let number = |key: &str| config[key].as_u64().map(|value| value as usize);
+        Self {
+            thread_count: number("thread_count").filter(|&threads| threads > 0),
+            read_ahead: number("read_ahead"),
+        }
+    }
+
+    /// Saves the config, returns where it was written.
+    pub fn save(&self) -> eyre::Result<PathBuf> {
+        let path = Self::path().ok_or_else(|| eyre::eyre!("no config directory found"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let config = json!({
+            "thread_count": self.thread_count,
+            "read_ahead": self.read_ahead,
+        });
+        fs::write(&path, serde_json::to_string_pretty(&config)?)?;
+        Ok(path)
+    }
+
+    /// Thread count to use when the user didn't pick one.
+    pub fn default_thread_count(&self) -> usize {
+        self.thread_count.unwrap_or_else(num_cpus::get)
+    }
+}
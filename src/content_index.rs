@@ -0,0 +1,136 @@
+//! Compact binary index of an output pak, for community tools that want to look entries up
+//! without parsing the pak itself.
+//!
+//! Layout (little endian): magic `MTDX`, `u32` version, `u32` entry count, then one 32 byte
+//! record per entry sorted by hash: `u64` hash, `u64` offset, `u64` size, `u8` kind, 7 bytes
+//! padding. Offsets and sizes are those of the entry data inside the pak.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::pak::PakFile;
+
+const MAGIC: &[u8; 4] = b"MTDX";
+const VERSION: u32 = 1;
+const RECORD_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Other = 0,
+    Tex = 1,
+}
+
+impl EntryKind {
+    fn from_u8(value: u8) -> eyre::Result<Self> {
+        match value {
+            0 => Ok(Self::Other),
+            1 => Ok(Self::Tex),
+            _ => eyre::bail!("unknown entry kind {value}"),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Tex => "tex",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IndexEntry {
+    pub hash: u64,
+    pub offset: u64,
+    pub size: u64,
+    pub kind: EntryKind,
+}
+
+#[derive(Debug, Default)]
+pub struct ContentIndex {
+    /// Sorted by hash.
+    pub entries: Vec<IndexEntry>,
+}
+
+impl ContentIndex {
+    pub fn sidecar_path(pak_path: &Path) -> PathBuf {
+        let mut path = pak_path.as_os_str().to_owned();
+        path.push(".cidx");
+        PathBuf::from(path)
+    }
+
+    /// Indexes the entries of a written pak, `tex_hashes` are the entries written as tex files.
+    pub fn from_pak(pak: &PakFile, tex_hashes: &HashSet<u64>) -> Self {
+        let mut entries: Vec<IndexEntry> = pak
+            .entries()
+            .iter()
+            .map(|entry| IndexEntry {
+                hash: entry.hash(),
+                offset: entry.offset(),
+                size: entry.compressed_size(),
+                kind: if tex_hashes.contains(&entry.hash()) {
+                    EntryKind::Tex
+                } else {
+                    EntryKind::Other
+                },
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.hash);
+        Self { entries }
+    }
+
+    /// Looks up an entry by hash.
+    pub fn get(&self, hash: u64) -> Option<&IndexEntry> {
+        self.entries
+            .binary_search_by_key(&hash, |entry| entry.hash)
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+
+    pub fn read(path: &Path) -> eyre::Result<Self> {
+        let mut reader = io::BufReader::new(fs::File::open(path)?);
+        let mut header = [0u8; 12];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            eyre::bail!("`{}` is not a content index", path.display());
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != VERSION {
+            eyre::bail!("unsupported content index version {version}");
+        }
+        let count = u32::from_le_bytes(header[8..12].try_into().unwrap());
+
+        let mut entries = Vec::with_capacity(count as usize);
+        let mut record = [0u8; RECORD_SIZE];
+        for _ in 0..count {
+            reader.read_exact(&mut record)?;
+            entries.push(IndexEntry {
+                hash: u64::from_le_bytes(record[0..8].try_into().unwrap()),
+                offset: u64::from_le_bytes(record[8..16].try_into().unwrap()),
+                size: u64::from_le_bytes(record[16..24].try_into().unwrap()),
+                kind: EntryKind::from_u8(record[24])?,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn write(&self, path: &Path) -> eyre::Result<()> {
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for entry in &self.entries {
+            let mut record = [0u8; RECORD_SIZE];
+            record[0..8].copy_from_slice(&entry.hash.to_le_bytes());
+            record[8..16].copy_from_slice(&entry.offset.to_le_bytes());
+            record[16..24].copy_from_slice(&entry.size.to_le_bytes());
+            record[24] = entry.kind as u8;
+            writer.write_all(&record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! DDS export of uncompressed tex files. Textures are written with the DX10 header, which carries
+//! the DXGI format of the tex as is, so every format the game uses round-trips.
+//!
+//! A tex holds `image_count` images (array layers or cubemap faces) of `mip_count` mips each,
+//! image-major like the resource layout DDS expects.
+
+use clap::ValueEnum;
+
+use crate::tex_header::TexHeader;
+
+/// How the images of array and cubemap textures are exported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DdsLayers {
+    /// One DDS holding all images as a texture array or cubemap.
+    #[default]
+    Single,
+    /// A numbered DDS per image.
+    Split,
+}
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_SIZE: u32 = 124;
+const PIXEL_FORMAT_SIZE: u32 = 32;
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0x200 | 0xFC00;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D11_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// Offset of the cubemap marker in the tex header, non-zero for cubemaps.
+const TEX_CUBEMAP_MARKER: usize = 0x18;
+
+/// Block width and bytes per block (per pixel for plain formats) of the formats the game uses,
+/// to strip the row padding of the tex pitch. Other formats are written as stored.
+fn block_layout(format: u32) -> Option<(u32, u32)> {
+    match format {
+        // BC1, BC4
+        70..=72 | 79..=81 => Some((4, 8)),
+        // BC2, BC3, BC5, BC6H, BC7
+        73..=78 | 82..=84 | 94..=99 => Some((4, 16)),
+        // R8G8B8A8, B8G8R8A8
+        27..=32 | 87 | 91 => Some((1, 4)),
+        // R8
+        60..=64 => Some((1, 1)),
+        _ => None,
+    }
+}
+
+/// Whether the tex is a cubemap, its images are then groups of 6 faces.
+pub fn is_cubemap(data: &[u8], header: &TexHeader) -> bool {
+    let marker = u32::from_le_bytes(
+        data[TEX_CUBEMAP_MARKER..TEX_CUBEMAP_MARKER + 4]
+            .try_into()
+            .unwrap(),
+    );
+    marker != 0 && header.image_count % 6 == 0
+}
+
+/// Mip data of `image`, all mips in order without row padding.
+fn image_data(data: &[u8], header: &TexHeader, image: usize) -> eyre::Result<Vec<u8>> {
+    let mip_count = header.mip_count as usize;
+    let mut out = vec![];
+    for mip in 0..mip_count {
+        let index = image * mip_count + mip;
+        let bytes = header.mip_data(data, index)?;
+        let pitch = header.mips[index].pitch as usize;
+        let width = (header.width as u32 >> mip).max(1);
+        let row_size = block_layout(header.format)
+            .map(|(block_width, block_size)| (width.div_ceil(block_width) * block_size) as usize);
+        match row_size {
+            Some(row_size) if pitch > row_size => {
+                for row in bytes.chunks(pitch) {
+                    out.extend_from_slice(&row[..row_size.min(row.len())]);
+                }
+            }
+            _ => out.extend_from_slice(bytes),
+        }
+    }
+    Ok(out)
+}
+
+/// Size of the top mip without padding, for the header's linear size.
+fn top_mip_size(header: &TexHeader) -> u32 {
+    match block_layout(header.format) {
+        Some((block_width, block_size)) => {
+            let width = (header.width as u32).div_ceil(block_width);
+            let height = (header.height as u32).div_ceil(block_width);
+            width * height * block_size
+        }
+        None => header.mips.first().map_or(0, |mip| mip.size),
+    }
+}
+
+fn push_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// DDS file of the given images, as a cubemap if `cubemap` (the images are then faces).
+fn write(header: &TexHeader, images: &[Vec<u8>], cubemap: bool) -> Vec<u8> {
+    let mip_count = header.mip_count as u32;
+    let mut caps = DDSCAPS_TEXTURE;
+    if mip_count > 1 {
+        caps |= DDSCAPS_MIPMAP | DDSCAPS_COMPLEX;
+    }
+    if images.len() > 1 {
+        caps |= DDSCAPS_COMPLEX;
+    }
+
+    let mut out = Vec::with_capacity(148 + images.iter().map(Vec::len).sum::<usize>());
+    out.extend_from_slice(MAGIC);
+    push_u32(&mut out, HEADER_SIZE);
+    push_u32(
+        &mut out,
+        DDSD_CAPS
+            | DDSD_HEIGHT
+            | DDSD_WIDTH
+            | DDSD_PIXELFORMAT
+            | DDSD_MIPMAPCOUNT
+            | DDSD_LINEARSIZE,
+    );
+    push_u32(&mut out, header.height as u32);
+    push_u32(&mut out, header.width as u32);
+    push_u32(&mut out, top_mip_size(header));
+    // depth, 3D textures aren't used by the game
+    push_u32(&mut out, 0);
+    push_u32(&mut out, mip_count);
+    out.extend_from_slice(&[0; 11 * 4]);
+    // pixel format, the real one is in the DX10 header
+    push_u32(&mut out, PIXEL_FORMAT_SIZE);
+    push_u32(&mut out, DDPF_FOURCC);
+    out.extend_from_slice(b"DX10");
+    out.extend_from_slice(&[0; 5 * 4]);
+    push_u32(&mut out, caps);
+    push_u32(
+        &mut out,
+        if cubemap {
+            DDSCAPS2_CUBEMAP_ALL_FACES
+        } else {
+            0
+        },
+    );
+    out.extend_from_slice(&[0; 3 * 4]);
+
+    push_u32(&mut out, header.format);
+    push_u32(&mut out, D3D10_RESOURCE_DIMENSION_TEXTURE2D);
+    push_u32(
+        &mut out,
+        if cubemap {
+            D3D11_RESOURCE_MISC_TEXTURECUBE
+        } else {
+            0
+        },
+    );
+    // cubemaps count whole cubes
+    let array_size = if cubemap {
+        images.len() / 6
+    } else {
+        images.len()
+    };
+    push_u32(&mut out, array_size as u32);
+    push_u32(&mut out, 0);
+
+    for image in images {
+        out.extend_from_slice(image);
+    }
+    out
+}
+
+/// One DDS with all images of the tex, a texture array or cubemap if it has several.
+pub fn export_layered(data: &[u8]) -> eyre::Result<Vec<u8>> {
+    let header = TexHeader::parse(data)?;
+    let images = (0..header.image_count as usize)
+        .map(|image| image_data(data, &header, image))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    Ok(write(&header, &images, is_cubemap(data, &header)))
+}
+
+/// A 2D DDS per image of the tex, in image order.
+pub fn export_split(data: &[u8]) -> eyre::Result<Vec<Vec<u8>>> {
+    let header = TexHeader::parse(data)?;
+    (0..header.image_count as usize)
+        .map(|image| {
+            let image = image_data(data, &header, image)?;
+            Ok(write(&header, &[image], false))
+        })
+        .collect()
+}
@@ -0,0 +1,55 @@
+//! Finds output entries with byte-identical data. The pak writer stores every entry's data on its
+//! own, so duplicates are only reported with the space sharing their data would save.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+#[derive(Default)]
+pub struct DuplicateFinder {
+    /// Content digest to the size and hashes of the entries with that content.
+    groups: Mutex<HashMap<[u8; 32], (u64, Vec<u64>)>>,
+}
+
+/// Entries sharing the same data.
+pub struct DuplicateGroup {
+    pub size: u64,
+    /// Entry hashes, sorted.
+    pub hashes: Vec<u64>,
+}
+
+impl DuplicateGroup {
+    /// Bytes saved if all entries shared one copy of the data.
+    pub fn savings(&self) -> u64 {
+        self.size * (self.hashes.len() as u64 - 1)
+    }
+}
+
+impl DuplicateFinder {
+    pub fn add(&self, hash: u64, data: &[u8]) {
+        let digest: [u8; 32] = Sha256::digest(data).into();
+        self.groups
+            .lock()
+            .entry(digest)
+            .or_insert_with(|| (data.len() as u64, vec![]))
+            .1
+            .push(hash);
+    }
+
+    /// Groups of more than one entry, largest savings first.
+    pub fn into_duplicates(self) -> Vec<DuplicateGroup> {
+        let mut duplicates: Vec<DuplicateGroup> = self
+            .groups
+            .into_inner()
+            .into_values()
+            .filter(|(_, hashes)| hashes.len() > 1)
+            .map(|(size, mut hashes)| {
+                hashes.sort();
+                DuplicateGroup { size, hashes }
+            })
+            .collect();
+        duplicates.sort_by_key(|group| std::cmp::Reverse(group.savings()));
+        duplicates
+    }
+}
@@ -0,0 +1,116 @@
+//! `diagnostics.zip` offered after a failed run: everything a bug report needs in one file, the
+//! error, the log, the input pak header, the tool version and the raw bytes of the failing entry.
+
+use std::{
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{names::NameTable, pak::PakFile, pak_header::PakHeader, util};
+
+/// Larger entries are left out, the bundle has to fit in an issue attachment.
+const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Context of an error processing a single entry, shown as the entry name.
+#[derive(Debug)]
+pub struct FailedEntry {
+    pub hash: u64,
+    name: String,
+}
+
+impl FailedEntry {
+    pub fn new(hash: u64, names: &NameTable) -> Self {
+        Self {
+            hash,
+            name: names.display_name(hash).into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for FailedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Where the bundle of a run writing `output_path` goes.
+pub fn path(output_path: &Path) -> PathBuf {
+    output_path.with_file_name("diagnostics.zip")
+}
+
+/// Writes the bundle for `error`, which happened processing `input_path`.
+pub fn write(
+    path: &Path,
+    error: &eyre::Report,
+    input_path: &Path,
+    log: &[String],
+) -> eyre::Result<()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(fs::File::create(path)?);
+
+    zip.start_file("error.txt", options)?;
+    writeln!(zip, "{error:?}")?;
+    zip.start_file("log.txt", options)?;
+    for line in log {
+        writeln!(zip, "{line}")?;
+    }
+
+    zip.start_file("info.txt", options)?;
+    writeln!(
+        zip,
+        "tool: {} v{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(
+        zip,
+        "os: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    writeln!(zip, "arguments: {}", args.join(" "))?;
+    writeln!(zip, "input: {}", util::display_path(input_path))?;
+    match fs::metadata(input_path) {
+        Ok(metadata) => writeln!(zip, "input size: {}", metadata.len())?,
+        Err(e) => writeln!(zip, "input size: {e}")?,
+    }
+    match PakHeader::read(input_path) {
+        Ok(header) => writeln!(zip, "input header: {header}")?,
+        Err(e) => writeln!(zip, "input header: {e}")?,
+    }
+
+    if let Some(failed) = error.downcast_ref::<FailedEntry>() {
+        // the bundle is still worth sending without the entry
+        match failing_entry(input_path, failed.hash) {
+            Ok(Some(data)) => {
+                zip.start_file(format!("entry_{:016X}.bin", failed.hash), options)?;
+                zip.write_all(&data)?;
+            }
+            Ok(None) => writeln!(
+                zip,
+                "entry {:016X}: larger than {MAX_ENTRY_SIZE} bytes, left out",
+                failed.hash
+            )?,
+            Err(e) => writeln!(zip, "entry {:016X}: {e}", failed.hash)?,
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// The decompressed bytes of the entry, `None` if it's too large to include.
+fn failing_entry(input_path: &Path, hash: u64) -> eyre::Result<Option<Vec<u8>>> {
+    let pak = PakFile::open(input_path)?;
+    let Some(entry) = pak.entries().iter().find(|entry| entry.hash() == hash) else {
+        eyre::bail!("not in the input");
+    };
+    if entry.uncompressed_size() > MAX_ENTRY_SIZE {
+        return Ok(None);
+    }
+    pak.read_entry(entry).map(Some)
+}
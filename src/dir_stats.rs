@@ -0,0 +1,72 @@
+//! Output size per top-level directory under `natives/STM`, to show where the size growth of
+//! decompression comes from.
+
+use std::collections::HashMap;
+
+use indicatif::HumanBytes;
+use parking_lot::Mutex;
+
+const UNKNOWN_DIR: &str = "(unknown)";
+
+#[derive(Debug, Clone, Copy, Default)]
+struct DirTotals {
+    entries: u64,
+    bytes_in: u64,
+    bytes_out: u64,
+}
+
+#[derive(Default)]
+pub struct DirStats {
+    dirs: Mutex<HashMap<String, DirTotals>>,
+}
+
+impl DirStats {
+    /// Adds a written entry, `bytes_in` is its size in the source pak.
+    pub fn add(&self, name: Option<&str>, bytes_in: u64, bytes_out: u64) {
+        let dir = name.map(top_level_dir).unwrap_or(UNKNOWN_DIR);
+        let mut dirs = self.dirs.lock();
+        // a lookup first, a key per entry would allocate for each of them
+        if !dirs.contains_key(dir) {
+            dirs.insert(dir.to_string(), DirTotals::default());
+        }
+        let totals = dirs.get_mut(dir).unwrap();
+        totals.entries += 1;
+        totals.bytes_in += bytes_in;
+        totals.bytes_out += bytes_out;
+    }
+
+    /// Table lines, directories with the largest output first.
+    pub fn table(self) -> Vec<String> {
+        let mut dirs: Vec<(String, DirTotals)> = self.dirs.into_inner().into_iter().collect();
+        dirs.sort_by_key(|(_, totals)| std::cmp::Reverse(totals.bytes_out));
+
+        let mut lines = vec![format!(
+            "{:<24} {:>8} {:>11} {:>11} {:>7}",
+            "Directory", "Entries", "Input", "Output", "Ratio"
+        )];
+        for (dir, totals) in &dirs {
+            lines.push(format!(
+                "{:<24} {:>8} {:>11} {:>11} {:>6.2}x",
+                dir,
+                totals.entries,
+                HumanBytes(totals.bytes_in).to_string(),
+                HumanBytes(totals.bytes_out).to_string(),
+                totals.bytes_out as f64 / totals.bytes_in.max(1) as f64
+            ));
+        }
+        lines
+    }
+}
+
+/// `natives/STM/Art/Model/...` -> `Art`, the streaming dir counts as its own.
+fn top_level_dir(name: &str) -> &str {
+    let relative = name
+        .get(..12)
+        .filter(|root| root.eq_ignore_ascii_case("natives/stm/"))
+        .map(|_| &name[12..])
+        .unwrap_or(name);
+    match relative.split_once('/') {
+        Some((dir, _)) => dir,
+        None => UNKNOWN_DIR,
+    }
+}
@@ -0,0 +1,128 @@
+//! `--estimate`: a random sample of the textures is decompressed up front and the output size and
+//! runtime are extrapolated from it, so users know what a run takes before committing to it.
+
+use std::time::{Duration, Instant};
+
+use dialoguer::{Confirm, theme::ColorfulTheme};
+use indicatif::{HumanBytes, HumanDuration};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use ree_pak_core::pak::PakEntry;
+
+use crate::{i18n::Msg, names::NameTable, pak::PakFile, progress::Progress, util::SplitMix64};
+
+pub struct Estimate {
+    /// Entries decompressed for the estimate.
+    pub sampled: usize,
+    pub output_size: u64,
+    pub runtime: Duration,
+}
+
+impl Estimate {
+    pub fn summary(&self) -> String {
+        format!(
+            "Estimated from {} sampled textures: about {} of output, {} to process.",
+            self.sampled,
+            HumanBytes(self.output_size),
+            HumanDuration(self.runtime)
+        )
+    }
+}
+
+/// How many of the textures an estimate processes.
+#[derive(Debug, Clone, Copy)]
+pub enum SampleSize {
+    /// A random share of them, in percent.
+    Percent(f64),
+    /// About this many, spread over the pak.
+    Count(usize),
+}
+
+/// Processes a sample of the tex entries, at least one, and extrapolates to all `entries`.
+/// `output_size` is the size the run writes for a read entry, 0 if it leaves the entry out.
+/// Entries known not to be textures count at their size if `copies_other`, and take no time.
+pub fn sample(
+    source: &PakFile,
+    entries: &[&PakEntry],
+    names: &NameTable,
+    size: SampleSize,
+    copies_other: &dyn Fn(&PakEntry) -> bool,
+    output_size: &(dyn Fn(&PakEntry, Vec<u8>) -> eyre::Result<u64> + Sync),
+    progress: &dyn Progress,
+) -> eyre::Result<Estimate> {
+    // unknown names are told apart by content, while sampling
+    let (tex, other): (Vec<&PakEntry>, Vec<&PakEntry>) = entries
+        .iter()
+        .partition(|entry| names.is_tex_file(entry.hash()) != Some(false));
+    let other_size: u64 = other
+        .iter()
+        .filter(|entry| copies_other(entry))
+        .map(|entry| entry.uncompressed_size())
+        .sum();
+    let tex_size: u64 = tex.iter().map(|entry| entry.uncompressed_size()).sum();
+
+    let mut rng = SplitMix64::new(SplitMix64::time_seed());
+    let mut sample: Vec<&PakEntry> = match size {
+        SampleSize::Percent(percent) => {
+            let ratio = (percent / 100.0).clamp(0.0, 1.0);
+            tex.iter()
+                .copied()
+                .filter(|_| rng.next_f64() < ratio)
+                .collect()
+        }
+        // neighbouring textures tend to be alike
+        SampleSize::Count(count) => tex
+            .iter()
+            .copied()
+            .step_by(tex.len().div_ceil(count).max(1))
+            .collect(),
+    };
+    if sample.is_empty() {
+        sample.extend(tex.get(rng.next_u64() as usize % tex.len().max(1)));
+    }
+
+    progress.begin_phase("Sampling textures", Some(sample.len() as u64));
+    let started = Instant::now();
+    let (read, written) = sample
+        .par_iter()
+        .map(|&entry| -> eyre::Result<(u64, u64)> {
+            let raw = source.read_entry(entry)?;
+            let read = raw.len() as u64;
+            let written = output_size(entry, raw)?;
+            progress.inc(1);
+            Ok((read, written))
+        })
+        .try_reduce(|| (0, 0), |a, b| Ok((a.0 + b.0, a.1 + b.1)))?;
+    let elapsed = started.elapsed();
+    progress.end_phase();
+
+    // the sample ran on the same pool as the run will, its throughput carries over
+    let scale = if read == 0 {
+        1.0
+    } else {
+        tex_size as f64 / read as f64
+    };
+    Ok(Estimate {
+        sampled: sample.len(),
+        output_size: other_size + (written as f64 * scale) as u64,
+        runtime: elapsed.mul_f64(scale),
+    })
+}
+
+/// Asks whether to go on with the run after the estimate, no if the prompt fails.
+pub fn confirm() -> bool {
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(Msg::ContinueRun.text())
+        .default(true)
+        .interact()
+        .unwrap_or(false)
+}
+
+/// Parses the `--estimate` sample size, a percentage above 0 up to 100.
+pub fn parse_sample_percent(s: &str) -> Result<f64, String> {
+    match s.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if percent > 0.0 && percent <= 100.0 => Ok(percent),
+        _ => Err(format!(
+            "sample size `{s}` must be a percentage above 0 up to 100"
+        )),
+    }
+}
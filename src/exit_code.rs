@@ -0,0 +1,121 @@
+//! Process exit codes, so scripts wrapping the tool can tell failures apart.
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{cancel::Cancelled, names::NameTableError, session_lock, util};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    /// Any error without a more specific code.
+    Failure = 1,
+    // 2 is used by clap for invalid command line arguments
+    /// An input file doesn't exist.
+    InputNotFound = 3,
+    /// An input pak or the file name list couldn't be parsed.
+    InvalidInput = 4,
+    /// Processing stopped early, the output only holds the entries processed before.
+    PartialFailure = 5,
+    /// Cancelled by Ctrl+C, the conventional 128 + SIGINT.
+    Cancelled = 130,
+}
+
+impl ExitCode {
+    /// Exit code of an error that ended the run.
+    pub fn of(error: &eyre::Report) -> Self {
+        if error.downcast_ref::<Cancelled>().is_some() {
+            Self::Cancelled
+        } else if error.downcast_ref::<InputNotFound>().is_some()
+            || error
+                .downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::NotFound)
+        {
+            Self::InputNotFound
+        } else if error.downcast_ref::<InvalidInput>().is_some()
+            || error.downcast_ref::<NameTableError>().is_some()
+        {
+            Self::InvalidInput
+        } else {
+            Self::Failure
+        }
+    }
+
+    /// Exits the process. Destructors don't run, so the lock files still held are removed here.
+    pub fn exit(self) -> ! {
+        session_lock::release_all();
+        std::process::exit(self as i32)
+    }
+}
+
+/// An input file given by the user doesn't exist.
+#[derive(Debug)]
+pub struct InputNotFound {
+    what: &'static str,
+    path: PathBuf,
+    hints: Vec<String>,
+}
+
+impl InputNotFound {
+    pub fn new(what: &'static str, path: &Path) -> Self {
+        Self {
+            what,
+            path: path.to_path_buf(),
+            hints: vec![],
+        }
+    }
+
+    /// Lines printed after the error, suggesting what the user may have meant.
+    pub fn with_hints(mut self, hints: Vec<String>) -> Self {
+        self.hints = hints;
+        self
+    }
+}
+
+impl fmt::Display for InputNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} `{}` not found.",
+            self.what,
+            util::display_path(&self.path)
+        )?;
+        for hint in &self.hints {
+            write!(f, "\n{hint}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InputNotFound {}
+
+/// An input file exists, but isn't what we expected.
+#[derive(Debug)]
+pub struct InvalidInput {
+    path: PathBuf,
+    cause: String,
+}
+
+impl InvalidInput {
+    pub fn new(path: &Path, cause: impl fmt::Display) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            cause: cause.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to read `{}`: {}",
+            util::display_path(&self.path),
+            self.cause
+        )
+    }
+}
+
+impl std::error::Error for InvalidInput {}
@@ -0,0 +1,63 @@
+//! Texture families: the maps of one material (albedo, normal, roughness, masks...) share a path
+//! and differ only in a map suffix, e.g. `ch03_000_0000_ALBD.tex.241106027` and
+//! `ch03_000_0000_NRRT.tex.241106027`.
+
+use std::collections::BTreeMap;
+
+use ree_pak_core::pak::PakEntry;
+
+use crate::names::NameTable;
+
+/// Path of the family the texture belongs to (the name without map suffix and extension), and
+/// its map suffix. `None` if the name has no map suffix.
+pub fn split(name: &str) -> Option<(&str, &str)> {
+    let stem = &name[..name.find(".tex").unwrap_or(name.len())];
+    let (base, suffix) = stem.rsplit_once('_')?;
+    let is_map_suffix = (2..=5).contains(&suffix.len())
+        && suffix
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && suffix.chars().any(|c| c.is_ascii_uppercase());
+    is_map_suffix.then_some((base, suffix))
+}
+
+/// Family path of a texture, the name itself if it has no map suffix.
+pub fn key(name: &str) -> &str {
+    split(name).map(|(base, _)| base).unwrap_or(name)
+}
+
+/// Entries of the same family as the entry with `hash`, the entry itself included.
+pub fn members<'a>(entries: &'a [PakEntry], hash: u64, names: &NameTable) -> Vec<&'a PakEntry> {
+    let Some(name) = names.get_name(hash) else {
+        return entries
+            .iter()
+            .filter(|entry| entry.hash() == hash)
+            .collect();
+    };
+    let family = key(&name);
+    entries
+        .iter()
+        .filter(|entry| {
+            names
+                .get_name(entry.hash())
+                .is_some_and(|other| key(&other) == family)
+        })
+        .collect()
+}
+
+/// Groups `(hash, name)` entries by family, families and their members sorted by name.
+pub fn group(
+    entries: impl IntoIterator<Item = (u64, String)>,
+) -> BTreeMap<String, Vec<(u64, String)>> {
+    let mut families: BTreeMap<String, Vec<(u64, String)>> = BTreeMap::new();
+    for (hash, name) in entries {
+        families
+            .entry(key(&name).to_string())
+            .or_default()
+            .push((hash, name));
+    }
+    for members in families.values_mut() {
+        members.sort_by(|a, b| a.1.cmp(&b.1));
+    }
+    families
+}
@@ -0,0 +1,106 @@
+//! Zip layout installed by Fluffy Mod Manager: a folder named after the mod holding the paks, a
+//! `modinfo.ini` and an optional screenshot.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+/// What the mod manager shows about the mod.
+#[derive(Debug, Clone, Default)]
+pub struct ModInfo {
+    /// Empty for the output file name.
+    pub name: String,
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub screenshot: Option<PathBuf>,
+}
+
+impl ModInfo {
+    fn ini(&self, screenshot_name: Option<&str>) -> String {
+        let mut ini = format!("name={}\n", self.name);
+        let mut field = |key: &str, value: Option<&str>| {
+            if let Some(value) = value {
+                // one line per key
+                ini.push_str(&format!("{key}={}\n", value.replace(['\r', '\n'], " ")));
+            }
+        };
+        field("version", self.version.as_deref());
+        field("description", self.description.as_deref());
+        field("author", self.author.as_deref());
+        field("screenshot", screenshot_name);
+        ini
+    }
+}
+
+fn zip_path(output_path: &Path, info: &ModInfo) -> PathBuf {
+    output_path.with_file_name(format!("{}.zip", sanitize(&info.name)))
+}
+
+/// Writes the zip with all output parts, returns its path.
+pub fn package(output_paths: &[PathBuf], info: &ModInfo) -> eyre::Result<PathBuf> {
+    let info = &ModInfo {
+        name: match info.name.trim() {
+            "" => output_paths[0]
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            name => name.to_string(),
+        },
+        ..info.clone()
+    };
+    let zip_path = zip_path(&output_paths[0], info);
+    let folder = sanitize(&info.name);
+    // the paks don't get smaller by much, and deflating GBs takes minutes
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    let mut zip = ZipWriter::new(fs::File::create(&zip_path)?);
+    let screenshot_name = match &info.screenshot {
+        Some(path) => {
+            let extension = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "png".to_string());
+            let name = format!("screenshot.{extension}");
+            zip.start_file(format!("{folder}/{name}"), options)?;
+            zip.write_all(&fs::read(path)?)?;
+            Some(name)
+        }
+        None => None,
+    };
+    zip.start_file(format!("{folder}/modinfo.ini"), options)?;
+    zip.write_all(info.ini(screenshot_name.as_deref()).as_bytes())?;
+
+    for path in output_paths {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        zip.start_file(format!("{folder}/{file_name}"), options)?;
+        io::copy(&mut fs::File::open(path)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(zip_path)
+}
+
+/// Mod name usable as a folder and file name.
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match name.trim().trim_end_matches('.') {
+        "" => "mod".to_string(),
+        name => name.to_string(),
+    }
+}
@@ -0,0 +1,106 @@
+//! Free space preflight: the output size is estimated before processing starts, so a full drive
+//! fails the run right away instead of hours in with a write error.
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use indicatif::HumanBytes;
+
+use crate::{progress::Progress, util};
+
+/// Textures processed to estimate the output size, when `--estimate` didn't already.
+pub const SAMPLE_SIZE: usize = 32;
+
+/// The output doesn't fit on the drive it goes to.
+#[derive(Debug)]
+pub struct NotEnoughSpace {
+    pub dir: PathBuf,
+    pub needed: u64,
+    pub available: u64,
+}
+
+impl fmt::Display for NotEnoughSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the output needs about {}, but only {} is free on the drive of `{}`. Free up space or write the output to another drive with `--output`, `--skip-space-check` runs anyway.",
+            HumanBytes(self.needed),
+            HumanBytes(self.available),
+            util::display_path(&self.dir)
+        )
+    }
+}
+
+impl std::error::Error for NotEnoughSpace {}
+
+/// Fails with [`NotEnoughSpace`] if `needed` bytes don't fit on the drive of `output_path`.
+pub fn check(output_path: &Path, needed: u64, progress: &dyn Progress) -> eyre::Result<()> {
+    let dir = output_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let available = match available(dir) {
+        Ok(available) => available,
+        Err(e) => {
+            progress.warn(&format!(
+                "Failed to query the free space of `{}`, the output may not fit: {e}",
+                util::display_path(dir)
+            ));
+            return Ok(());
+        }
+    };
+    progress.info(&format!(
+        "The output needs about {}, {} is free.",
+        HumanBytes(needed),
+        HumanBytes(available)
+    ));
+    if needed > available {
+        return Err(NotEnoughSpace {
+            dir: dir.to_path_buf(),
+            needed,
+            available,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Bytes free for the current user on the drive of `dir`.
+#[cfg(windows)]
+fn available(dir: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let dir: Vec<u16> = dir.as_os_str().encode_wide().chain([0]).collect();
+    let mut available = 0;
+    // SAFETY: `dir` is NUL-terminated, the totals we don't need may be null
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            dir.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+/// Bytes free for unprivileged users on the file system of `dir`.
+#[cfg(unix)]
+fn available(dir: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let dir = CString::new(dir.as_os_str().as_bytes())?;
+    // SAFETY: plain syscall filling the zeroed struct, `dir` is NUL-terminated
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(dir.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
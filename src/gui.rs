@@ -0,0 +1,509 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+};
+
+use eframe::egui;
+use indicatif::HumanBytes;
+use parking_lot::Mutex;
+
+use crate::{
+    cancel::{self, Cancelled},
+    category::Category,
+    cli::NameTableArgs,
+    compression::OutputCompression,
+    config::Config,
+    i18n::Msg,
+    names, open_files,
+    pipeline::{self, Options},
+    process::TexTransform,
+    progress::Progress,
+    retry,
+    stats::StatsSnapshot,
+    storage::StorageKind,
+    transcode::{TranscodeFormat, TranscodeQuality},
+    update, util,
+};
+
+pub fn run(name_args: &NameTableArgs) -> eyre::Result<()> {
+    let native_options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([720.0, 640.0]),
+        ..Default::default()
+    };
+    let name_args = name_args.clone();
+    eframe::run_native(
+        "MHWs Tex Decompressor",
+        native_options,
+        Box::new(move |cc| Ok(Box::new(GuiApp::new(cc, name_args)))),
+    )
+    .map_err(|e| eyre::eyre!("Failed to start GUI: {e}"))
+}
+
+/// Lines kept in the log pane, the oldest are dropped first.
+const MAX_LOG_LINES: usize = 20_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LogLevel {
+    Detail,
+    Info,
+    Warning,
+}
+
+impl LogLevel {
+    const ALL: [Self; 3] = [Self::Warning, Self::Info, Self::Detail];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Detail => "Everything",
+            Self::Info => "Info and warnings",
+            Self::Warning => "Warnings only",
+        }
+    }
+}
+
+struct LogLine {
+    level: LogLevel,
+    text: String,
+}
+
+/// Progress of the running job, shared between the worker thread and the UI.
+struct GuiProgress {
+    ctx: egui::Context,
+    log: Mutex<VecDeque<LogLine>>,
+    phase: Mutex<String>,
+    /// 0 if the current phase has no known length.
+    total: AtomicU64,
+    position: AtomicU64,
+    stats: Mutex<StatsSnapshot>,
+    running: AtomicBool,
+}
+
+impl GuiProgress {
+    fn push_log(&self, level: LogLevel, text: String) {
+        let mut log = self.log.lock();
+        if log.len() == MAX_LOG_LINES {
+            log.pop_front();
+        }
+        log.push_back(LogLine { level, text });
+        self.ctx.request_repaint();
+    }
+}
+
+impl Progress for GuiProgress {
+    fn info(&self, message: &str) {
+        self.push_log(LogLevel::Info, message.to_string());
+    }
+
+    fn warn(&self, message: &str) {
+        self.push_log(LogLevel::Warning, message.to_string());
+    }
+
+    fn wants_details(&self) -> bool {
+        true
+    }
+
+    fn detail(&self, message: &str) {
+        self.push_log(LogLevel::Detail, message.to_string());
+    }
+
+    fn begin_phase(&self, message: &str, total: Option<u64>) {
+        *self.phase.lock() = message.to_string();
+        self.total.store(total.unwrap_or(0), Ordering::Relaxed);
+        self.position.store(0, Ordering::Relaxed);
+        self.ctx.request_repaint();
+    }
+
+    fn inc(&self, delta: u64) {
+        self.position.fetch_add(delta, Ordering::Relaxed);
+        self.ctx.request_repaint();
+    }
+
+    fn update_stats(&self, stats: &StatsSnapshot) {
+        *self.stats.lock() = *stats;
+        self.ctx.request_repaint();
+    }
+
+    fn end_phase(&self) {
+        self.phase.lock().clear();
+        self.total.store(0, Ordering::Relaxed);
+        self.ctx.request_repaint();
+    }
+}
+
+struct GuiApp {
+    name_args: NameTableArgs,
+    input: String,
+    full_package: bool,
+    feature_clone: bool,
+    categories: [bool; Category::ALL.len()],
+    previous_output: String,
+    thread_count: usize,
+    /// Read-ahead depth `bench` found fastest, `None` if it didn't save one.
+    read_ahead: Option<usize>,
+    split_size: String,
+    verify_sample: f64,
+    drop_top_mips: u8,
+    min_mip_size: u16,
+    transcode: Option<TranscodeFormat>,
+    content_index: bool,
+    manifest: bool,
+    /// Least important log lines shown in the log pane.
+    log_level: LogLevel,
+    progress: Arc<GuiProgress>,
+}
+
+impl GuiApp {
+    fn new(cc: &eframe::CreationContext<'_>, name_args: NameTableArgs) -> Self {
+        // the settings `bench` saved, as the command line uses them
+        let config = Config::load();
+        Self {
+            name_args,
+            input: String::new(),
+            full_package: false,
+            feature_clone: true,
+            categories: [true; Category::ALL.len()],
+            previous_output: String::new(),
+            thread_count: config.default_thread_count(),
+            read_ahead: config.read_ahead,
+            split_size: String::new(),
+            verify_sample: 0.0,
+            drop_top_mips: 0,
+            min_mip_size: 0,
+            transcode: None,
+            content_index: false,
+            manifest: false,
+            log_level: LogLevel::Info,
+            progress: Arc::new(GuiProgress {
+                ctx: cc.egui_ctx.clone(),
+                log: Mutex::new(VecDeque::new()),
+                phase: Mutex::new(String::new()),
+                total: AtomicU64::new(0),
+                position: AtomicU64::new(0),
+                stats: Mutex::new(StatsSnapshot::default()),
+                running: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    fn options(&self) -> eyre::Result<Options> {
+        let previous_output = self.previous_output.trim();
+        let split_size = self.split_size.trim();
+
+        Ok(Options {
+            input_path: util::user_path(&self.input),
+            output: None,
+            name_template: None,
+            full_package: self.full_package,
+            feature_clone: self.feature_clone,
+            keep_compression: false,
+            compression: OutputCompression::default(),
+            categories: Category::ALL
+                .iter()
+                .zip(self.categories)
+                .filter(|(_, selected)| *selected)
+                .map(|(category, _)| *category)
+                .collect(),
+            formats: vec![],
+            previous_output: (!previous_output.is_empty())
+                .then(|| util::user_path(previous_output)),
+            thread_count: self.thread_count,
+            split_by_category: false,
+            split_size: if split_size.is_empty() {
+                None
+            } else {
+                Some(util::parse_size(split_size).map_err(|e| eyre::eyre!(e))?)
+            },
+            verify_sample: self.verify_sample,
+            verify_seed: None,
+            estimate: None,
+            confirm_estimate: false,
+            skip_larger_than: None,
+            skip_smaller_than: None,
+            min_entry_size: None,
+            max_entry_size: None,
+            tex_transform: TexTransform {
+                drop_top_mips: self.drop_top_mips,
+                min_mip_size: self.min_mip_size,
+                transcode: self.transcode,
+                transcode_quality: TranscodeQuality::default(),
+                header_fast_path: false,
+            },
+            content_index: self.content_index,
+            copy_paths: vec![],
+            non_tex_list: None,
+            manifest: self.manifest,
+            sign_key: None,
+            variants: vec![],
+            embed_info: false,
+            top_impact: None,
+            pak_version: None,
+            pak_features: None,
+            read_ahead: self.read_ahead,
+            storage: StorageKind::Fs,
+            async_io: false,
+            report_duplicates: false,
+            max_open_files: open_files::DEFAULT_LIMIT,
+            align: None,
+            deterministic: false,
+            retries: retry::DEFAULT_RETRIES,
+            low_priority: false,
+            io_throttle: None,
+            unknown_list: None,
+            names_dump: None,
+            allow_processed_input: false,
+            check_free_space: true,
+            overrides: None,
+            script: None,
+            // asked for when starting
+            force: false,
+            picked_entries: None,
+            only_entries: None,
+            shard: None,
+            fluffy: None,
+        })
+    }
+
+    fn start(&mut self) {
+        let progress = Arc::clone(&self.progress);
+        progress.log.lock().clear();
+        *progress.stats.lock() = StatsSnapshot::default();
+
+        let mut options = match self.options() {
+            Ok(options) => options,
+            Err(e) => {
+                progress.warn(&format!("Error: {e}"));
+                return;
+            }
+        };
+        if options.overwrites_output() {
+            let overwrite = rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Overwrite output")
+                .set_description(
+                    Msg::OverwriteOutput
+                        .with(&[("path", &util::display_path(&options.output_path()))]),
+                )
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if overwrite != rfd::MessageDialogResult::Yes {
+                return;
+            }
+            options.force = true;
+        }
+        let name_args = self.name_args.clone();
+
+        cancel::reset();
+        progress.running.store(true, Ordering::SeqCst);
+        thread::spawn(move || {
+            let result = names::load_table(&name_args, &*progress)
+                .and_then(|name_table| pipeline::run(&options, &name_table, &*progress));
+            match result {
+                Ok(summary) => {
+                    match &summary.processing_error {
+                        Some(e) if e.downcast_ref::<Cancelled>().is_some() => progress.warn(
+                            "Cancelled, the output holds the entries processed so far. Give it as the previous output to continue where it stopped.",
+                        ),
+                        Some(e) => {
+                            progress.warn(&format!("Error occurred when processing tex: {e:#}"));
+                            progress.warn(
+                                "The process terminated early, the output holds the entries processed so far.",
+                            );
+                        }
+                        None => {}
+                    }
+                    progress.info(&format!(
+                        "Wrote {} entries, {}.",
+                        summary.entries_written,
+                        HumanBytes(summary.bytes_written)
+                    ));
+                    for path in &summary.output_paths {
+                        progress.info(&format!("Output: {}", util::display_path(path)));
+                    }
+                    progress.info("Done!");
+                }
+                Err(e) => progress.warn(&format!("Error: {e}")),
+            }
+            progress.running.store(false, Ordering::SeqCst);
+            progress.ctx.request_repaint();
+        });
+    }
+}
+
+/// Logs whether a newer release is out, the download is left to the release page.
+fn check_update(progress: Arc<GuiProgress>) {
+    thread::spawn(move || match update::latest_release() {
+        Ok(release) if release.is_newer() => progress
+            .warn(&Msg::UpdateAvailable.with(&[("tag", &release.tag), ("url", &release.page_url)])),
+        Ok(_) => progress.info(&Msg::UpToDate.with(&[("version", env!("CARGO_PKG_VERSION"))])),
+        Err(e) => progress.warn(&format!("Failed to check for updates: {e}")),
+    });
+}
+
+fn path_input(ui: &mut egui::Ui, label: &str, value: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.text_edit_singleline(value);
+        let picked = ui
+            .button("Browse...")
+            .clicked()
+            .then(|| {
+                rfd::FileDialog::new()
+                    .add_filter("pak", &["pak"])
+                    .pick_file()
+            })
+            .flatten();
+        if let Some(path) = picked {
+            *value = path.to_string_lossy().to_string();
+        }
+    });
+}
+
+impl eframe::App for GuiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let running = self.progress.running.load(Ordering::SeqCst);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading(format!(
+                    "MHWs Tex Decompressor v{}",
+                    env!("CARGO_PKG_VERSION")
+                ));
+                if ui.button("Check for updates").clicked() {
+                    check_update(Arc::clone(&self.progress));
+                }
+            });
+            ui.add_space(8.0);
+
+            ui.add_enabled_ui(!running, |ui| {
+                path_input(ui, "Input .pak file", &mut self.input);
+                ui.checkbox(
+                    &mut self.full_package,
+                    "Package all files, including non-tex files (for replacing original files)",
+                );
+                ui.checkbox(
+                    &mut self.feature_clone,
+                    "Clone feature flags from original file",
+                );
+                if !self.full_package {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label("Categories:");
+                        for (category, selected) in Category::ALL.iter().zip(&mut self.categories) {
+                            ui.checkbox(selected, category.label());
+                        }
+                    });
+                }
+                path_input(ui, "Previous output (optional)", &mut self.previous_output);
+                ui.horizontal(|ui| {
+                    ui.label("Threads");
+                    ui.add(egui::DragValue::new(&mut self.thread_count).range(1..=256));
+                    ui.label("Split size (optional)");
+                    ui.add(egui::TextEdit::singleline(&mut self.split_size).hint_text("20G"));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Verify sample %");
+                    ui.add(egui::Slider::new(&mut self.verify_sample, 0.0..=100.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Drop top mips");
+                    ui.add(egui::DragValue::new(&mut self.drop_top_mips).range(0..=8));
+                    ui.label("Min mip size");
+                    ui.add(egui::DragValue::new(&mut self.min_mip_size).range(0..=4096));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Transcode BC7 to");
+                    egui::ComboBox::from_id_salt("transcode")
+                        .selected_text(match self.transcode {
+                            Some(format) => format.to_string().to_uppercase(),
+                            None => "Keep BC7".to_string(),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.transcode, None, "Keep BC7");
+                            ui.selectable_value(
+                                &mut self.transcode,
+                                Some(TranscodeFormat::Bc1),
+                                "BC1",
+                            );
+                            ui.selectable_value(
+                                &mut self.transcode,
+                                Some(TranscodeFormat::Bc3),
+                                "BC3",
+                            );
+                        });
+                });
+                ui.checkbox(
+                    &mut self.content_index,
+                    "Write content index (.cidx) for other tools",
+                );
+                ui.checkbox(
+                    &mut self.manifest,
+                    "Write checksum manifest for distribution",
+                );
+                if ui.button("Start").clicked() {
+                    self.start();
+                }
+            });
+            if running && ui.button("Cancel").clicked() {
+                cancel::request();
+            }
+
+            ui.separator();
+            let phase = self.progress.phase.lock().clone();
+            if !phase.is_empty() {
+                let total = self.progress.total.load(Ordering::Relaxed);
+                let position = self.progress.position.load(Ordering::Relaxed);
+                let stats = *self.progress.stats.lock();
+                if total > 0 {
+                    ui.add(
+                        egui::ProgressBar::new(position as f32 / total as f32).text(format!(
+                            "{phase} {position}/{total}, written {} ({}/s)",
+                            HumanBytes(stats.bytes_out),
+                            HumanBytes(stats.write_rate())
+                        )),
+                    );
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(phase);
+                    });
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Log");
+                egui::ComboBox::from_id_salt("log_level")
+                    .selected_text(self.log_level.label())
+                    .show_ui(ui, |ui| {
+                        for level in LogLevel::ALL {
+                            ui.selectable_value(&mut self.log_level, level, level.label());
+                        }
+                    });
+            });
+            // a run logs a line per entry, only the rows in view are laid out, one line each
+            let log = self.progress.log.lock();
+            let shown: Vec<&LogLine> = log
+                .iter()
+                .filter(|line| line.level >= self.log_level)
+                .collect();
+            let row_height = ui.text_style_height(&egui::TextStyle::Body);
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                    for line in &shown[rows] {
+                        let text = egui::RichText::new(&line.text);
+                        let text = match line.level {
+                            LogLevel::Warning => text.color(egui::Color32::YELLOW),
+                            LogLevel::Info => text,
+                            LogLevel::Detail => text.weak(),
+                        };
+                        ui.add(egui::Label::new(text).truncate());
+                    }
+                });
+        });
+    }
+}
@@ -0,0 +1,96 @@
+//! Processing per file type. An entry goes to the handler registered for the extension and version
+//! of its name, or for its magic if the name is unknown. Supporting another file type only takes a
+//! handler in [`HANDLERS`], entries without one are copied as is.
+
+use std::io::Cursor;
+
+use re_tex::tex::Tex;
+
+use crate::{
+    names::NameTable,
+    process::TexTransform,
+    tex_header::{self, TEX_MAGIC},
+    transcode,
+};
+
+pub trait EntryHandler: Sync {
+    /// Extension of the handled files, without the version suffix.
+    fn extension(&self) -> &'static str;
+    /// Versions of the file format handled, the numeric suffix of the file names.
+    fn versions(&self) -> &'static [u32];
+    /// First bytes of the handled files, to recognize entries of unknown name.
+    fn magic(&self) -> &'static [u8];
+    /// Transforms the raw data of a source entry into the data written to the output pak.
+    fn process(&self, data: Vec<u8>, transform: &TexTransform) -> eyre::Result<Vec<u8>>;
+}
+
+static HANDLERS: &[&dyn EntryHandler] = &[&TexHandler];
+
+/// The handler of the entry, by its name if the table knows it, by its content otherwise.
+pub fn find(names: &NameTable, hash: u64, data: &[u8]) -> Option<&'static dyn EntryHandler> {
+    match names.get_name(hash) {
+        Some(name) => {
+            let (extension, version) = split_extension(name)?;
+            HANDLERS
+                .iter()
+                .find(|handler| {
+                    handler.extension() == extension && handler.versions().contains(&version)
+                })
+                .copied()
+        }
+        // like `is_tex_entry`, a loaded table not knowing the name rules out the known formats
+        None if names.is_loaded() => None,
+        None => HANDLERS
+            .iter()
+            .find(|handler| data.starts_with(handler.magic()))
+            .copied(),
+    }
+}
+
+/// Extension and version of a file name like `foo.tex.241106027`.
+fn split_extension(name: &str) -> Option<(&str, u32)> {
+    let (rest, version) = name.rsplit_once('.')?;
+    let (_, extension) = rest.rsplit_once('.')?;
+    Some((extension, version.parse().ok()?))
+}
+
+/// Textures: the mips are decompressed, then the tex transform of the run is applied.
+pub struct TexHandler;
+
+impl EntryHandler for TexHandler {
+    fn extension(&self) -> &'static str {
+        "tex"
+    }
+
+    fn versions(&self) -> &'static [u32] {
+        &[241106027]
+    }
+
+    fn magic(&self) -> &'static [u8] {
+        TEX_MAGIC
+    }
+
+    fn process(&self, data: Vec<u8>, transform: &TexTransform) -> eyre::Result<Vec<u8>> {
+        let fast_path = if transform.header_fast_path {
+            tex_header::rewrite_uncompressed(&data)?
+        } else {
+            None
+        };
+        let mut data = match fast_path {
+            Some(data) => data,
+            None => {
+                let mut tex = Tex::from_reader(&mut Cursor::new(data))?;
+                // decompress mipmaps
+                tex.batch_decompress()?;
+                tex.as_bytes()?
+            }
+        };
+        if transform.trims_mips() {
+            data = tex_header::trim_mips(&data, transform.drop_top_mips, transform.min_mip_size)?;
+        }
+        if let Some(format) = transform.transcode {
+            data = transcode::transcode(&data, format, transform.transcode_quality)?;
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,248 @@
+//! Translations of the interactive flow: prompts and the messages around them. Many users follow
+//! localized guides, the prompts they see should match the guide.
+//!
+//! Messages are plain tables, `{name}` placeholders are filled in with [`Msg::with`]. Errors,
+//! warnings and the command line help stay in English, they're what users paste into bug reports.
+
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    En,
+    #[value(name = "zh-cn")]
+    ZhCn,
+    Ja,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Sets the language for the run, `None` to follow the system locale.
+pub fn init(lang: Option<Lang>) {
+    let _ = LANG.set(lang.unwrap_or_else(system_lang));
+}
+
+fn lang() -> Lang {
+    *LANG.get().unwrap_or(&Lang::En)
+}
+
+fn from_locale(locale: &str) -> Option<Lang> {
+    let locale = locale.to_ascii_lowercase();
+    if locale.starts_with("zh") {
+        Some(Lang::ZhCn)
+    } else if locale.starts_with("ja") {
+        Some(Lang::Ja)
+    } else if locale.starts_with("en") {
+        Some(Lang::En)
+    } else {
+        None
+    }
+}
+
+#[cfg(windows)]
+fn system_lang() -> Lang {
+    use winreg::{RegKey, enums::HKEY_CURRENT_USER};
+
+    RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(r"Control Panel\International")
+        .and_then(|key| key.get_value::<String, _>("LocaleName"))
+        .ok()
+        .and_then(|locale| from_locale(&locale))
+        .unwrap_or(Lang::En)
+}
+
+#[cfg(not(windows))]
+fn system_lang() -> Lang {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|locale| !locale.is_empty())
+        .and_then(|locale| from_locale(&locale))
+        .unwrap_or(Lang::En)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    False,
+    True,
+    InputFile,
+    InputPakPath,
+    SelectDetectedPak,
+    EnterPathManually,
+    OutputMayFail,
+    UiTexturesOnly,
+    UseDefaultOptions,
+    FullPackage,
+    CloneFeatureFlags,
+    Categories,
+    PreviousOutput,
+    ThreadCount,
+    OverwriteOutput,
+    Wrote,
+    SplitInto,
+    Done,
+    RenameHint,
+    PressEnterToExit,
+    ContinueRun,
+    OtherOutputFolder,
+    WriteDiagnostics,
+    DiagnosticsWritten,
+    UpToDate,
+    UpdateAvailable,
+    DownloadUpdate,
+    UpdateDownloaded,
+}
+
+impl Msg {
+    pub fn text(self) -> &'static str {
+        match lang() {
+            Lang::En => self.en(),
+            Lang::ZhCn => self.zh_cn(),
+            Lang::Ja => self.ja(),
+        }
+    }
+
+    /// The text with its `{name}` placeholders replaced.
+    pub fn with(self, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.text().to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+
+    fn en(self) -> &'static str {
+        match self {
+            Self::False => "False",
+            Self::True => "True",
+            Self::InputFile => "Input file: {path}",
+            Self::InputPakPath => "Input .pak file path",
+            Self::SelectDetectedPak => "Found Monster Hunter Wilds install, select input .pak file",
+            Self::EnterPathManually => "Enter path manually",
+            Self::OutputMayFail => "{concern}, the output may fail to write",
+            Self::UiTexturesOnly => {
+                "Only decompress the UI textures, to fix a blurry HUD and menus?"
+            }
+            Self::UseDefaultOptions => "Use default options?",
+            Self::FullPackage => {
+                "Package all files, including non-tex files (for replacing original files)"
+            }
+            Self::CloneFeatureFlags => "Clone feature flags from original file?",
+            Self::Categories => "Texture categories to include (space to toggle, enter to confirm)",
+            Self::PreviousOutput => {
+                "Previous output pak for incremental update (leave empty to process all)"
+            }
+            Self::ThreadCount => "Number of threads to use (default: {count})",
+            Self::OverwriteOutput => "`{path}` already exists, overwrite it?",
+            Self::Wrote => "Wrote {count} entries, {size}.",
+            Self::SplitInto => "Output was split into {count} parts:",
+            Self::Done => "Done!",
+            Self::RenameHint => {
+                "You should rename the output file like `re_chunk_000.pak.sub_000.pak.patch_xxx.pak`, or manage it by your favorite mod manager."
+            }
+            Self::PressEnterToExit => "Press Enter to exit",
+            Self::ContinueRun => "Continue?",
+            Self::OtherOutputFolder => {
+                "The output needs about {needed}, but only {available} is free. Folder on another drive to write it to (empty to stop)"
+            }
+            Self::WriteDiagnostics => {
+                "The run failed, write `{path}` with the log and error details to attach to a bug report?"
+            }
+            Self::DiagnosticsWritten => "Wrote `{path}`, attach it to your bug report.",
+            Self::UpToDate => "v{version} is the latest version.",
+            Self::UpdateAvailable => "{tag} is available: {url}",
+            Self::DownloadUpdate => "Download `{name}` next to this executable?",
+            Self::UpdateDownloaded => {
+                "Downloaded `{path}`, close the tool and use it instead of this executable."
+            }
+        }
+    }
+
+    fn zh_cn(self) -> &'static str {
+        match self {
+            Self::False => "否",
+            Self::True => "是",
+            Self::InputFile => "输入文件：{path}",
+            Self::InputPakPath => "输入 .pak 文件路径",
+            Self::SelectDetectedPak => "已找到怪物猎人荒野的安装目录，请选择输入的 .pak 文件",
+            Self::EnterPathManually => "手动输入路径",
+            Self::OutputMayFail => "{concern}，输出文件可能无法写入",
+            Self::UiTexturesOnly => "仅解压 UI 贴图，修复模糊的 HUD 和菜单？",
+            Self::UseDefaultOptions => "使用默认选项？",
+            Self::FullPackage => "打包所有文件，包括非 tex 文件（用于替换原始文件）",
+            Self::CloneFeatureFlags => "是否从原文件复制特性标志？",
+            Self::Categories => "要包含的贴图类别（空格切换，回车确认）",
+            Self::PreviousOutput => "用于增量更新的上一次输出 pak（留空则全部处理）",
+            Self::ThreadCount => "使用的线程数（默认：{count}）",
+            Self::OverwriteOutput => "`{path}` 已存在，是否覆盖？",
+            Self::Wrote => "已写入 {count} 个条目，{size}。",
+            Self::SplitInto => "输出已拆分为 {count} 个部分：",
+            Self::Done => "完成！",
+            Self::RenameHint => {
+                "请将输出文件重命名为类似 `re_chunk_000.pak.sub_000.pak.patch_xxx.pak` 的名称，或使用你喜欢的模组管理器管理。"
+            }
+            Self::PressEnterToExit => "按回车键退出",
+            Self::ContinueRun => "是否继续？",
+            Self::OtherOutputFolder => {
+                "输出约需 {needed}，但仅剩 {available} 可用空间。请输入其他磁盘上的输出文件夹（留空则停止）"
+            }
+            Self::WriteDiagnostics => {
+                "运行失败，是否写入包含日志和错误详情的 `{path}`，以便附加到问题报告中？"
+            }
+            Self::DiagnosticsWritten => "已写入 `{path}`，请将其附加到你的问题报告中。",
+            Self::UpToDate => "v{version} 已是最新版本。",
+            Self::UpdateAvailable => "有新版本 {tag}：{url}",
+            Self::DownloadUpdate => "是否将 `{name}` 下载到本程序所在目录？",
+            Self::UpdateDownloaded => "已下载 `{path}`，请关闭本工具并改用新版本。",
+        }
+    }
+
+    fn ja(self) -> &'static str {
+        match self {
+            Self::False => "いいえ",
+            Self::True => "はい",
+            Self::InputFile => "入力ファイル：{path}",
+            Self::InputPakPath => "入力 .pak ファイルのパス",
+            Self::SelectDetectedPak => {
+                "モンスターハンターワイルズのインストールが見つかりました。入力 .pak ファイルを選択してください"
+            }
+            Self::EnterPathManually => "パスを手動で入力",
+            Self::OutputMayFail => "{concern}、出力を書き込めない可能性があります",
+            Self::UiTexturesOnly => {
+                "UI テクスチャのみ展開して、ぼやけた HUD とメニューを直しますか？"
+            }
+            Self::UseDefaultOptions => "デフォルトの設定を使用しますか？",
+            Self::FullPackage => {
+                "tex 以外のファイルも含めてすべてパッケージ化する（元のファイルの置き換え用）"
+            }
+            Self::CloneFeatureFlags => "元のファイルから機能フラグをコピーしますか？",
+            Self::Categories => "含めるテクスチャのカテゴリ（スペースで切り替え、Enter で確定）",
+            Self::PreviousOutput => "差分更新に使う前回の出力 pak（空欄ですべて処理）",
+            Self::ThreadCount => "使用するスレッド数（デフォルト：{count}）",
+            Self::OverwriteOutput => "`{path}` は既に存在します。上書きしますか？",
+            Self::Wrote => "{count} 件のエントリ、{size} を書き込みました。",
+            Self::SplitInto => "出力は {count} 個に分割されました：",
+            Self::Done => "完了！",
+            Self::RenameHint => {
+                "出力ファイルを `re_chunk_000.pak.sub_000.pak.patch_xxx.pak` のような名前に変更するか、お好みの MOD マネージャーで管理してください。"
+            }
+            Self::PressEnterToExit => "Enter キーを押して終了",
+            Self::ContinueRun => "続行しますか？",
+            Self::OtherOutputFolder => {
+                "出力には約 {needed} 必要ですが、空き容量は {available} しかありません。出力先にする別のドライブのフォルダ（空欄で中止）"
+            }
+            Self::WriteDiagnostics => {
+                "実行に失敗しました。ログとエラーの詳細を含む `{path}` を書き出して、バグ報告に添付しますか？"
+            }
+            Self::DiagnosticsWritten => "`{path}` を書き出しました。バグ報告に添付してください。",
+            Self::UpToDate => "v{version} は最新バージョンです。",
+            Self::UpdateAvailable => "新しいバージョン {tag} があります：{url}",
+            Self::DownloadUpdate => {
+                "`{name}` をこの実行ファイルと同じフォルダーにダウンロードしますか？"
+            }
+            Self::UpdateDownloaded => {
+                "`{path}` をダウンロードしました。ツールを閉じて、新しいバージョンを使用してください。"
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Heuristic streaming cost of a texture, i.e. how much decompressing it in game contributes to
+//! stutter. Large textures in a block format that is slow to decode score highest, weighted by
+//! how often textures of their category stream in while playing.
+
+use ree_pak_core::pak::PakEntry;
+
+use crate::{
+    category::Category, names::NameTable, pak::PakFile, process::is_tex_entry,
+    tex_header::TexHeader,
+};
+
+/// Streaming cost of the entry, `None` if it isn't a tex file.
+pub fn score_entry(
+    source: &PakFile,
+    entry: &PakEntry,
+    names: &NameTable,
+) -> eyre::Result<Option<f64>> {
+    let raw = source.read_entry(entry)?;
+    if !is_tex_entry(names, entry.hash(), &raw) {
+        return Ok(None);
+    }
+    let Ok(header) = TexHeader::parse(&raw) else {
+        return Ok(None);
+    };
+    let category = names
+        .get_name(entry.hash())
+        .map(|name| Category::of(&name))
+        .unwrap_or(Category::Other);
+    Ok(Some(score(&header, category)))
+}
+
+pub fn score(header: &TexHeader, category: Category) -> f64 {
+    let size_mib = header.decompressed_size() as f64 / (1 << 20) as f64;
+    size_mib * format_weight(header.format) * category_weight(category)
+}
+
+fn format_weight(format: u32) -> f64 {
+    match format {
+        // BC6H, BC7
+        94..=99 => 1.0,
+        // BC1 - BC5
+        70..=84 => 0.8,
+        // uncompressed, already paid for by their size
+        _ => 0.6,
+    }
+}
+
+fn category_weight(category: Category) -> f64 {
+    match category {
+        // streamed in constantly while moving through a stage
+        Category::Environment => 1.5,
+        Category::Monster => 1.3,
+        Category::Character => 1.2,
+        Category::Effect => 1.1,
+        Category::Other => 1.0,
+        Category::Item => 0.8,
+        Category::Ui => 0.6,
+    }
+}
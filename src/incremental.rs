@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use ree_pak_core::pak::PakEntry;
+
+use crate::{pak::PakFile, process::TexTransform};
+
+const SOURCE_INDEX_HEADER: &str = "# mhws-tex-decompressor source index v1";
+const TRANSFORM_PREFIX: &str = "# transform:";
+const SOURCE_PREFIX: &str = "# source:";
+
+/// Identity of a source entry, used to decide whether it changed between game updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceRecord {
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub checksum: u64,
+}
+
+impl SourceRecord {
+    pub fn from_entry(entry: &PakEntry) -> Self {
+        Self {
+            compressed_size: entry.compressed_size(),
+            uncompressed_size: entry.uncompressed_size(),
+            checksum: entry.checksum(),
+        }
+    }
+}
+
+/// Size and entry table SHA-256 of the source pak an output was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFingerprint {
+    pub size: u64,
+    pub toc_sha256: String,
+}
+
+/// Sidecar file written next to every output, recording which source entries it was built from.
+#[derive(Debug, Default)]
+pub struct SourceIndex {
+    records: HashMap<u64, SourceRecord>,
+    /// [`TexTransform`] the output was generated with, empty for plain decompression.
+    transform: String,
+    source: Option<SourceFingerprint>,
+}
+
+impl SourceIndex {
+    pub fn new(transform: &TexTransform, source: SourceFingerprint) -> Self {
+        Self {
+            records: HashMap::new(),
+            transform: transform.to_string(),
+            source: Some(source),
+        }
+    }
+
+    pub fn sidecar_path(output_path: &Path) -> PathBuf {
+        let mut path = output_path.as_os_str().to_owned();
+        path.push(".srcidx");
+        PathBuf::from(path)
+    }
+
+    pub fn insert(&mut self, hash: u64, record: SourceRecord) {
+        self.records.insert(hash, record);
+    }
+
+    pub fn get(&self, hash: u64) -> Option<&SourceRecord> {
+        self.records.get(&hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// The source pak, `None` for indexes written before it was recorded.
+    pub fn source(&self) -> Option<&SourceFingerprint> {
+        self.source.as_ref()
+    }
+
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let file = fs::File::open(path)?;
+        let reader = io::BufReader::new(file);
+        let mut index = Self::default();
+
+        for (line_no, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if let Some(transform) = line.strip_prefix(TRANSFORM_PREFIX) {
+                index.transform = transform.trim().to_string();
+                continue;
+            }
+            if let Some(source) = line.strip_prefix(SOURCE_PREFIX) {
+                if let Some((size, toc_sha256)) = source.trim().split_once(' ') {
+                    index.source = Some(SourceFingerprint {
+                        size: size.parse()?,
+                        toc_sha256: toc_sha256.to_string(),
+                    });
+                }
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [hash, compressed_size, uncompressed_size, checksum] = fields[..] else {
+                eyre::bail!(
+                    "malformed source index line {} in {}",
+                    line_no + 1,
+                    path.display()
+                );
+            };
+            let hash = u64::from_str_radix(hash, 16)?;
+            let record = SourceRecord {
+                compressed_size: compressed_size.parse()?,
+                uncompressed_size: uncompressed_size.parse()?,
+                checksum: u64::from_str_radix(checksum, 16)?,
+            };
+            index.insert(hash, record);
+        }
+
+        Ok(index)
+    }
+
+    pub fn save(&self, path: &Path) -> eyre::Result<()> {
+        let file = fs::File::create(path)?;
+        let mut writer = io::BufWriter::new(file);
+        writeln!(writer, "{SOURCE_INDEX_HEADER}")?;
+        if !self.transform.is_empty() {
+            writeln!(writer, "{TRANSFORM_PREFIX} {}", self.transform)?;
+        }
+        if let Some(source) = &self.source {
+            writeln!(
+                writer,
+                "{SOURCE_PREFIX} {} {}",
+                source.size, source.toc_sha256
+            )?;
+        }
+
+        let mut hashes: Vec<&u64> = self.records.keys().collect();
+        hashes.sort();
+        for hash in hashes {
+            let record = &self.records[hash];
+            writeln!(
+                writer,
+                "{:016x} {} {} {:016x}",
+                hash, record.compressed_size, record.uncompressed_size, record.checksum
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A previously generated output (all of its parts), used as the source for unchanged entries.
+pub struct PreviousOutput {
+    paks: Vec<PakFile>,
+    /// Entry hash to the part containing it.
+    entries: HashMap<u64, (usize, PakEntry)>,
+    index: SourceIndex,
+}
+
+impl PreviousOutput {
+    pub fn open(
+        parts: &[PathBuf],
+        index_path: &Path,
+        transform: &TexTransform,
+    ) -> eyre::Result<Self> {
+        if !index_path.is_file() {
+            eyre::bail!(
+                "source index `{}` not found, the previous output can't be used for incremental update.",
+                index_path.display()
+            );
+        }
+        let index = SourceIndex::load(index_path)?;
+        if index.transform != transform.to_string() {
+            eyre::bail!(
+                "the previous output was generated with different texture options ({}), it can't be used for incremental update.",
+                if index.transform.is_empty() {
+                    "none"
+                } else {
+                    &index.transform
+                }
+            );
+        }
+
+        let paks = parts
+            .iter()
+            .map(|path| PakFile::open(path))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let entries = paks
+            .iter()
+            .enumerate()
+            .flat_map(|(part, pak)| {
+                pak.entries()
+                    .iter()
+                    .map(move |entry| (entry.hash(), (part, entry.clone())))
+            })
+            .collect();
+
+        Ok(Self {
+            paks,
+            entries,
+            index,
+        })
+    }
+
+    /// Returns the previous output entry if the source entry is unchanged since it was generated.
+    pub fn unchanged_entry(&self, source: &PakEntry) -> Option<(&PakFile, &PakEntry)> {
+        let record = self.index.get(source.hash())?;
+        if *record != SourceRecord::from_entry(source) {
+            return None;
+        }
+        let (part, entry) = self.entries.get(&source.hash())?;
+        Some((&self.paks[*part], entry))
+    }
+}
@@ -0,0 +1,100 @@
+//! Suggestions for an input path that doesn't lead to a pak, for the usual mistakes: stray quotes
+//! from a copied path, the game folder instead of a pak in it, or the base chunk when the
+//! textures are in its sub paks.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{chunk_family, util};
+
+/// Candidates listed at most, the game folder holds a lot of paks.
+const MAX_CANDIDATES: usize = 5;
+
+/// Hints for the input `path` that isn't a file, best guess first.
+pub fn hints(path: &Path) -> Vec<String> {
+    let mut hints = vec![];
+    let text = path.to_string_lossy();
+    let path = if text.contains(['"', '\'']) {
+        let unquoted = PathBuf::from(text.replace(['"', '\''], "").trim());
+        if unquoted.is_file() {
+            hints.push(format!(
+                "the path contains quotes, did you mean `{}`?",
+                util::display_path(&unquoted)
+            ));
+            return hints;
+        }
+        hints.push("the path contains quotes, they aren't part of the file name.".to_string());
+        unquoted
+    } else {
+        path.to_path_buf()
+    };
+
+    if path.is_dir() {
+        hints.push(format!(
+            "`{}` is a folder, pass one of its paks instead.",
+            util::display_path(&path)
+        ));
+        push_candidates(&mut hints, &path);
+        return hints;
+    }
+
+    let sub_paks = sub_paks(&path);
+    if !sub_paks.is_empty() {
+        hints.push("the textures are in the sub paks of this chunk:".to_string());
+        hints.extend(
+            sub_paks
+                .iter()
+                .take(MAX_CANDIDATES)
+                .map(|path| format!("  {}", util::display_path(path))),
+        );
+        return hints;
+    }
+
+    // the closest folder that exists, a typo may be anywhere in the path
+    if let Some(dir) = path.ancestors().skip(1).find(|dir| dir.is_dir()) {
+        push_candidates(&mut hints, dir);
+    }
+    hints
+}
+
+/// The sub paks next to `path` if it's a base chunk like `re_chunk_000.pak`, which holds few
+/// textures when it has sub paks.
+pub fn sub_paks(path: &Path) -> Vec<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    if chunk_family::split_name(&file_name).is_some() {
+        return vec![];
+    }
+    let first = path.with_file_name(format!("{file_name}.sub_000.pak"));
+    if !first.is_file() {
+        return vec![];
+    }
+    chunk_family::parts(&first).unwrap_or_default()
+}
+
+fn push_candidates(hints: &mut Vec<String>, dir: &Path) {
+    let mut paks: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|dir_entry| dir_entry.ok().map(|dir_entry| dir_entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "pak"))
+        .collect();
+    if paks.is_empty() {
+        return;
+    }
+    // sub paks hold the textures, list them first
+    paks.sort_by_key(|path| {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        (chunk_family::split_name(&file_name).is_none(), path.clone())
+    });
+    hints.push(format!("paks found in `{}`:", util::display_path(dir)));
+    hints.extend(
+        paks.iter()
+            .take(MAX_CANDIDATES)
+            .map(|path| format!("  {}", util::display_path(path))),
+    );
+    if paks.len() > MAX_CANDIDATES {
+        hints.push(format!("  and {} more", paks.len() - MAX_CANDIDATES));
+    }
+}
@@ -1,254 +1,289 @@
-use std::{
-    fs::{self, OpenOptions},
-    io::{self, Write},
-    path::Path,
-    sync::{
-        Arc,
-        atomic::{AtomicUsize, Ordering},
-    },
-    time::Duration,
-};
-
+mod alignment;
+#[cfg(feature = "async-io")]
+mod async_read;
+mod build_info;
+mod cancel;
+mod category;
+mod changelog;
+mod chunk_family;
+mod cli;
+mod commands;
+mod compression;
+mod config;
+mod content_index;
+mod dds;
+mod dedup;
+mod diagnostics;
+mod dir_stats;
+mod estimate;
+mod exit_code;
+mod family;
+mod fluffy;
+mod free_space;
+#[cfg(feature = "gui")]
+mod gui;
+mod handler;
+mod i18n;
+mod impact;
+mod incremental;
+mod input_hints;
+mod manifest;
+mod merge;
+mod metadata;
+mod names;
+mod naming;
+mod open_files;
+mod output;
+mod output_location;
+mod overrides;
+mod pak;
+mod pak_header;
+mod path_hash;
+#[cfg(feature = "tui")]
+mod picker;
+mod pipeline;
+mod preset;
+mod preview;
+mod priority;
+mod process;
+mod processed_input;
+mod progress;
+mod ratio_check;
+mod read_ahead;
+mod retry;
+#[cfg(feature = "scripting")]
+mod script;
+mod selector;
+mod session_lock;
+mod shard;
+mod signing;
+mod stats;
+mod status_file;
+mod stdio;
+mod steam;
+mod storage;
+mod streaming;
+mod tex_format;
+mod tex_header;
+mod throttle;
+mod transcode;
+mod update;
+mod util;
+mod verify;
+#[cfg(not(windows))]
+mod wine;
+
+use std::io::{self, IsTerminal};
+
+use clap::Parser;
 use colored::Colorize;
-use dialoguer::{Input, Select, theme::ColorfulTheme};
-use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
-use parking_lot::Mutex;
-use rayon::{
-    ThreadPoolBuilder,
-    iter::{IntoParallelRefIterator, ParallelIterator},
-};
-use re_tex::tex::Tex;
-use ree_pak_core::{
-    filename::{FileNameExt, FileNameTable},
-    pak::PakEntry,
-    read::archive::PakArchiveReader,
-    write::FileOptions,
-};
+use dialoguer::{Input, theme::ColorfulTheme};
 
-const FILE_NAME_LIST: &[u8] = include_bytes!("../assets/MHWs_STM_Release.list.zst");
+use cli::{Cli, Command, NameTableArgs};
+use exit_code::ExitCode;
+use progress::{ConsoleProgress, Verbosity};
 
 fn main() {
-    std::panic::set_hook(Box::new(panic_hook));
+    let cli = Cli::parse();
+    i18n::init(cli.lang);
+
+    // GUI builds are mostly launched by double-clicking, open the window unless asked otherwise
+    #[cfg(feature = "gui")]
+    if cli.command.is_none() && std::env::args_os().len() == 1 {
+        if let Err(e) = gui::run(&cli.names) {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            ExitCode::of(&e).exit();
+        }
+        return;
+    }
+
+    let verbosity = cli.verbosity();
+    // an unreachable GitHub shouldn't stop the run
+    if cli.check_update {
+        if let Err(e) = commands::check_update::run(io::stdin().is_terminal()) {
+            eprintln!(
+                "{}: failed to check for updates: {e}",
+                "Warning".yellow().bold()
+            );
+        }
+    }
+    if let Some(command) = cli.command {
+        if let Err(e) = run_command(command, &cli.names, verbosity) {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            ExitCode::of(&e).exit();
+        }
+        return;
+    }
 
-    println!("Version v{} - Tool by @Eigeen", env!("CARGO_PKG_VERSION"));
+    // nobody is there to press Enter in scripts and CI
+    let pause =
+        !cli.decompress.no_pause && !cli.decompress.writes_stdout() && io::stdin().is_terminal();
+    if pause {
+        std::panic::set_hook(Box::new(panic_hook));
+    }
 
-    if let Err(e) = main_entry() {
-        eprintln!("{}: {}", "Error".red().bold(), e);
+    if verbosity != Verbosity::Quiet {
+        println!("Version v{} - Tool by @Eigeen", env!("CARGO_PKG_VERSION"));
+        changelog::show_if_updated();
+    }
+
+    let exit_code = commands::decompress::run(&cli.decompress, &cli.names, verbosity)
+        .unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            ExitCode::of(&e)
+        });
+    if pause {
         wait_for_exit();
-        std::process::exit(1);
     }
-    wait_for_exit();
+    exit_code.exit();
 }
 
 fn panic_hook(info: &std::panic::PanicHookInfo) {
     eprintln!("{}: {}", "Panic".red().bold(), info);
     wait_for_exit();
-    std::process::exit(1);
+    ExitCode::Failure.exit();
 }
 
-fn main_entry() -> eyre::Result<()> {
-    let input: String = Input::with_theme(&ColorfulTheme::default())
-        .show_default(true)
-        .default("re_chunk_000.pak.sub_000.pak".to_string())
-        .with_prompt("Input .pak file path")
-        .interact_text()
-        .unwrap()
-        .trim_matches(|c| c == '\"' || c == '\'')
-        .to_string();
-
-    let input_path = Path::new(&input);
-    if !input_path.is_file() {
-        eyre::bail!("input file not exists.");
+fn run_command(
+    command: Command,
+    name_args: &NameTableArgs,
+    verbosity: Verbosity,
+) -> eyre::Result<()> {
+    match &command {
+        Command::Changelog => {
+            changelog::show_all();
+            return Ok(());
+        }
+        Command::CheckManifest { manifest } => {
+            return commands::check_manifest::run(&util::long_path(manifest));
+        }
+        Command::VerifySignature { file, public_key } => {
+            return commands::verify_signature::run(&util::long_path(file), public_key);
+        }
+        Command::BuildInfo { pak } => return commands::build_info::run(&util::long_path(pak)),
+        Command::MergeShards { shards, out, force } => {
+            let shards: Vec<_> = shards.iter().map(|path| util::long_path(path)).collect();
+            return commands::merge_shards::run(&util::long_path(out), &shards, *force);
+        }
+        #[cfg(feature = "gui")]
+        Command::Gui => return gui::run(name_args),
+        _ => {}
     }
 
-    const FALSE_TRUE_SELECTION: [&str; 2] = ["False", "True"];
-
-    let use_full_package_mode = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Package all files, including non-tex files (for replacing original files)")
-        .default(0)
-        .items(&FALSE_TRUE_SELECTION)
-        .interact()
-        .unwrap();
-    let use_full_package_mode = use_full_package_mode == 1;
-
-    let use_feature_clone = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Clone feature flags from original file?")
-        .default(1)
-        .items(&FALSE_TRUE_SELECTION)
-        .interact()
-        .unwrap();
-    let use_feature_clone = use_feature_clone == 1;
-
-    // Get thread count from user
-    let default_threads = num_cpus::get();
-    let thread_count: usize = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!(
-            "Number of threads to use (default: {})",
-            default_threads
-        ))
-        .default(default_threads)
-        .interact()
-        .unwrap();
-
-    // Configure rayon thread pool
-    ThreadPoolBuilder::new()
-        .num_threads(thread_count)
-        .build_global()
-        .map_err(|e| eyre::eyre!("Failed to configure thread pool: {}", e))?;
-
-    println!("Loading embedded file name table...");
-    let filename_table = FileNameTable::from_bytes(FILE_NAME_LIST)?;
-
-    let file = fs::File::open(input_path)?;
-    let mut reader = io::BufReader::new(file);
-
-    println!("Reading pak archive...");
-    let pak_archive = ree_pak_core::read::read_archive(&mut reader)?;
-
-    // Store the input path for creating multiple readers
-    let input_path_arc = Arc::new(input_path.to_path_buf());
-    let pak_archive_arc = Arc::new(pak_archive);
-
-    // filtered entries
-    let entries = if use_full_package_mode {
-        pak_archive_arc.entries().iter().collect::<Vec<_>>()
-    } else {
-        println!("Filtering entries...");
-        pak_archive_arc
-            .entries()
-            .iter()
-            .filter(|entry| is_tex_file(entry.hash(), &filename_table))
-            .collect::<Vec<_>>()
-    };
-
-    // new pak archive
-    let output_path = input_path.with_extension("uncompressed.pak");
-    println!("Output file: {}", output_path.to_string_lossy());
-    let out_file = OpenOptions::new()
-        .create(true)
-        .truncate(true)
-        .write(true)
-        .open(output_path)?;
-    let pak_writer = ree_pak_core::write::PakWriter::new(out_file, entries.len() as u64);
-    let pak_writer_mtx = Arc::new(Mutex::new(pak_writer));
-
-    let bar = ProgressBar::new(entries.len() as u64);
-    bar.set_style(
-        ProgressStyle::default_bar().template("Bytes written: {msg}\n{pos}/{len} {wide_bar}")?,
-    );
-    bar.enable_steady_tick(Duration::from_millis(200));
-
-    let pak_writer_mtx1 = Arc::clone(&pak_writer_mtx);
-    let bar1 = bar.clone();
-    let bytes_written = AtomicUsize::new(0);
-    let filename_table_arc = Arc::new(filename_table);
-    let pak_archive_arc1 = Arc::clone(&pak_archive_arc);
-
-    let err = entries
-        .par_iter()
-        .try_for_each(move |&entry| -> eyre::Result<()> {
-            let pak_writer_mtx = &pak_writer_mtx1;
-            let bar = &bar1;
-
-            // Create a new file reader for each thread to avoid lock contention
-            let file = fs::File::open(&*input_path_arc)?;
-            let reader = io::BufReader::new(file);
-            let mut archive_reader = PakArchiveReader::new(reader, &pak_archive_arc1);
-
-            // read raw tex file
-            let mut entry_reader = archive_reader.owned_entry_reader(entry.clone())?;
-
-            if !is_tex_file(entry.hash(), &filename_table_arc) {
-                // plain file, just copy
-                let mut buf = vec![];
-                std::io::copy(&mut entry_reader, &mut buf)?;
-                let mut pak_writer = pak_writer_mtx.lock();
-                let write_bytes = write_to_pak(
-                    &mut pak_writer,
-                    entry,
-                    entry.hash(),
-                    &buf,
-                    use_feature_clone,
-                )?;
-                bytes_written.fetch_add(write_bytes, Ordering::SeqCst);
-            } else {
-                let mut tex = Tex::from_reader(&mut entry_reader)?;
-                // decompress mipmaps
-                tex.batch_decompress()?;
-
-                let tex_bytes = tex.as_bytes()?;
-                let mut pak_writer = pak_writer_mtx.lock();
-                let write_bytes = write_to_pak(
-                    &mut pak_writer,
-                    entry,
-                    entry.hash(),
-                    &tex_bytes,
-                    use_feature_clone,
-                )?;
-                bytes_written.fetch_add(write_bytes, Ordering::SeqCst);
-            }
-
-            bar.inc(1);
-            if bar.position() % 100 == 0 {
-                bar.set_message(
-                    HumanBytes(bytes_written.load(Ordering::SeqCst) as u64).to_string(),
-                );
-            }
-            Ok(())
-        });
-    if let Err(e) = err {
-        eprintln!("Error occurred when processing tex: {e}");
-        eprintln!(
-            "The process terminated early, we'll save the current processed tex files to pak file."
-        );
-    }
-
-    let pak_writer = Arc::try_unwrap(pak_writer_mtx);
-    match pak_writer {
-        Ok(pak_writer) => pak_writer.into_inner().finish()?,
-        Err(_) => panic!("Arc::try_unwrap failed"),
-    };
-
-    bar.finish();
-    println!("{}", "Done!".cyan().bold());
-    if !use_full_package_mode {
-        println!(
-            "You should rename the output file like `re_chunk_000.pak.sub_000.pak.patch_xxx.pak`, or manage it by your favorite mod manager."
-        );
-    }
-
-    Ok(())
-}
-
-fn is_tex_file(hash: u64, file_name_table: &FileNameTable) -> bool {
-    let Some(file_name) = file_name_table.get_file_name(hash) else {
-        return false;
-    };
-    file_name.get_name().ends_with(".tex.241106027")
-}
-
-fn write_to_pak<W>(
-    writer: &mut ree_pak_core::write::PakWriter<W>,
-    entry: &PakEntry,
-    file_name: impl FileNameExt,
-    data: &[u8],
-    use_feature_clone: bool,
-) -> eyre::Result<usize>
-where
-    W: io::Write + io::Seek,
-{
-    let mut file_options = FileOptions::default();
-    if use_feature_clone {
-        file_options = file_options.with_unk_attr(*entry.unk_attr())
+    let names = names::load_table(name_args, &ConsoleProgress::new(verbosity))?;
+    match command {
+        Command::Diff {
+            old,
+            new,
+            tex_only,
+            report,
+        } => commands::diff::run(
+            &util::long_path(&old),
+            &util::long_path(&new),
+            tex_only,
+            report.as_deref(),
+            &names,
+        ),
+        Command::Compare {
+            source,
+            output,
+            entry,
+        } => commands::compare::run(
+            &util::long_path(&source),
+            &util::long_path(&output),
+            &entry,
+            &names,
+        ),
+        Command::ContentIndex { index, entry } => {
+            commands::content_index::run(&util::long_path(&index), entry.as_ref(), &names)
+        }
+        Command::ExtractOne {
+            pak,
+            entry,
+            out,
+            family,
+        } => commands::extract_one::run(
+            &util::long_path(&pak),
+            &entry,
+            &util::long_path(&out),
+            family,
+            &names,
+        ),
+        Command::ReplaceOne {
+            pak,
+            entry,
+            file,
+            family,
+        } => commands::replace_one::run(
+            &util::long_path(&pak),
+            &entry,
+            &util::long_path(&file),
+            family,
+            &names,
+        ),
+        Command::Families { pak, entry } => {
+            commands::families::run(&util::long_path(&pak), entry.as_ref(), &names)
+        }
+        Command::Stats { pak, csv } => {
+            commands::stats::run(&util::long_path(&pak), csv.as_deref(), &names, verbosity)
+        }
+        Command::Hashes { entries } => commands::hashes::run(&entries, &names),
+        Command::Preview {
+            pak,
+            entry,
+            out,
+            size,
+        } => commands::preview::run(
+            &util::long_path(&pak),
+            &entry,
+            &util::long_path(&out),
+            size,
+            &names,
+        ),
+        Command::ExportDds {
+            pak,
+            entry,
+            out,
+            layers,
+        } => commands::export_dds::run(
+            &util::long_path(&pak),
+            &entry,
+            &util::long_path(&out),
+            layers,
+            &names,
+        ),
+        Command::Watch { dir, out } => {
+            commands::watch::run(&util::long_path(&dir), &util::long_path(&out))
+        }
+        Command::Bench {
+            pak,
+            sample,
+            no_save,
+        } => commands::bench::run(&util::long_path(&pak), sample, !no_save, &names),
+        Command::Rebuild {
+            manifest,
+            pak,
+            force,
+        } => commands::rebuild::run(
+            &util::long_path(&manifest),
+            &util::long_path(&pak),
+            force,
+            &names,
+            verbosity,
+        ),
+        Command::Changelog
+        | Command::CheckManifest { .. }
+        | Command::VerifySignature { .. }
+        | Command::BuildInfo { .. }
+        | Command::MergeShards { .. } => unreachable!(),
+        #[cfg(feature = "gui")]
+        Command::Gui => unreachable!(),
     }
-    writer.start_file(file_name, file_options)?;
-    writer.write_all(data)?;
-    Ok(data.len())
 }
 
 fn wait_for_exit() {
     let _: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt("Press Enter to exit")
+        .with_prompt(i18n::Msg::PressEnterToExit.text())
         .allow_empty(true)
         .interact_text()
         .unwrap();
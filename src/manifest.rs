@@ -0,0 +1,250 @@
+//! `manifest.json` written next to an output, so distributed packs can be verified.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::{names::NameTable, pak::PakFile, pipeline::Options, util, verify::SampleReport};
+
+/// Size and CRC32 of an entry as written to the output.
+#[derive(Debug, Clone, Copy)]
+pub struct EntryChecksum {
+    pub size: u64,
+    pub crc32: u32,
+}
+
+impl EntryChecksum {
+    pub fn of(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            crc32: crc32fast::hash(data),
+        }
+    }
+}
+
+/// Decisions of the run, so a reported problem can be reproduced from the user's manifest.
+pub struct Audit<'a> {
+    /// What was done with each processed source entry, by hash.
+    pub actions: &'a HashMap<u64, &'static str>,
+    pub verification: Option<&'a SampleReport>,
+}
+
+pub fn manifest_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("manifest.json")
+}
+
+/// Writes the manifest of all output parts, the parts must be complete.
+pub fn write(
+    path: &Path,
+    options: &Options,
+    output_paths: &[PathBuf],
+    checksums: &HashMap<u64, EntryChecksum>,
+    audit: &Audit,
+    names: &NameTable,
+) -> eyre::Result<()> {
+    let mut outputs = vec![];
+    for output_path in output_paths {
+        let pak = PakFile::open(output_path)?;
+        let mut entries: Vec<Value> = pak
+            .entries()
+            .iter()
+            .filter_map(|entry| {
+                let checksum = checksums.get(&entry.hash())?;
+                Some(json!({
+                    "hash": format!("{:016X}", entry.hash()),
+                    "name": names.get_name(entry.hash()),
+                    "size": checksum.size,
+                    "crc32": format!("{:08x}", checksum.crc32),
+                    "action": audit.actions.get(&entry.hash()),
+                }))
+            })
+            .collect();
+        entries.sort_by(|a, b| a["hash"].as_str().cmp(&b["hash"].as_str()));
+
+        outputs.push(json!({
+            "file": file_name(output_path),
+            "size": fs::metadata(output_path)?.len(),
+            "sha256": sha256_file(output_path)?,
+            "entries": entries,
+        }));
+    }
+
+    // processed entries that didn't make it into the output, and why
+    let mut skipped: Vec<Value> = audit
+        .actions
+        .iter()
+        .filter(|(hash, _)| !checksums.contains_key(hash))
+        .map(|(hash, action)| {
+            json!({
+                "hash": format!("{hash:016X}"),
+                "name": names.get_name(*hash),
+                "action": action,
+            })
+        })
+        .collect();
+    skipped.sort_by(|a, b| a["hash"].as_str().cmp(&b["hash"].as_str()));
+
+    let hex_hashes = |hashes: &[u64]| -> Vec<String> {
+        hashes.iter().map(|hash| format!("{hash:016X}")).collect()
+    };
+    let verification = audit.verification.map(|report| {
+        json!({
+            "seed": format!("{:016x}", report.seed),
+            "sampled": hex_hashes(&report.sampled),
+            "mismatched": hex_hashes(&report.mismatched),
+            "missing": hex_hashes(&report.missing),
+        })
+    });
+
+    let manifest = json!({
+        "tool": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "source": file_name(&options.input_path),
+        "options": options_json(options)?,
+        "outputs": outputs,
+        "skipped": skipped,
+        "verification": verification,
+    });
+    fs::write(path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(())
+}
+
+/// The options that decide the output, as recorded in the manifest.
+pub fn options_json(options: &Options) -> eyre::Result<Value> {
+    // embedded, so the output can be rebuilt from the manifest alone
+    let overrides: Option<Value> = match &options.overrides {
+        Some(path) => Some(serde_json::from_slice(&fs::read(path)?)?),
+        None => None,
+    };
+    Ok(json!({
+        "full_package": options.full_package,
+        "feature_clone": options.feature_clone,
+        "categories": options.categories.iter().map(|c| c.label()).collect::<Vec<_>>(),
+        "formats": options.formats.iter().map(|f| f.to_string()).collect::<Vec<_>>(),
+        "split_size": options.split_size,
+        "split_by_category": options.split_by_category,
+        "align": options.align,
+        "deterministic": options.deterministic,
+        "skip_larger_than": options.skip_larger_than,
+        "skip_smaller_than": options.skip_smaller_than,
+        "min_entry_size": options.min_entry_size,
+        "max_entry_size": options.max_entry_size,
+        "tex_transform": options.tex_transform.to_string(),
+        "copy_paths": options.copy_paths.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+        "top_impact": options.top_impact,
+        "picked_entries": options.picked_entries.as_ref().map(|picked| picked.len()),
+        "verify_sample": options.verify_sample,
+        "pak_version": options.pak_version.map(|version| version.to_string()),
+        "pak_features": options.pak_features.map(|features| format!("{features:#06x}")),
+        "keep_compression": options.keep_compression,
+        "compression": options.compression.to_string(),
+        "shard": options.shard.map(|shard| shard.to_string()),
+        "overrides": overrides,
+        // the script itself isn't embedded, its hash tells whether a copy is the one used
+        "script": options.script.as_deref().map(sha256_file).transpose()?,
+    }))
+}
+
+/// What a manifest records about the run that wrote it, enough to rebuild the output.
+pub struct Recorded {
+    /// File name of the source pak.
+    pub source: String,
+    /// The `options` object as written by [`write`].
+    pub options: Value,
+    /// `(file name, SHA-256)` of every output part.
+    pub outputs: Vec<(String, String)>,
+    /// Every entry written to any part, by hash.
+    pub entries: HashMap<u64, EntryChecksum>,
+}
+
+pub fn read(path: &Path) -> eyre::Result<Recorded> {
+    let manifest: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let malformed = || eyre::eyre!("`{}` is not a manifest", util::display_path(path));
+    let outputs = manifest["outputs"].as_array().ok_or_else(malformed)?;
+
+    let mut recorded = Recorded {
+        source: manifest["source"].as_str().unwrap_or_default().to_string(),
+        options: manifest["options"].clone(),
+        outputs: vec![],
+        entries: HashMap::new(),
+    };
+    for output in outputs {
+        let (Some(file), Some(sha256), Some(entries)) = (
+            output["file"].as_str(),
+            output["sha256"].as_str(),
+            output["entries"].as_array(),
+        ) else {
+            return Err(malformed());
+        };
+        recorded
+            .outputs
+            .push((file.to_string(), sha256.to_string()));
+        for entry in entries {
+            let hash = entry["hash"]
+                .as_str()
+                .and_then(|hash| util::parse_hex_u64(hash).ok());
+            let crc32 = entry["crc32"]
+                .as_str()
+                .and_then(|crc32| u32::from_str_radix(crc32, 16).ok());
+            let (Some(hash), Some(size), Some(crc32)) = (hash, entry["size"].as_u64(), crc32)
+            else {
+                return Err(malformed());
+            };
+            recorded.entries.insert(hash, EntryChecksum { size, crc32 });
+        }
+    }
+    Ok(recorded)
+}
+
+/// Checks the SHA-256 of the files listed in a manifest, which are looked up next to it.
+/// Returns the files that are missing or don't match.
+pub fn check(path: &Path) -> eyre::Result<Vec<String>> {
+    let manifest: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let outputs = manifest["outputs"]
+        .as_array()
+        .ok_or_else(|| eyre::eyre!("`{}` is not a manifest", util::display_path(path)))?;
+
+    let mut failed = vec![];
+    for output in outputs {
+        let (Some(file), Some(expected)) = (output["file"].as_str(), output["sha256"].as_str())
+        else {
+            eyre::bail!("malformed output in `{}`", util::display_path(path));
+        };
+        let file_path = dir.join(file);
+        if !file_path.is_file() || sha256_file(&file_path)? != expected {
+            failed.push(file.to_string());
+        }
+    }
+    Ok(failed)
+}
+
+pub fn sha256_file(path: &Path) -> eyre::Result<String> {
+    let mut reader = io::BufReader::new(fs::File::open(path)?);
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
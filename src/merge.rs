@@ -0,0 +1,106 @@
+//! Combines several output paks into one, for `merge-shards` and merged chunk families.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ree_pak_core::{pak::PakEntry, write::PakWriter};
+
+use crate::{
+    alignment, build_info,
+    metadata::TrailingBlock,
+    output,
+    pak::PakFile,
+    pak_header::PakHeader,
+    process::{StoreOptions, write_to_pak},
+    util,
+};
+
+/// What to do with an entry held by more than one of the paks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// The paks must not overlap, they're parts of one run.
+    Fail,
+    /// The entry of the later pak wins, like the game loads them.
+    LaterWins,
+}
+
+/// Writes the entries of all `paths` to `out_path`, with the header and trailing metadata of the
+/// first one, and the entry data at multiples of `align` if given. Returns the number of entries
+/// and bytes written.
+///
+/// The paks are copied one after another. With `remove_merged`, each is removed once copied, so
+/// the disk only needs room for the largest one on top of them.
+pub fn merge(
+    out_path: &Path,
+    paths: &[PathBuf],
+    overlap: Overlap,
+    align: Option<u64>,
+    remove_merged: bool,
+) -> eyre::Result<(usize, u64)> {
+    let paks = paths
+        .iter()
+        .map(|path| PakFile::open(path))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    // read up front, the first pak may be gone by the time the output is completed
+    let first = &paths[0];
+    let first_header = PakHeader::read(first)?;
+    let trailing_block = TrailingBlock::read(first, paks[0].archive())?;
+
+    // the build info is embedded in all of them, it's the same
+    let mut owners: HashMap<u64, (usize, PakEntry)> = HashMap::new();
+    for (pak_index, pak) in paks.iter().enumerate() {
+        for entry in pak.entries() {
+            if let Some((other, _)) = owners.insert(entry.hash(), (pak_index, entry.clone())) {
+                if overlap == Overlap::Fail && entry.hash() != build_info::hash() {
+                    eyre::bail!(
+                        "`{}` and `{}` both hold entry {:016X}, they aren't shards of the same run.",
+                        util::display_path(&paths[other]),
+                        util::display_path(&paths[pak_index]),
+                        entry.hash()
+                    );
+                }
+            }
+        }
+    }
+    let mut entries: Vec<(usize, PakEntry)> = owners.into_values().collect();
+    entries.sort_by_key(|(pak_index, entry)| (*pak_index, entry.hash()));
+
+    let temp_path = output::temp_path(out_path);
+    let mut writer = PakWriter::new(fs::File::create(&temp_path)?, entries.len() as u64);
+    // stored like in the source paks, which already decided compression and flags
+    let store = StoreOptions {
+        feature_clone: true,
+        keep_compression: true,
+        codec: None,
+    };
+    let progress = util::spinner("Merging paks...");
+    let mut bytes = 0;
+    for (pak_index, source) in paks.into_iter().enumerate() {
+        for (_, entry) in entries.iter().filter(|(owner, _)| *owner == pak_index) {
+            let data = source.read_entry(entry)?;
+            bytes += write_to_pak(&mut writer, entry, entry.hash(), &data, store)? as u64;
+        }
+        // closed first, Windows can't remove an open file
+        drop(source);
+        if remove_merged {
+            fs::remove_file(&paths[pak_index])?;
+        }
+    }
+    writer.finish()?;
+    progress.finish_and_clear();
+
+    // the writer packs the entries back to back again, whatever the paks' alignment was
+    if let Some(alignment) = align {
+        alignment::align_entries(&temp_path, alignment)?;
+    }
+    PakHeader::for_output(PakHeader::read(&temp_path)?, first_header, None, None)
+        .write_to(&temp_path)?;
+    if let Some(trailing_block) = trailing_block {
+        trailing_block.append_to(&temp_path)?;
+    }
+    fs::rename(&temp_path, out_path)?;
+    Ok((entries.len(), bytes))
+}
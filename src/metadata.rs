@@ -0,0 +1,46 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use ree_pak_core::pak::PakArchive;
+
+/// Opaque bytes found after the last entry's data in a source pak (comments, signatures, tool metadata).
+pub struct TrailingBlock {
+    data: Vec<u8>,
+}
+
+impl TrailingBlock {
+    /// Reads the trailing block of the pak, if there is anything past the end of the entry data.
+    pub fn read(path: &Path, archive: &PakArchive) -> eyre::Result<Option<Self>> {
+        let data_end = archive
+            .entries()
+            .iter()
+            .map(|entry| entry.offset() + entry.compressed_size())
+            .max()
+            .unwrap_or(0);
+
+        let mut file = fs::File::open(path)?;
+        let file_len = file.metadata()?.len();
+        if data_end == 0 || file_len <= data_end {
+            return Ok(None);
+        }
+
+        file.seek(SeekFrom::Start(data_end))?;
+        let mut data = Vec::with_capacity((file_len - data_end) as usize);
+        file.read_to_end(&mut data)?;
+        Ok(Some(Self { data }))
+    }
+
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Appends the block to a finished output pak.
+    pub fn append_to(&self, path: &Path) -> eyre::Result<()> {
+        let mut file = OpenOptions::new().append(true).open(path)?;
+        file.write_all(&self.data)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use std::{borrow::Cow, collections::HashMap, fmt, fs, path::Path};
+
+use ree_pak_core::filename::FileNameTable;
+
+use crate::{cli::NameTableArgs, path_hash, progress::Progress, util};
+
+/// Selected at build time, see `build.rs`.
+const FILE_NAME_LIST: &[u8] = include_bytes!(env!("MHWS_NAME_LIST_PATH"));
+
+/// The file name table used to resolve entry hashes, if one is loaded.
+pub struct NameTable {
+    table: Option<FileNameTable>,
+    /// Names merged from plain `.list` files on top of the table.
+    extra: HashMap<u64, String>,
+}
+
+impl NameTable {
+    pub fn load_embedded() -> Result<Self, NameTableError> {
+        let table = FileNameTable::from_bytes(FILE_NAME_LIST)
+            .map_err(|e| NameTableError::new("the embedded file name list", e))?;
+        Ok(Self {
+            table: Some(table),
+            extra: HashMap::new(),
+        })
+    }
+
+    pub fn load_file(path: &Path) -> Result<Self, NameTableError> {
+        let source = format!("`{}`", path.display());
+        let bytes = fs::read(path).map_err(|e| NameTableError::new(&source, e))?;
+        let table =
+            FileNameTable::from_bytes(&bytes).map_err(|e| NameTableError::new(&source, e))?;
+        Ok(Self {
+            table: Some(table),
+            extra: HashMap::new(),
+        })
+    }
+
+    /// A table that resolves nothing, entries are identified by hash only.
+    pub fn empty() -> Self {
+        Self {
+            table: None,
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Adds the paths of a plain text `.list` file (one path per line, UTF-8 or UTF-16 with a
+    /// BOM), returns how many of them weren't known yet. Paths are normalized before hashing, see
+    /// [`path_hash::normalize_path`].
+    pub fn merge_list(&mut self, path: &Path) -> Result<usize, NameTableError> {
+        let source = format!("`{}`", path.display());
+        let bytes = fs::read(path).map_err(|e| NameTableError::new(&source, e))?;
+        let list = decode_list(&bytes).map_err(|e| NameTableError::new(&source, e))?;
+        let mut added = 0;
+        for name in list.lines().filter(|line| !line.trim().is_empty()) {
+            let name = path_hash::normalize_path(name);
+            let hash = path_hash::hash_path(&name);
+            if !self.contains(hash) {
+                self.extra.insert(hash, name);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.table.is_some()
+    }
+
+    /// Name of the entry, borrowed from the table. Full archive runs look up every name several
+    /// times (filters, reports, manifest), copies would add up to hundreds of MB.
+    pub fn get_name(&self, hash: u64) -> Option<&str> {
+        if let Some(name) = self.extra.get(&hash) {
+            return Some(name);
+        }
+        let file_name = self.table.as_ref()?.get_file_name(hash)?;
+        Some(file_name.get_name())
+    }
+
+    /// Whether the hash is in the table.
+    pub fn contains(&self, hash: u64) -> bool {
+        self.extra.contains_key(&hash)
+            || self
+                .table
+                .as_ref()
+                .is_some_and(|table| table.get_file_name(hash).is_some())
+    }
+
+    /// Whether the entry is a tex file by its name, `None` if no table is loaded to tell.
+    pub fn is_tex_file(&self, hash: u64) -> Option<bool> {
+        self.table.as_ref()?;
+        let Some(name) = self.get_name(hash) else {
+            return Some(false);
+        };
+        Some(name.ends_with(".tex.241106027"))
+    }
+
+    /// File name of the entry without its directory, or its hash in hex if it's not in the table.
+    pub fn file_name(&self, hash: u64) -> Cow<'_, str> {
+        match self.display_name(hash) {
+            Cow::Borrowed(name) => Cow::Borrowed(
+                name.rsplit_once('/')
+                    .map_or(name, |(_, file_name)| file_name),
+            ),
+            hex => hex,
+        }
+    }
+
+    /// Resolved name of the entry, or its hash in hex if it's not in the table.
+    pub fn display_name(&self, hash: u64) -> Cow<'_, str> {
+        match self.get_name(hash) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(format!("{hash:016X}")),
+        }
+    }
+}
+
+/// Text of a list file, lists saved by Windows editors are often UTF-16 or have a UTF-8 BOM.
+fn decode_list(bytes: &[u8]) -> Result<String, String> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|_| "invalid UTF-16".to_string())
+    };
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|_| "invalid UTF-8".to_string())
+        }
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        _ => String::from_utf8(bytes.to_vec())
+            .map_err(|_| "not UTF-8, save the list as UTF-8 or UTF-16 with BOM".to_string()),
+    }
+}
+
+/// The file name list couldn't be decoded, with suggestions the user can act on.
+#[derive(Debug)]
+pub struct NameTableError {
+    source: String,
+    cause: String,
+}
+
+impl NameTableError {
+    fn new(source: &str, cause: impl fmt::Display) -> Self {
+        Self {
+            source: source.to_string(),
+            cause: cause.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for NameTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "failed to load file name list from {}: {}",
+            self.source, self.cause
+        )?;
+        writeln!(f, "Suggestions:")?;
+        writeln!(
+            f,
+            "  - Re-download the tool, the embedded list may have been corrupted."
+        )?;
+        writeln!(
+            f,
+            "  - If using `--name-list`, make sure the file is a complete .list.zst file."
+        )?;
+        write!(
+            f,
+            "  - Run with `--skip-name-table` to detect tex files by content instead (names won't be shown)."
+        )
+    }
+}
+
+impl std::error::Error for NameTableError {}
+
+/// Loads the file name table selected on the command line.
+pub fn load_table(args: &NameTableArgs, progress: &dyn Progress) -> eyre::Result<NameTable> {
+    if args.skip_name_table {
+        progress.info("Skipping file name table, tex files will be detected by content.");
+        return Ok(NameTable::empty());
+    }
+    let mut table = match &args.name_list {
+        Some(path) => {
+            progress.info(&format!(
+                "Loading file name table from {}...",
+                util::display_path(path)
+            ));
+            NameTable::load_file(&util::long_path(path))?
+        }
+        None => {
+            progress.info("Loading embedded file name table...");
+            NameTable::load_embedded()?
+        }
+    };
+    for path in &args.extra_list {
+        let added = table.merge_list(&util::long_path(path))?;
+        progress.info(&format!(
+            "Added {added} file names from {}.",
+            util::display_path(path)
+        ));
+    }
+    Ok(table)
+}
@@ -0,0 +1,106 @@
+//! Output file names from a template, so generated paks describe how they were made, e.g.
+//! `{input}.patch_042.{transform}.{mode}.pak`.
+
+use crate::{manifest, pipeline::Options, steam};
+
+/// Tokens a template can use:
+/// - `input`: the input file name without `.pak`
+/// - `mode`: `full` or `tex-only`
+/// - `transform`: texture changes on top of decompression, e.g. `drop2.bc1`
+/// - `filters_hash`: 8 hex digits identifying the options that decide the output
+/// - `game_build`: the Steam build id of the install the input pak is in
+const TOKENS: &[&str] = &["input", "mode", "transform", "filters_hash", "game_build"];
+
+/// Checks that a template only uses known tokens, for the command line parser.
+pub fn parse_template(template: &str) -> Result<String, String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err("unclosed `{` in name template".to_string());
+        };
+        let token = &rest[start + 1..start + len];
+        if !TOKENS.contains(&token) {
+            let known: Vec<String> = TOKENS.iter().map(|name| format!("{{{name}}}")).collect();
+            return Err(format!(
+                "unknown token `{{{token}}}`, use one of {}",
+                known.join(", ")
+            ));
+        }
+        rest = &rest[start + len + 1..];
+    }
+    if template.contains(['/', '\\']) {
+        return Err("the name template is a file name, not a path".to_string());
+    }
+    Ok(template.to_string())
+}
+
+/// File name of the output for a template checked by [`parse_template`].
+pub fn expand(template: &str, options: &Options) -> String {
+    let mut name = template.to_string();
+    for token in TOKENS {
+        let pattern = format!("{{{token}}}");
+        if name.contains(&pattern) {
+            name = name.replace(&pattern, &value(token, options));
+        }
+    }
+    // tokens that expand to nothing leave empty segments behind
+    while name.contains("..") {
+        name = name.replace("..", ".");
+    }
+    let name = name.trim_matches('.');
+    if name.to_ascii_lowercase().ends_with(".pak") {
+        name.to_string()
+    } else {
+        format!("{name}.pak")
+    }
+}
+
+fn value(token: &str, options: &Options) -> String {
+    match token {
+        "input" => {
+            let file_name = options
+                .input_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match file_name.len().checked_sub(4) {
+                Some(end) if file_name[end..].eq_ignore_ascii_case(".pak") => {
+                    file_name[..end].to_string()
+                }
+                _ => file_name,
+            }
+        }
+        "mode" => {
+            if options.full_package {
+                "full".to_string()
+            } else {
+                "tex-only".to_string()
+            }
+        }
+        "transform" => {
+            let transform = &options.tex_transform;
+            let mut parts = vec![];
+            if transform.drop_top_mips > 0 {
+                parts.push(format!("drop{}", transform.drop_top_mips));
+            }
+            if transform.min_mip_size > 1 {
+                parts.push(format!("min{}", transform.min_mip_size));
+            }
+            if let Some(format) = transform.transcode {
+                parts.push(format.to_string());
+            }
+            parts.join(".")
+        }
+        "filters_hash" => {
+            // an unreadable overrides file fails the run later on
+            let options_json = manifest::options_json(options)
+                .map(|json| json.to_string())
+                .unwrap_or_default();
+            format!("{:08x}", crc32fast::hash(options_json.as_bytes()))
+        }
+        "game_build" => {
+            steam::build_id(&options.input_path).unwrap_or_else(|| "unknown".to_string())
+        }
+        _ => unreachable!("unknown token `{token}`"),
+    }
+}
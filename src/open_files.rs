@@ -0,0 +1,42 @@
+//! Global cap on the files held open at once, so workers, the read-ahead thread and the paks of
+//! an incremental update stay under the OS limit instead of failing with "too many open files".
+
+use parking_lot::{Condvar, Mutex, const_mutex};
+
+pub const DEFAULT_LIMIT: usize = 256;
+
+struct OpenFiles {
+    /// Open files and the limit.
+    state: Mutex<(usize, usize)>,
+    released: Condvar,
+}
+
+static OPEN_FILES: OpenFiles = OpenFiles {
+    state: const_mutex((0, DEFAULT_LIMIT)),
+    released: Condvar::new(),
+};
+
+/// Sets the limit, at least 2 so the read-ahead thread and a worker can always make progress.
+pub fn set_limit(limit: usize) {
+    OPEN_FILES.state.lock().1 = limit.max(2);
+    OPEN_FILES.released.notify_all();
+}
+
+/// Permission to hold a file open, released on drop. Blocks while the limit is reached.
+pub fn acquire() -> Permit {
+    let mut state = OPEN_FILES.state.lock();
+    while state.0 >= state.1 {
+        OPEN_FILES.released.wait(&mut state);
+    }
+    state.0 += 1;
+    Permit(())
+}
+
+pub struct Permit(());
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        OPEN_FILES.state.lock().0 -= 1;
+        OPEN_FILES.released.notify_one();
+    }
+}
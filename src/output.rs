@@ -0,0 +1,185 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ree_pak_core::{
+    pak::PakEntry,
+    write::{FileOptions, PakWriter},
+};
+
+use crate::process::{StoreOptions, write_to_pak};
+
+/// Path of the `part`-th (1-based) output file, the first part keeps the base path.
+pub fn part_path(base_path: &Path, part: usize) -> PathBuf {
+    if part <= 1 {
+        return base_path.to_path_buf();
+    }
+    base_path.with_extension(format!("patch_{part:03}.pak"))
+}
+
+/// Where a part is written until it's complete, so an interrupted run never leaves a file that
+/// looks finished.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".tmp");
+    PathBuf::from(path)
+}
+
+/// Moves the finished parts from their [`temp_path`] into place, and removes leftover parts of an
+/// earlier output that had more of them.
+pub fn commit(paths: &[PathBuf]) -> eyre::Result<()> {
+    for path in paths {
+        fs::rename(temp_path(path), path)?;
+    }
+    if let Some(base_path) = paths.first() {
+        for part in paths.len() + 1.. {
+            let stale_path = part_path(base_path, part);
+            if !stale_path.is_file() {
+                break;
+            }
+            fs::remove_file(stale_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// All parts of a previously written output that exist on disk, in order.
+pub fn existing_parts(base_path: &Path) -> Vec<PathBuf> {
+    (1..)
+        .map(|part| part_path(base_path, part))
+        .take_while(|path| path.is_file())
+        .collect()
+}
+
+/// Writes processed entries to the output pak, rolling over to a new part when the size cap is hit.
+///
+/// With a size cap or `in_source_order`, entries are written in source order regardless of which
+/// worker finishes first, so the entry-to-part assignment and the bytes of the output are the same
+/// on every run. Entries finished early wait in memory for the ones before them, the pipeline
+/// bounds how many are handed out ahead.
+pub struct OutputWriter {
+    base_path: PathBuf,
+    capacity: u64,
+    split_size: Option<u64>,
+    in_source_order: bool,
+    writer: PakWriter<File>,
+    part_bytes: u64,
+    part_entries: u64,
+    entries: u64,
+    paths: Vec<PathBuf>,
+    next_index: usize,
+    pending: BTreeMap<usize, Option<(PakEntry, Vec<u8>, StoreOptions)>>,
+}
+
+impl OutputWriter {
+    pub fn create(
+        base_path: &Path,
+        capacity: u64,
+        split_size: Option<u64>,
+        in_source_order: bool,
+    ) -> eyre::Result<Self> {
+        let writer = create_pak_writer(&temp_path(base_path), capacity)?;
+        Ok(Self {
+            base_path: base_path.to_path_buf(),
+            capacity,
+            split_size,
+            in_source_order,
+            writer,
+            part_bytes: 0,
+            part_entries: 0,
+            entries: 0,
+            paths: vec![base_path.to_path_buf()],
+            next_index: 0,
+            pending: BTreeMap::new(),
+        })
+    }
+
+    fn is_ordered(&self) -> bool {
+        self.in_source_order || self.split_size.is_some()
+    }
+
+    /// Submits the result of the `index`-th source entry, `None` if it was skipped.
+    ///
+    /// Returns the number of bytes written by this call.
+    pub fn submit(
+        &mut self,
+        index: usize,
+        item: Option<(&PakEntry, Vec<u8>, StoreOptions)>,
+    ) -> eyre::Result<usize> {
+        if !self.is_ordered() {
+            return match item {
+                Some((entry, data, store)) => self.write(entry, &data, store),
+                None => Ok(0),
+            };
+        }
+
+        self.pending.insert(
+            index,
+            item.map(|(entry, data, store)| (entry.clone(), data, store)),
+        );
+        let mut written = 0;
+        while let Some(item) = self.pending.remove(&self.next_index) {
+            if let Some((entry, data, store)) = item {
+                written += self.write(&entry, &data, store)?;
+            }
+            self.next_index += 1;
+        }
+        Ok(written)
+    }
+
+    fn write(&mut self, entry: &PakEntry, data: &[u8], store: StoreOptions) -> eyre::Result<usize> {
+        let exceeds_split_size = self
+            .split_size
+            .is_some_and(|split_size| self.part_bytes + data.len() as u64 > split_size);
+        if exceeds_split_size && self.part_entries > 0 {
+            self.roll_over()?;
+        }
+
+        let written = write_to_pak(&mut self.writer, entry, entry.hash(), data, store)?;
+        self.part_bytes += written as u64;
+        self.part_entries += 1;
+        self.entries += 1;
+        Ok(written)
+    }
+
+    fn roll_over(&mut self) -> eyre::Result<()> {
+        let path = part_path(&self.base_path, self.paths.len() + 1);
+        let writer = create_pak_writer(&temp_path(&path), self.capacity)?;
+        std::mem::replace(&mut self.writer, writer).finish()?;
+        self.paths.push(path);
+        self.part_bytes = 0;
+        self.part_entries = 0;
+        Ok(())
+    }
+
+    /// Writes an entry that has no source entry, e.g. the build info, to the current part.
+    pub fn append(&mut self, hash: u64, data: &[u8]) -> eyre::Result<()> {
+        self.writer.start_file(hash, FileOptions::default())?;
+        self.writer.write_all(data)?;
+        self.entries += 1;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    /// Finishes the current part and returns the paths of all written parts. They're still at
+    /// their [`temp_path`] until [`commit`]ted.
+    pub fn finish(self) -> eyre::Result<Vec<PathBuf>> {
+        self.writer.finish()?;
+        Ok(self.paths)
+    }
+}
+
+fn create_pak_writer(path: &Path, capacity: u64) -> eyre::Result<PakWriter<File>> {
+    let out_file = OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(path)?;
+    Ok(PakWriter::new(out_file, capacity))
+}
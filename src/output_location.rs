@@ -0,0 +1,110 @@
+//! Folders where writing the output tends to fail or crawl: Program Files (needs admin rights,
+//! writes may be silently redirected to the VirtualStore) and OneDrive (uploads every written
+//! byte, files may be online-only placeholders).
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{progress::Progress, util};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Concern {
+    ProtectedDir,
+    CloudSynced,
+}
+
+impl Concern {
+    /// Short note for lists of paths.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ProtectedDir => "in Program Files",
+            Self::CloudSynced => "in OneDrive",
+        }
+    }
+
+    fn explanation(self) -> &'static str {
+        match self {
+            Self::ProtectedDir => {
+                "Program Files needs administrator rights to write to, without them writes fail or end up in the VirtualStore where the game doesn't look. Move the Steam library to a folder like `D:\\SteamLibrary` (Steam > Settings > Storage), or run the tool as administrator."
+            }
+            Self::CloudSynced => {
+                "OneDrive uploads every byte written to it and may keep files online-only, which makes a multi-GB output slow and fragile. Copy the input pak to a local folder outside OneDrive and run on that copy."
+            }
+        }
+    }
+}
+
+fn env_dirs(names: &[&str]) -> Vec<PathBuf> {
+    names
+        .iter()
+        .filter_map(|name| env::var_os(name))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Whether the path is in a folder known to cause trouble, the env vars only exist on Windows.
+pub fn concern(path: &Path) -> Option<Concern> {
+    // components compare case-sensitively, Windows paths don't
+    let path = path.to_string_lossy().to_lowercase();
+    let is_under = |dirs: Vec<PathBuf>| {
+        dirs.iter().any(|dir| {
+            let dir = dir.to_string_lossy().to_lowercase();
+            !dir.is_empty() && path.contains(dir.trim_end_matches('\\'))
+        })
+    };
+    if is_under(env_dirs(&[
+        "ProgramFiles",
+        "ProgramFiles(x86)",
+        "ProgramW6432",
+    ])) {
+        Some(Concern::ProtectedDir)
+    } else if is_under(env_dirs(&[
+        "OneDrive",
+        "OneDriveConsumer",
+        "OneDriveCommercial",
+    ])) {
+        Some(Concern::CloudSynced)
+    } else {
+        None
+    }
+}
+
+/// Warns about a troublesome output folder and fails early, with the reason, if it can't be
+/// written to.
+pub fn check(output_path: &Path, progress: &dyn Progress) -> eyre::Result<()> {
+    let dir = output_path.parent().unwrap_or(Path::new("."));
+    let concern = concern(dir);
+    if let Some(concern) = concern {
+        progress.warn(&format!(
+            "The output goes {} ({}). {}",
+            concern.label(),
+            util::display_path(dir),
+            concern.explanation()
+        ));
+    }
+
+    let probe_path = dir.join(format!(".{}-write-test", env!("CARGO_PKG_NAME")));
+    match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&probe_path)
+    {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe_path);
+            Ok(())
+        }
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let hint = concern.unwrap_or(Concern::ProtectedDir).explanation();
+            eyre::bail!(
+                "can't write to `{}`, where the output goes next to the input: {e}. {hint}",
+                util::display_path(dir)
+            )
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,109 @@
+//! Per-entry overrides from a JSON file, applied when the output is written:
+//!
+//! ```json
+//! {
+//!   "entries": [
+//!     { "entry": "GUI/**/*.tex.*", "include": false },
+//!     { "entry": "natives/STM/Sound/", "include": true, "compression": "keep" },
+//!     { "entry": "0x1234ABCD5678EF90", "feature_clone": false }
+//!   ]
+//! }
+//! ```
+//!
+//! `entry` takes the same identifiers as the command line. An entry matched by several rules gets
+//! the settings of all of them, later rules win.
+
+use std::{fs, path::Path};
+
+use serde_json::Value;
+
+use crate::{exit_code::InvalidInput, names::NameTable, selector::EntrySelector};
+
+/// Settings of an entry that differ from the run's options, `None` where they don't.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EntryOverride {
+    /// Always include (`true`) or leave out (`false`) the entry, regardless of the selection.
+    pub include: Option<bool>,
+    /// Compress the entry like the source (`"keep"`) or store it uncompressed (`"none"`).
+    pub keep_compression: Option<bool>,
+    /// Clone the feature flags of the source entry.
+    pub feature_clone: Option<bool>,
+}
+
+impl EntryOverride {
+    fn merge(&mut self, other: &Self) {
+        self.include = other.include.or(self.include);
+        self.keep_compression = other.keep_compression.or(self.keep_compression);
+        self.feature_clone = other.feature_clone.or(self.feature_clone);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Overrides {
+    rules: Vec<(EntrySelector, EntryOverride)>,
+}
+
+impl Overrides {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let invalid = |cause: String| InvalidInput::new(path, cause);
+        let file: Value =
+            serde_json::from_slice(&fs::read(path)?).map_err(|e| invalid(e.to_string()))?;
+        let rules = file["entries"]
+            .as_array()
+            .ok_or_else(|| invalid("expected an `entries` list".to_string()))?;
+
+        let mut overrides = Self::default();
+        for (i, rule) in rules.iter().enumerate() {
+            let parse_rule = || -> Result<(EntrySelector, EntryOverride), String> {
+                let selector = rule["entry"]
+                    .as_str()
+                    .ok_or("missing `entry`")?
+                    .parse::<EntrySelector>()?;
+                let flag = |key: &str| match &rule[key] {
+                    Value::Null => Ok(None),
+                    Value::Bool(value) => Ok(Some(*value)),
+                    _ => Err(format!("`{key}` must be true or false")),
+                };
+                let keep_compression = match &rule["compression"] {
+                    Value::Null => None,
+                    Value::String(value) if value == "keep" => Some(true),
+                    Value::String(value) if value == "none" => Some(false),
+                    _ => return Err("`compression` must be \"keep\" or \"none\"".to_string()),
+                };
+                Ok((
+                    selector,
+                    EntryOverride {
+                        include: flag("include")?,
+                        keep_compression,
+                        feature_clone: flag("feature_clone")?,
+                    },
+                ))
+            };
+            let rule = parse_rule().map_err(|e| invalid(format!("entry {}: {e}", i + 1)))?;
+            overrides.rules.push(rule);
+        }
+        Ok(overrides)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn selectors(&self) -> Vec<EntrySelector> {
+        self.rules
+            .iter()
+            .map(|(selector, _)| selector.clone())
+            .collect()
+    }
+
+    /// Merged settings of all rules matching the entry.
+    pub fn get(&self, hash: u64, names: &NameTable) -> EntryOverride {
+        let mut merged = EntryOverride::default();
+        for (selector, entry_override) in &self.rules {
+            if selector.matches(hash, names) {
+                merged.merge(entry_override);
+            }
+        }
+        merged
+    }
+}
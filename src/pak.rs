@@ -0,0 +1,122 @@
+use std::{io, path::Path};
+
+use ree_pak_core::{
+    pak::{PakArchive, PakEntry},
+    read::archive::PakArchiveReader,
+};
+
+use crate::{
+    exit_code::InvalidInput,
+    storage::{ReadSeek, Storage, StorageKind},
+    throttle,
+};
+
+/// A pak archive, readable from several threads at once.
+pub struct PakFile {
+    archive: PakArchive,
+    storage: Box<dyn Storage>,
+}
+
+impl PakFile {
+    pub fn open(path: &Path) -> eyre::Result<Self> {
+        Self::open_with(path, StorageKind::Fs)
+    }
+
+    /// Opens the pak with entries read through the given storage backend.
+    pub fn open_with(path: &Path, kind: StorageKind) -> eyre::Result<Self> {
+        let storage = kind.open(path)?;
+        let archive = ree_pak_core::read::read_archive(&mut storage.reader()?)
+            .map_err(|e| InvalidInput::new(path, e))?;
+        Ok(Self { archive, storage })
+    }
+
+    pub fn archive(&self) -> &PakArchive {
+        &self.archive
+    }
+
+    pub fn entries(&self) -> &[PakEntry] {
+        self.archive.entries()
+    }
+
+    /// Reads the decompressed data of the entry.
+    pub fn read_entry(&self, entry: &PakEntry) -> eyre::Result<Vec<u8>> {
+        // a new reader for each call to avoid lock contention between threads
+        read_entry_from(self.storage.reader()?, &self.archive, entry)
+    }
+
+    /// A reader for one thread that reads entries one after another through a single reader.
+    pub fn sequential_reader(&self) -> eyre::Result<SequentialReader<'_>> {
+        Ok(SequentialReader {
+            pak: self,
+            reader: self.storage.reader()?,
+        })
+    }
+
+    /// Decompresses an entry from its stored bytes, read by the caller from the entry's offset.
+    #[cfg(feature = "async-io")]
+    pub fn decode_stored(&self, entry: &PakEntry, stored: Vec<u8>) -> eyre::Result<Vec<u8>> {
+        let reader = StoredEntry {
+            offset: entry.offset(),
+            data: io::Cursor::new(stored),
+        };
+        read_entry_from(reader, &self.archive, entry)
+    }
+}
+
+pub struct SequentialReader<'a> {
+    pak: &'a PakFile,
+    reader: Box<dyn ReadSeek + 'a>,
+}
+
+impl SequentialReader<'_> {
+    /// Reads the decompressed data of the entry, cheapest when called in offset order.
+    pub fn read_entry(&mut self, entry: &PakEntry) -> eyre::Result<Vec<u8>> {
+        read_entry_from(&mut self.reader, &self.pak.archive, entry)
+    }
+}
+
+/// The stored bytes of a single entry, seekable at their offset in the pak.
+#[cfg(feature = "async-io")]
+struct StoredEntry {
+    offset: u64,
+    data: io::Cursor<Vec<u8>>,
+}
+
+#[cfg(feature = "async-io")]
+impl io::Read for StoredEntry {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.data.read(buf)
+    }
+}
+
+#[cfg(feature = "async-io")]
+impl io::Seek for StoredEntry {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            io::SeekFrom::Start(pos) => {
+                let Some(pos) = pos.checked_sub(self.offset) else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "seek before the stored entry",
+                    ));
+                };
+                io::SeekFrom::Start(pos)
+            }
+            pos => pos,
+        };
+        Ok(self.data.seek(pos)? + self.offset)
+    }
+}
+
+fn read_entry_from<R>(reader: R, archive: &PakArchive, entry: &PakEntry) -> eyre::Result<Vec<u8>>
+where
+    R: io::Read + io::Seek,
+{
+    let mut archive_reader = PakArchiveReader::new(reader, archive);
+    let mut entry_reader = archive_reader.owned_entry_reader(entry.clone())?;
+
+    let mut buf = Vec::with_capacity(entry.uncompressed_size() as usize);
+    io::copy(&mut entry_reader, &mut buf)?;
+    throttle::consume(entry.compressed_size());
+    Ok(buf)
+}
@@ -0,0 +1,125 @@
+//! Version and feature flags in the header of a pak.
+//!
+//! Layout (little endian): magic `KPKA`, `u8` major version, `u8` minor version, `u16` feature
+//! flags, followed by the entry count and the rest of the header which are left alone.
+
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::exit_code::InvalidInput;
+
+const MAGIC: &[u8; 4] = b"KPKA";
+/// The entry table is encrypted, set by the writer depending on how it wrote the table.
+const FEATURE_ENCRYPTED_TOC: u16 = 0x0008;
+/// Entry records in the table: `u32` hash lower, `u32` hash upper, `u64` offset, `u64` compressed
+/// size, `u64` size, `u64` attributes, `u64` checksum.
+pub const ENTRY_RECORD_SIZE: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PakVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl FromStr for PakVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s.trim().split_once('.').unwrap_or((s.trim(), "0"));
+        let parse = |part: &str| {
+            part.parse::<u8>()
+                .map_err(|_| format!("invalid pak version `{s}`, expected e.g. `4.1`"))
+        };
+        Ok(Self {
+            major: parse(major)?,
+            minor: parse(minor)?,
+        })
+    }
+}
+
+impl fmt::Display for PakVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Parses feature flags given in hex (`0x10`, `10`).
+pub fn parse_features(s: &str) -> Result<u16, String> {
+    let s = s.trim();
+    let hex = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u16::from_str_radix(hex, 16).map_err(|_| format!("invalid feature flags `{s}`"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PakHeader {
+    pub version: PakVersion,
+    pub features: u16,
+}
+
+impl PakHeader {
+    pub fn read(path: &Path) -> eyre::Result<Self> {
+        let mut header = [0u8; 8];
+        fs::File::open(path)?.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(InvalidInput::new(path, "not a pak file").into());
+        }
+        Ok(Self {
+            version: PakVersion {
+                major: header[4],
+                minor: header[5],
+            },
+            features: u16::from_le_bytes([header[6], header[7]]),
+        })
+    }
+
+    /// Header of an output written by us, `None` values are taken from `source`.
+    ///
+    /// The encrypted entry table flag of `written` is kept unless the features are given, it
+    /// describes how the writer stored the table rather than anything the game checks.
+    pub fn for_output(
+        written: Self,
+        source: Self,
+        version: Option<PakVersion>,
+        features: Option<u16>,
+    ) -> Self {
+        let features = features.unwrap_or(
+            (source.features & !FEATURE_ENCRYPTED_TOC) | (written.features & FEATURE_ENCRYPTED_TOC),
+        );
+        Self {
+            version: version.unwrap_or(source.version),
+            features,
+        }
+    }
+
+    /// Whether the entry table is encrypted, it can't be patched in place then.
+    pub fn has_encrypted_toc(&self) -> bool {
+        self.features & FEATURE_ENCRYPTED_TOC != 0
+    }
+
+    /// Overwrites the version and feature flags of a finished pak.
+    pub fn write_to(&self, path: &Path) -> eyre::Result<()> {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.seek(SeekFrom::Start(MAGIC.len() as u64))?;
+        file.write_all(&[self.version.major, self.version.minor])?;
+        file.write_all(&self.features.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for PakHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "version {}, features {:#06x}",
+            self.version, self.features
+        )
+    }
+}
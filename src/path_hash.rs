@@ -0,0 +1,56 @@
+//! Entry hashes of pak file paths: murmur3 (x86, 32 bit, seed `0xFFFFFFFF`) of the UTF-16LE
+//! lowercase path in the low half and of the uppercase path in the high half.
+
+use unicode_normalization::UnicodeNormalization;
+
+const SEED: u32 = 0xFFFF_FFFF;
+
+/// Canonical form of a path from a community list: NFC, `/` separators and no leading `/`. The
+/// hash is case-insensitive already, but a decomposed `é` or a `\` hashes differently.
+pub fn normalize_path(path: &str) -> String {
+    let path: String = path.trim().nfc().collect();
+    path.replace('\\', "/").trim_start_matches('/').to_string()
+}
+
+pub fn hash_path(path: &str) -> u64 {
+    let lower = murmur3_utf16(&path.to_lowercase());
+    let upper = murmur3_utf16(&path.to_uppercase());
+    (upper as u64) << 32 | lower as u64
+}
+
+fn murmur3_utf16(s: &str) -> u32 {
+    let bytes: Vec<u8> = s.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    murmur3_32(&bytes, SEED)
+}
+
+fn murmur3_32(data: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xCC9E_2D51;
+    const C2: u32 = 0x1B87_3593;
+
+    let mut h = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        let mut k = u32::from_le_bytes(block.try_into().unwrap());
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h ^= k;
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xE654_6B64);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k = tail
+            .iter()
+            .rev()
+            .fold(0u32, |k, &byte| (k << 8) | byte as u32);
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h ^= k;
+    }
+
+    h ^= data.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xC2B2_AE35);
+    h ^= h >> 16;
+    h
+}
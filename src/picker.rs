@@ -0,0 +1,205 @@
+//! Terminal UI to hand-pick the entries that go into the output, with search-as-you-type.
+
+use std::collections::HashSet;
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+
+use crate::family;
+
+const HELP: &str = "type to search  up/down move  space toggle  ctrl+f toggle family  ctrl+a toggle all shown  enter confirm  esc cancel";
+
+struct Item {
+    hash: u64,
+    name: String,
+    /// Lowercase name to match the query against.
+    key: String,
+    /// Texture family, see [`family`].
+    family: String,
+}
+
+struct Picker {
+    items: Vec<Item>,
+    query: String,
+    /// Indices into `items` matching the query.
+    shown: Vec<usize>,
+    selected: HashSet<u64>,
+    cursor: usize,
+    offset: usize,
+}
+
+/// Lets the user pick from `(hash, name)` entries, `None` if cancelled.
+pub fn pick(entries: Vec<(u64, String)>) -> eyre::Result<Option<HashSet<u64>>> {
+    let mut items: Vec<Item> = entries
+        .into_iter()
+        .map(|(hash, name)| Item {
+            hash,
+            key: name.to_lowercase(),
+            family: family::key(&name).to_string(),
+            name,
+        })
+        .collect();
+    items.sort_by(|a, b| a.key.cmp(&b.key));
+    let mut picker = Picker {
+        shown: (0..items.len()).collect(),
+        items,
+        query: String::new(),
+        selected: HashSet::new(),
+        cursor: 0,
+        offset: 0,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = picker.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Picker {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> eyre::Result<Option<HashSet<u64>>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            // Windows reports releases too
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Enter => return Ok(Some(std::mem::take(&mut self.selected))),
+                KeyCode::Up => self.cursor = self.cursor.saturating_sub(1),
+                KeyCode::Down => self.cursor += 1,
+                KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(20),
+                KeyCode::PageDown => self.cursor += 20,
+                KeyCode::Char(' ') => self.toggle_current(),
+                KeyCode::Char('a') if ctrl => self.toggle_shown(),
+                KeyCode::Char('f') if ctrl => self.toggle_family(),
+                KeyCode::Char(c) if !ctrl => {
+                    self.query.push(c);
+                    self.filter();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.filter();
+                }
+                _ => {}
+            }
+            self.cursor = self.cursor.min(self.shown.len().saturating_sub(1));
+        }
+    }
+
+    fn filter(&mut self) {
+        let query = self.query.to_lowercase();
+        self.shown = (0..self.items.len())
+            .filter(|&i| self.items[i].key.contains(&query))
+            .collect();
+        self.cursor = 0;
+        self.offset = 0;
+    }
+
+    fn toggle_current(&mut self) {
+        if let Some(&i) = self.shown.get(self.cursor) {
+            let hash = self.items[i].hash;
+            if !self.selected.remove(&hash) {
+                self.selected.insert(hash);
+            }
+        }
+    }
+
+    /// Selects all shown entries, or deselects them if they all are selected already.
+    fn toggle_shown(&mut self) {
+        let hashes: Vec<u64> = self.shown.iter().map(|&i| self.items[i].hash).collect();
+        self.toggle_all(hashes);
+    }
+
+    /// Toggles the whole family of the current entry, shown or not.
+    fn toggle_family(&mut self) {
+        let Some(&current) = self.shown.get(self.cursor) else {
+            return;
+        };
+        let family = &self.items[current].family;
+        let hashes: Vec<u64> = self
+            .items
+            .iter()
+            .filter(|item| item.family == *family)
+            .map(|item| item.hash)
+            .collect();
+        self.toggle_all(hashes);
+    }
+
+    /// Selects all `hashes`, or deselects them if they all are selected already.
+    fn toggle_all(&mut self, hashes: Vec<u64>) {
+        if hashes.iter().all(|hash| self.selected.contains(hash)) {
+            hashes.iter().for_each(|hash| {
+                self.selected.remove(hash);
+            });
+        } else {
+            self.selected.extend(hashes);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(self.query.as_str()).block(Block::bordered().title(format!(
+                "Search ({} shown, {} selected)",
+                self.shown.len(),
+                self.selected.len()
+            ))),
+            search_area,
+        );
+
+        // only the visible window is built, the list can have 100k entries
+        let height = list_area.height as usize;
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if height > 0 && self.cursor >= self.offset + height {
+            self.offset = self.cursor + 1 - height;
+        }
+        let lines: Vec<Line> = self
+            .shown
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(height)
+            .map(|(row, &i)| {
+                let item = &self.items[i];
+                let check = if self.selected.contains(&item.hash) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let line = Line::from(format!("{check} {}", item.name));
+                if row == self.cursor {
+                    line.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    line
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), list_area);
+        frame.render_widget(
+            Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)),
+            help_area,
+        );
+    }
+}
@@ -0,0 +1,1339 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::WrapErr;
+use indicatif::HumanBytes;
+use parking_lot::Mutex;
+use rayon::{
+    ThreadPoolBuilder,
+    iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+};
+use ree_pak_core::pak::PakEntry;
+
+#[cfg(feature = "async-io")]
+use crate::async_read;
+#[cfg(feature = "scripting")]
+use crate::script::Script;
+use crate::{
+    alignment, build_info,
+    cancel::{self, Cancelled},
+    category::Category,
+    compression::OutputCompression,
+    content_index::ContentIndex,
+    dedup::DuplicateFinder,
+    diagnostics::FailedEntry,
+    dir_stats::DirStats,
+    estimate::{self, SampleSize},
+    exit_code::InputNotFound,
+    fluffy::{self, ModInfo},
+    free_space, handler, impact,
+    incremental::{PreviousOutput, SourceFingerprint, SourceIndex, SourceRecord},
+    input_hints,
+    manifest::{self, Audit, EntryChecksum},
+    metadata::TrailingBlock,
+    names::NameTable,
+    naming, open_files,
+    output::{self, OutputWriter},
+    output_location,
+    overrides::{EntryOverride, Overrides},
+    pak::PakFile,
+    pak_header::{PakHeader, PakVersion},
+    priority,
+    process::{StoreOptions, TexTransform, estimate_tex_size, is_tex_entry, process_entry},
+    processed_input,
+    progress::Progress,
+    ratio_check::RatioCheck,
+    read_ahead, retry,
+    selector::{self, EntrySelector},
+    session_lock::SessionLock,
+    shard::Shard,
+    signing,
+    stats::Stats,
+    storage::StorageKind,
+    streaming,
+    tex_format::TexFormat,
+    tex_header::{self, TexHeader},
+    throttle,
+    util::{self, SplitMix64},
+    verify,
+};
+
+/// Default read-ahead depth per worker thread.
+const READ_AHEAD_PER_THREAD: usize = 4;
+/// Entries per worker thread an output written in source order may hold back in memory, waiting
+/// for an earlier entry.
+const IN_ORDER_WINDOW_PER_THREAD: usize = 8;
+
+/// Source data of an entry read ahead of the workers, `None` if it wasn't.
+type Prefetched = eyre::Result<Option<Vec<u8>>>;
+
+/// Why a read entry isn't written to the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LeftOut {
+    NotTex,
+    Size,
+    Format,
+    Script,
+}
+
+impl LeftOut {
+    /// What the verbose log and the manifest say about the entry.
+    fn action(self) -> &'static str {
+        match self {
+            Self::NotTex => "left out, not a tex file",
+            Self::Size => "skipped by size",
+            Self::Format => "skipped by format",
+            Self::Script => "left out by the script",
+        }
+    }
+}
+
+/// Everything that decides what a decompression run produces.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub input_path: PathBuf,
+    /// Output pak, `None` for next to the input.
+    pub output: Option<PathBuf>,
+    /// File name template of the output next to the input, see [`naming`].
+    pub name_template: Option<String>,
+    /// Package all files, including non-tex files.
+    pub full_package: bool,
+    /// Clone feature flags from the original entries.
+    pub feature_clone: bool,
+    /// Keep the compression of the source entries for non-tex entries.
+    pub keep_compression: bool,
+    /// Codec of the output entries per class, over `keep_compression`.
+    pub compression: OutputCompression,
+    /// Tex categories to include, ignored in full package mode.
+    pub categories: Vec<Category>,
+    /// Pixel formats of the textures to include, empty for all.
+    pub formats: Vec<TexFormat>,
+    /// Previous output to reuse unchanged entries from.
+    pub previous_output: Option<PathBuf>,
+    pub thread_count: usize,
+    pub split_size: Option<u64>,
+    /// Write an output per tex category instead of a single one.
+    pub split_by_category: bool,
+    /// Percent of entries to verify after writing, 0 to skip.
+    pub verify_sample: f64,
+    /// Seed picking the verified entries, to reproduce an earlier run. Random if `None`.
+    pub verify_seed: Option<u64>,
+    /// Percent of the textures decompressed up front to estimate the output size and runtime.
+    pub estimate: Option<f64>,
+    /// Ask whether to go on after printing the estimate.
+    pub confirm_estimate: bool,
+    /// Skip tex files whose decompressed size is larger than this.
+    pub skip_larger_than: Option<u64>,
+    /// Skip tex files whose decompressed size is smaller than this.
+    pub skip_smaller_than: Option<u64>,
+    /// Only select entries whose uncompressed size in the source pak is at least this.
+    pub min_entry_size: Option<u64>,
+    /// Only select entries whose uncompressed size in the source pak is at most this.
+    pub max_entry_size: Option<u64>,
+    pub tex_transform: TexTransform,
+    /// Write a binary content index next to every output part.
+    pub content_index: bool,
+    /// Non-tex entries copied in tex-only mode.
+    pub copy_paths: Vec<EntrySelector>,
+    /// Write the non-tex entries left out of a tex-only output to this file.
+    pub non_tex_list: Option<PathBuf>,
+    /// Write a `manifest.json` with checksums of the output and its entries.
+    pub manifest: bool,
+    /// Sign the manifest with the Ed25519 key in this file.
+    pub sign_key: Option<PathBuf>,
+    /// Also write a variant of the output per size, with the textures capped to it.
+    pub variants: Vec<u16>,
+    /// Embed a build info entry with the tool version, source pak and options.
+    pub embed_info: bool,
+    /// Only include the N textures with the highest streaming cost.
+    pub top_impact: Option<usize>,
+    /// Header version of the output, `None` to match the source.
+    pub pak_version: Option<PakVersion>,
+    /// Header feature flags of the output, `None` to match the source.
+    pub pak_features: Option<u16>,
+    /// Number of raw entries the reader thread reads ahead of the workers, 0 to let every
+    /// worker read its own entries. `None` for a default based on the thread count.
+    pub read_ahead: Option<usize>,
+    /// How the source pak is read.
+    pub storage: StorageKind,
+    /// Read entries concurrently on tokio instead of a single reader thread.
+    pub async_io: bool,
+    /// Report output entries with identical data and the space sharing it would save.
+    pub report_duplicates: bool,
+    /// Maximum number of files held open at once.
+    pub max_open_files: usize,
+    /// Start the data of every output entry at a multiple of this many bytes.
+    pub align: Option<u64>,
+    /// Write the entries in source order, for reproducible outputs.
+    pub deterministic: bool,
+    /// Times a failed entry read is tried again before the run stops.
+    pub retries: u32,
+    /// Run the workers at a lower scheduling priority.
+    pub low_priority: bool,
+    /// Cap on the bytes read and written per second.
+    pub io_throttle: Option<u64>,
+    /// Write the source entries missing from the file name table to this file.
+    pub unknown_list: Option<PathBuf>,
+    /// Write the names of all source entries to this file.
+    pub names_dump: Option<PathBuf>,
+    /// Run even if the input looks like an output of the tool.
+    pub allow_processed_input: bool,
+    /// Check that the estimated output size fits on the output drive before processing.
+    pub check_free_space: bool,
+    /// JSON file with per-entry overrides of inclusion and how entries are stored.
+    pub overrides: Option<PathBuf>,
+    /// Rhai script filtering and transforming entries.
+    pub script: Option<PathBuf>,
+    /// Only include these tex entries, hand-picked by the user.
+    pub picked_entries: Option<HashSet<u64>>,
+    /// Overwrite an existing output.
+    pub force: bool,
+    /// Only write these entries, to rebuild an output from its manifest.
+    pub only_entries: Option<HashSet<u64>>,
+    /// Only process this share of the selected entries.
+    pub shard: Option<Shard>,
+    /// Pack the output into a zip for Fluffy Mod Manager.
+    pub fluffy: Option<ModInfo>,
+}
+
+impl Options {
+    pub fn output_path(&self) -> PathBuf {
+        match (&self.output, &self.name_template) {
+            (Some(output), _) => output.clone(),
+            (None, Some(template)) => self
+                .input_path
+                .with_file_name(naming::expand(template, self)),
+            (None, None) => match self.shard {
+                Some(shard) => self.input_path.with_extension(format!(
+                    "uncompressed.shard{}of{}.pak",
+                    shard.index, shard.count
+                )),
+                None => self.input_path.with_extension("uncompressed.pak"),
+            },
+        }
+    }
+
+    /// Output of the variant with textures capped to `max_size`.
+    pub fn variant_path(&self, max_size: u16) -> PathBuf {
+        self.output_path()
+            .with_extension(format!("max{max_size}.pak"))
+    }
+
+    /// Output of the entries of `category` with [`Options::split_by_category`].
+    pub fn category_path(&self, category: Category) -> PathBuf {
+        self.output_path()
+            .with_extension(format!("{}.pak", category.id()))
+    }
+
+    /// Whether the run would replace an output that isn't the previous output it builds on.
+    pub fn overwrites_output(&self) -> bool {
+        let output_path = self.output_path();
+        output_path.is_file() && self.previous_output.as_deref() != Some(output_path.as_path())
+    }
+
+    /// Whether the outputs are written in source order, see [`OutputWriter`].
+    fn writes_in_order(&self) -> bool {
+        self.deterministic || self.split_size.is_some()
+    }
+
+    fn filters_categories(&self) -> bool {
+        !self.full_package && self.categories.len() < Category::ALL.len()
+    }
+
+    fn filters_tex_size(&self) -> bool {
+        self.skip_larger_than.is_some() || self.skip_smaller_than.is_some()
+    }
+
+    fn accepts_tex_size(&self, size: u64) -> bool {
+        self.skip_larger_than.is_none_or(|max| size <= max)
+            && self.skip_smaller_than.is_none_or(|min| size >= min)
+    }
+
+    fn filters_formats(&self) -> bool {
+        !self.formats.is_empty()
+    }
+
+    /// Whether the tex is in a selected format, from its header. Unreadable headers are kept,
+    /// decompressing reports them.
+    fn accepts_format(&self, raw: &[u8]) -> bool {
+        match TexHeader::parse(raw) {
+            Ok(header) => self.formats.contains(&TexFormat::of(header.format)),
+            Err(_) => true,
+        }
+    }
+
+    fn filters_entry_size(&self) -> bool {
+        self.min_entry_size.is_some() || self.max_entry_size.is_some()
+    }
+
+    fn accepts_entry_size(&self, size: u64) -> bool {
+        self.max_entry_size.is_none_or(|max| size <= max)
+            && self.min_entry_size.is_none_or(|min| size >= min)
+    }
+
+    /// Whether a non-tex entry is copied as is.
+    fn copies_non_tex(&self, name_table: &NameTable, hash: u64) -> bool {
+        self.copy_paths
+            .iter()
+            .any(|selector| selector.matches(hash, name_table))
+    }
+
+    /// How an entry is stored in the output, taking its overrides into account.
+    fn store_options(&self, is_tex: bool, entry_override: &EntryOverride) -> StoreOptions {
+        StoreOptions {
+            feature_clone: entry_override.feature_clone.unwrap_or(self.feature_clone),
+            // tex entries are decompressed, that's the point of the output
+            keep_compression: entry_override
+                .keep_compression
+                .unwrap_or(self.keep_compression && !is_tex),
+            // an override of the entry says how it's stored, not the class
+            codec: match entry_override.keep_compression {
+                Some(_) => None,
+                None => self.compression.codec(is_tex),
+            },
+        }
+    }
+}
+
+pub struct Summary {
+    pub output_paths: Vec<PathBuf>,
+    pub entries_written: usize,
+    pub bytes_written: u64,
+    /// The error that terminated processing early, the output holds everything before it.
+    pub processing_error: Option<eyre::Report>,
+}
+
+pub fn run(
+    options: &Options,
+    name_table: &NameTable,
+    progress: &dyn Progress,
+) -> eyre::Result<Summary> {
+    // long paths for the input and everything next to it, incl. the output
+    let options = &Options {
+        input_path: util::long_path(&options.input_path),
+        output: options.output.as_deref().map(util::long_path),
+        previous_output: options.previous_output.as_deref().map(util::long_path),
+        ..options.clone()
+    };
+    if !options.input_path.is_file() {
+        return Err(InputNotFound::new("input file", &options.input_path)
+            .with_hints(input_hints::hints(&options.input_path))
+            .into());
+    }
+    if let Some(sub_pak) = input_hints::sub_paks(&options.input_path).first() {
+        progress.warn(&format!(
+            "`{}` has sub paks, the textures are usually in `{}`.",
+            util::display_path(&options.input_path),
+            util::display_path(sub_pak)
+        ));
+    }
+    output_location::check(&options.output_path(), progress)?;
+    // held until the run returns
+    let _lock = SessionLock::acquire(&options.output_path())?;
+    if options.overwrites_output() && !options.force {
+        eyre::bail!(
+            "`{}` already exists, use `--force` to overwrite it.",
+            util::display_path(&options.output_path())
+        );
+    }
+    if options.filters_categories() && !name_table.is_loaded() {
+        eyre::bail!("category selection requires a file name table.");
+    }
+    selector::check_names(&options.copy_paths, name_table)?;
+    if options.full_package && options.top_impact.is_some() {
+        eyre::bail!("selecting textures by streaming cost doesn't work with full package mode.");
+    }
+    if options.full_package && options.picked_entries.is_some() {
+        eyre::bail!("picking textures doesn't work with full package mode.");
+    }
+    if options.split_by_category {
+        if options.full_package {
+            eyre::bail!("splitting the output by category doesn't work with full package mode.");
+        }
+        if options.previous_output.is_some() {
+            eyre::bail!(
+                "incremental updates can't be combined with splitting the output by category."
+            );
+        }
+        if !name_table.is_loaded() {
+            eyre::bail!("splitting the output by category requires a file name table.");
+        }
+    }
+    if options.full_package && options.filters_entry_size() {
+        eyre::bail!(
+            "`--min-size` and `--max-size` select entries, they don't work with full package mode. Use `--skip-larger-than` or `--skip-smaller-than` instead."
+        );
+    }
+
+    open_files::set_limit(options.max_open_files);
+    throttle::set_limit(options.io_throttle);
+    // the worker pool built below inherits it
+    if let Some(Err(e)) = options.low_priority.then(priority::lower) {
+        progress.warn(&format!("Failed to lower the process priority: {e}"));
+    }
+
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(options.thread_count)
+        .build()
+        .map_err(|e| eyre::eyre!("Failed to configure thread pool: {}", e))?;
+    pool.install(|| run_in_pool(options, name_table, progress))
+}
+
+fn run_in_pool(
+    options: &Options,
+    name_table: &NameTable,
+    progress: &dyn Progress,
+) -> eyre::Result<Summary> {
+    let input_path = options.input_path.as_path();
+    let use_full_package_mode = options.full_package;
+    // a bad key should fail before hours of processing, not after
+    let signing_key = options
+        .sign_key
+        .as_deref()
+        .map(signing::read_signing_key)
+        .transpose()?;
+    #[cfg(feature = "scripting")]
+    let script = options.script.as_deref().map(Script::load).transpose()?;
+
+    if !options.allow_processed_input {
+        processed_input::check(input_path)?;
+    }
+    progress.begin_phase("Reading pak archive...", None);
+    let source = PakFile::open_with(input_path, options.storage)?;
+    progress.end_phase();
+    progress.info(&format!(
+        "Read pak archive with {} entries.",
+        source.entries().len()
+    ));
+    // recorded with the output, so it can be told from its source later
+    let source_fingerprint = SourceFingerprint {
+        size: fs::metadata(input_path)?.len(),
+        toc_sha256: build_info::toc_sha256(input_path, &source)?,
+    };
+    if let Some(dump_path) = &options.names_dump {
+        dump_names(&source, dump_path, name_table)?;
+        progress.info(&format!(
+            "Wrote the entry names to {}.",
+            util::display_path(dump_path)
+        ));
+    }
+
+    // full package output should keep everything we don't transform
+    let trailing_block = if use_full_package_mode {
+        TrailingBlock::read(input_path, source.archive())?
+    } else {
+        None
+    };
+
+    // non-tex entries absent from a tex-only output, listed if asked for
+    let left_out = Mutex::new(vec![]);
+    let list_left_out = |hash: u64| {
+        if options.non_tex_list.is_some() {
+            left_out.lock().push(hash);
+        }
+    };
+
+    let overrides = match &options.overrides {
+        Some(path) => Overrides::load(path)?,
+        None => Overrides::default(),
+    };
+    selector::check_names(&overrides.selectors(), name_table)?;
+
+    // filtered entries
+    let entries = if use_full_package_mode {
+        source.entries().iter().collect::<Vec<_>>()
+    } else {
+        // name resolution dominates on 100k-entry paks, spread it over the pool
+        let filter_categories = options.filters_categories();
+        progress.begin_phase("Filtering entries", Some(source.entries().len() as u64));
+        let mut entries = source
+            .entries()
+            .par_iter()
+            .inspect(|_| progress.inc(1))
+            .filter(|entry| {
+                if options.copies_non_tex(name_table, entry.hash()) {
+                    return true;
+                }
+                // without a name table, tex files are told apart by content while processing
+                if name_table.is_tex_file(entry.hash()) == Some(false) {
+                    list_left_out(entry.hash());
+                    return false;
+                }
+                if !options.accepts_entry_size(entry.uncompressed_size()) {
+                    return false;
+                }
+                if let Some(picked) = &options.picked_entries {
+                    return picked.contains(&entry.hash());
+                }
+                !filter_categories
+                    || name_table
+                        .get_name(entry.hash())
+                        .is_some_and(|name| options.categories.contains(&Category::of(&name)))
+            })
+            .collect::<Vec<_>>();
+        progress.end_phase();
+        progress.info(&format!(
+            "Selected {} of {} entries.",
+            entries.len(),
+            source.entries().len()
+        ));
+        // before `--top-impact` and the overrides, which then decide on both halves
+        if name_table.is_loaded() {
+            let (streamed, added) =
+                streaming::complete_pairs(&mut entries, source.entries(), name_table);
+            if added > 0 {
+                progress.info(&format!(
+                    "Added {added} streaming halves of the {streamed} selected streamed textures, so their high-res mips are decompressed too."
+                ));
+            }
+        }
+        match options.top_impact {
+            Some(top) => select_top_impact(&source, entries, top, options, name_table, progress)?,
+            None => entries,
+        }
+    };
+    let mut entries = if overrides.is_empty() {
+        entries
+    } else {
+        apply_included_overrides(&source, entries, &overrides, name_table, progress)
+    };
+    if let Some(only) = &options.only_entries {
+        entries.retain(|entry| only.contains(&entry.hash()));
+    }
+    if let Some(shard) = options.shard {
+        entries.retain(|entry| shard.contains(entry.hash()));
+        progress.info(&format!(
+            "Shard {shard}: processing {} entries.",
+            entries.len()
+        ));
+    }
+
+    let forced = |hash: u64| overrides.get(hash, name_table).include == Some(true);
+    // why a read entry isn't written to the output, `None` if it is
+    let left_out_reason = |hash: u64, raw: &[u8]| -> eyre::Result<Option<LeftOut>> {
+        if forced(hash) {
+            return Ok(None);
+        }
+        let is_tex = is_tex_entry(name_table, hash, raw);
+        if !is_tex && !use_full_package_mode && !options.copies_non_tex(name_table, hash) {
+            return Ok(Some(LeftOut::NotTex));
+        }
+        if is_tex && options.filters_tex_size() && !options.accepts_tex_size(estimate_tex_size(raw))
+        {
+            return Ok(Some(LeftOut::Size));
+        }
+        if is_tex && options.filters_formats() && !options.accepts_format(raw) {
+            return Ok(Some(LeftOut::Format));
+        }
+        #[cfg(feature = "scripting")]
+        if let Some(false) = script
+            .as_ref()
+            .map(|script| script.filter(name_table, hash, raw))
+            .transpose()?
+        {
+            return Ok(Some(LeftOut::Script));
+        }
+        Ok(None)
+    };
+    // the data written for a kept entry, done again the same way by `--verify-sample`
+    let process_kept = |hash: u64, raw: Vec<u8>| -> eyre::Result<Vec<u8>> {
+        let handler = handler::find(name_table, hash, &raw);
+        let data = process_entry(raw, handler, &options.tex_transform)?;
+        #[cfg(feature = "scripting")]
+        let data = match &script {
+            Some(script) => script.transform(name_table, hash, data)?,
+            None => data,
+        };
+        Ok(data)
+    };
+
+    // extrapolated from a sample processed like the run does
+    let estimate = |size: SampleSize| {
+        estimate::sample(
+            &source,
+            &entries,
+            name_table,
+            size,
+            &|entry| {
+                use_full_package_mode
+                    || options.copies_non_tex(name_table, entry.hash())
+                    || forced(entry.hash())
+            },
+            &|entry, raw| match left_out_reason(entry.hash(), &raw)? {
+                Some(_) => Ok(0),
+                None => Ok(process_kept(entry.hash(), raw)?.len() as u64),
+            },
+            progress,
+        )
+    };
+    let sampled_size = match options.estimate {
+        Some(percent) => {
+            let estimate = estimate(SampleSize::Percent(percent))?;
+            progress.info(&estimate.summary());
+            if options.confirm_estimate && !estimate::confirm() {
+                return Err(Cancelled.into());
+            }
+            Some(estimate.output_size)
+        }
+        None => None,
+    };
+    if options.check_free_space {
+        let output_size = match sampled_size {
+            Some(size) => size,
+            None => estimate(SampleSize::Count(free_space::SAMPLE_SIZE))?.output_size,
+        };
+        // the variants are at most as large as the main output
+        let padding = options.align.unwrap_or(0) * entries.len() as u64;
+        let needed = (output_size + padding) * (1 + options.variants.len() as u64);
+        free_space::check(&options.output_path(), needed, progress)?;
+    }
+
+    // new pak archive
+    let output_path = options.output_path();
+    progress.info(&format!(
+        "Output file: {}",
+        util::display_path(&output_path)
+    ));
+
+    let previous_output = match &options.previous_output {
+        None => None,
+        Some(previous_path) => {
+            if !previous_path.is_file() {
+                return Err(InputNotFound::new("previous output file", previous_path).into());
+            }
+            let previous_index_path = SourceIndex::sidecar_path(previous_path);
+            let mut previous_parts = output::existing_parts(previous_path);
+            // the previous output would be truncated by the new one, move it aside first. Only the
+            // latest one is kept, the backup of an earlier update goes.
+            if *previous_path == output_path {
+                let moved_path = output_path.with_extension("prev.pak");
+                for stale_part in output::existing_parts(&moved_path) {
+                    fs::remove_file(stale_part)?;
+                }
+                for (i, part) in previous_parts.iter_mut().enumerate() {
+                    let moved_part = output::part_path(&moved_path, i + 1);
+                    fs::rename(&*part, &moved_part)?;
+                    *part = moved_part;
+                }
+                progress.info(&format!(
+                    "Moved the previous output to {}, it's kept until the next update.",
+                    util::display_path(&moved_path)
+                ));
+            }
+            progress.info("Reading previous output...");
+            Some(PreviousOutput::open(
+                &previous_parts,
+                &previous_index_path,
+                &options.tex_transform,
+            )?)
+        }
+    };
+    // an output per category if splitting, outputs left empty are dropped when finishing
+    let output_categories = if options.split_by_category {
+        Category::ALL.map(Some).to_vec()
+    } else {
+        vec![None]
+    };
+    let output_writers = output_categories
+        .iter()
+        .map(|category| {
+            let path = match category {
+                Some(category) => options.category_path(*category),
+                None => output_path.clone(),
+            };
+            OutputWriter::create(
+                &path,
+                entries.len() as u64 + options.embed_info as u64,
+                options.split_size,
+                options.deterministic,
+            )
+            .map(|writer| (*category, Mutex::new(writer)))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let entry_category = |hash: u64| {
+        options.split_by_category.then(|| {
+            name_table
+                .get_name(hash)
+                .map_or(Category::Other, Category::of)
+        })
+    };
+
+    // fed the same entries, the textures are only decompressed once
+    let variant_writers = options
+        .variants
+        .iter()
+        .map(|&max_size| {
+            OutputWriter::create(
+                &options.variant_path(max_size),
+                entries.len() as u64,
+                options.split_size,
+                options.deterministic,
+            )
+            .map(Mutex::new)
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    progress.begin_phase("Processing entries", Some(entries.len() as u64));
+
+    let stats = Stats::new();
+    let source_index = Mutex::new(SourceIndex::new(&options.tex_transform, source_fingerprint));
+    let tex_hashes = Mutex::new(HashSet::new());
+    let checksums = Mutex::new(HashMap::new());
+    let duplicates = DuplicateFinder::default();
+    let ratio_check = RatioCheck::default();
+    let dir_stats = DirStats::default();
+    let actions = Mutex::new(HashMap::new());
+    // entries read only after retries, with the number of retries
+    let retried = Mutex::new(Vec::new());
+
+    // unchanged since the previous output, copy the processed data over
+    let reused_entry = |entry: &PakEntry| {
+        previous_output
+            .as_ref()
+            .and_then(|previous| previous.unchanged_entry(entry))
+    };
+
+    // reads again after a failure, with `prefetched` the attempt of the read-ahead thread
+    let read_retrying = |entry: &PakEntry,
+                         prefetched: Option<eyre::Result<Vec<u8>>>,
+                         read: &dyn Fn() -> eyre::Result<Vec<u8>>| {
+        let mut prefetched = prefetched;
+        let (data, retries) =
+            retry::run(options.retries, || prefetched.take().unwrap_or_else(read));
+        if data.is_ok() && retries > 0 {
+            retried.lock().push((entry.hash(), retries));
+        }
+        data
+    };
+
+    // `raw` is the source data if the read-ahead thread already read it, or its error
+    let process_one = |index: usize, entry: &PakEntry, raw: Prefetched| -> eyre::Result<()> {
+        cancel::check()?;
+        stats.set_last_entry(entry.hash());
+        let entry_override = overrides.get(entry.hash(), name_table);
+        let (data, action) = if let Some((previous_pak, previous_entry)) = reused_entry(entry) {
+            let data = read_retrying(entry, None, &|| previous_pak.read_entry(previous_entry))?;
+            Stats::add(&stats.reused_entries, 1);
+            Stats::add(&stats.bytes_in, data.len() as u64);
+            (Some(data), "reused")
+        } else {
+            let raw = read_retrying(entry, raw.transpose(), &|| source.read_entry(entry))?;
+            Stats::add(&stats.bytes_in, raw.len() as u64);
+            // the only integrity data of an entry the tool can check, its checksum isn't documented
+            if raw.len() as u64 != entry.uncompressed_size() {
+                Stats::add(&stats.size_mismatches, 1);
+                progress.warn(&format!(
+                    "{} decompressed to {} bytes, the pak records {}",
+                    name_table.display_name(entry.hash()),
+                    raw.len(),
+                    entry.uncompressed_size()
+                ));
+            }
+            let is_tex = is_tex_entry(name_table, entry.hash(), &raw);
+            if let Some(reason) = left_out_reason(entry.hash(), &raw)? {
+                match reason {
+                    LeftOut::NotTex => list_left_out(entry.hash()),
+                    LeftOut::Size => Stats::add(&stats.skipped_by_size, 1),
+                    LeftOut::Format => Stats::add(&stats.skipped_by_format, 1),
+                    LeftOut::Script => {}
+                }
+                (None, reason.action())
+            } else {
+                let action = if is_tex { "decompressed" } else { "copied" };
+                if let Some(header) = is_tex.then(|| TexHeader::parse(&raw).ok()).flatten() {
+                    ratio_check.add(
+                        entry.hash(),
+                        header.format,
+                        entry.compressed_size(),
+                        header.decompressed_size(),
+                    );
+                }
+                let data = process_kept(entry.hash(), raw)
+                    .wrap_err_with(|| FailedEntry::new(entry.hash(), name_table))?;
+                (Some(data), action)
+            }
+        };
+        if progress.wants_details() {
+            progress.detail(&format!(
+                "{}: {action}",
+                name_table.display_name(entry.hash())
+            ));
+        }
+        if options.manifest {
+            actions.lock().insert(entry.hash(), action);
+        }
+
+        if let Some(data) = &data {
+            source_index
+                .lock()
+                .insert(entry.hash(), SourceRecord::from_entry(entry));
+            let is_tex = is_tex_entry(name_table, entry.hash(), data);
+            if is_tex {
+                Stats::add(&stats.tex_entries, 1);
+            } else {
+                Stats::add(&stats.other_entries, 1);
+            }
+            if options.content_index && is_tex {
+                tex_hashes.lock().insert(entry.hash());
+            }
+            if options.manifest {
+                checksums
+                    .lock()
+                    .insert(entry.hash(), EntryChecksum::of(data));
+            }
+            if options.report_duplicates {
+                duplicates.add(entry.hash(), data);
+            }
+            dir_stats.add(
+                name_table.get_name(entry.hash()),
+                entry.compressed_size(),
+                data.len() as u64,
+            );
+        }
+        for (writer, &max_size) in variant_writers.iter().zip(&options.variants) {
+            let variant = match &data {
+                Some(data) => {
+                    let is_tex = is_tex_entry(name_table, entry.hash(), data);
+                    let store = options.store_options(is_tex, &entry_override);
+                    let data = if is_tex {
+                        tex_header::cap_size(data, max_size)
+                            .wrap_err_with(|| FailedEntry::new(entry.hash(), name_table))?
+                    } else {
+                        data.clone()
+                    };
+                    Some((entry, data, store))
+                }
+                None => None,
+            };
+            let write_bytes = writer.lock().submit(index, variant)?;
+            throttle::consume(write_bytes as u64);
+        }
+        let mut item = data.map(|data| {
+            let is_tex = is_tex_entry(name_table, entry.hash(), &data);
+            let store = options.store_options(is_tex, &entry_override);
+            (entry, data, store)
+        });
+        // every writer sees every index, ordered writers wait for the skipped ones too
+        let category = entry_category(entry.hash());
+        for (writer_category, writer) in &output_writers {
+            let item = if *writer_category == category {
+                item.take()
+            } else {
+                None
+            };
+            // the lock is released first, so only this worker waits for the budget
+            let write_bytes = writer.lock().submit(index, item)?;
+            throttle::consume(write_bytes as u64);
+            Stats::add(&stats.bytes_out, write_bytes as u64);
+        }
+        progress.inc(1);
+        Ok(())
+    };
+
+    // workers read the pak from memory directly, there's nothing to read ahead
+    let read_ahead_depth = options
+        .read_ahead
+        .unwrap_or(if options.storage.is_in_memory() {
+            0
+        } else {
+            options.thread_count * READ_AHEAD_PER_THREAD
+        });
+    let count_failure = |e: &eyre::Report| {
+        if e.downcast_ref::<Cancelled>().is_none() {
+            Stats::add(&stats.failures, 1);
+        }
+    };
+    // entries finished ahead of their turn wait in memory, the reader stops handing out more
+    let in_order_window = options
+        .writes_in_order()
+        .then_some(options.thread_count * IN_ORDER_WINDOW_PER_THREAD);
+    let err = stats.report_while(progress, || {
+        // its reads complete in any order, in-order outputs go through the reader thread
+        #[cfg(feature = "async-io")]
+        if options.async_io && in_order_window.is_none() {
+            return async_read::try_for_each(
+                input_path,
+                &source,
+                &entries,
+                read_ahead_depth.max(1),
+                |entry| reused_entry(entry).is_none(),
+                |index, entry, raw| process_one(index, entry, raw).inspect_err(count_failure),
+            );
+        }
+        if read_ahead_depth == 0 && in_order_window.is_none() {
+            entries
+                .par_iter()
+                .enumerate()
+                .try_for_each(|(index, &entry)| {
+                    process_one(index, entry, Ok(None)).inspect_err(count_failure)
+                })
+        } else {
+            read_ahead::try_for_each(
+                &source,
+                &entries,
+                read_ahead_depth.max(1),
+                in_order_window,
+                // without read-ahead the workers still read, the reader only hands out entries
+                |entry| read_ahead_depth > 0 && reused_entry(entry).is_none(),
+                |index, entry, raw| process_one(index, entry, raw).inspect_err(count_failure),
+            )
+        }
+    });
+    let stats = stats.snapshot();
+    progress.end_phase();
+
+    // writing the entry tables and moving the outputs in place takes a while for big outputs,
+    // without a phase of its own the run looks stuck at 100%
+    progress.begin_phase(
+        "Finalizing archive...",
+        Some((output_writers.len() + options.variants.len()) as u64),
+    );
+    let source_header = PakHeader::read(input_path)?;
+    let mut output_writers: Vec<_> = output_writers
+        .into_iter()
+        .map(|(category, writer)| (category, writer.into_inner()))
+        .collect();
+    if options.embed_info {
+        let writer = match output_writers
+            .iter()
+            .position(|(_, writer)| !writer.is_empty())
+        {
+            Some(index) => &mut output_writers[index].1,
+            None => &mut output_writers[0].1,
+        };
+        writer.append(build_info::hash(), &build_info::build(options, &source)?)?;
+    }
+    let mut output_paths = vec![];
+    for (category, writer) in output_writers {
+        let is_empty = writer.is_empty();
+        let paths = writer.finish()?;
+        if category.is_some() && is_empty {
+            for path in &paths {
+                fs::remove_file(output::temp_path(path))?;
+            }
+        } else {
+            complete_output(&paths, source_header, options, trailing_block.as_ref())?;
+            if let Some(category) = category {
+                progress.info(&format!(
+                    "{}: {}",
+                    category.label(),
+                    util::display_path(&paths[0])
+                ));
+            }
+            output_paths.extend(paths);
+        }
+        progress.inc(1);
+    }
+    for (writer, &max_size) in variant_writers.into_iter().zip(&options.variants) {
+        let paths = writer.into_inner().finish()?;
+        complete_output(&paths, source_header, options, trailing_block.as_ref())?;
+        progress.info(&format!(
+            "Variant with textures capped to {max_size}px: {}",
+            util::display_path(&paths[0])
+        ));
+        progress.inc(1);
+    }
+    progress.end_phase();
+    if !output_paths.is_empty() && (options.pak_version.is_some() || options.pak_features.is_some())
+    {
+        progress.info(&format!(
+            "Output pak header: {}.",
+            PakHeader::read(&output_paths[0])?
+        ));
+    }
+    if let Some(trailing_block) = &trailing_block {
+        progress.info(&format!(
+            "Preserved {} of trailing metadata from source pak.",
+            HumanBytes(trailing_block.size() as u64)
+        ));
+    }
+
+    let source_index = source_index.into_inner();
+    // incremental updates work on single outputs
+    if !options.split_by_category {
+        source_index.save(&SourceIndex::sidecar_path(&output_path))?;
+    }
+
+    if let Some(list_path) = &options.non_tex_list {
+        let mut names: Vec<_> = left_out
+            .into_inner()
+            .into_iter()
+            .map(|hash| name_table.display_name(hash))
+            .collect();
+        names.sort();
+        let mut list = names.join("\n");
+        list.push('\n');
+        fs::write(list_path, list)?;
+        progress.info(&format!(
+            "Listed {} non-tex entries left out in {}.",
+            names.len(),
+            util::display_path(list_path)
+        ));
+    }
+
+    if options.content_index {
+        let tex_hashes = tex_hashes.into_inner();
+        for path in &output_paths {
+            let index = ContentIndex::from_pak(&PakFile::open(path)?, &tex_hashes);
+            index.write(&ContentIndex::sidecar_path(path))?;
+        }
+        progress.info("Wrote content index next to the output.");
+    }
+
+    if name_table.is_loaded() {
+        for line in dir_stats.table() {
+            progress.info(&line);
+        }
+        report_unknown_hashes(&source, options, name_table, progress)?;
+    }
+
+    let anomalies = ratio_check.into_anomalies();
+    if !anomalies.is_empty() {
+        progress.warn(&format!(
+            "{} textures decompress at a ratio far off the typical one of their format, they may be corrupted or not the texture their name says. `--verbose` lists them.",
+            anomalies.len()
+        ));
+        for anomaly in &anomalies {
+            progress.detail(&format!(
+                "{}: format {}, {:.1}x, typical {:.1}x",
+                name_table.display_name(anomaly.hash),
+                anomaly.format,
+                anomaly.ratio,
+                anomaly.typical
+            ));
+        }
+    }
+
+    if options.report_duplicates {
+        let duplicates = duplicates.into_duplicates();
+        if progress.wants_details() {
+            for group in &duplicates {
+                let names: Vec<_> = group
+                    .hashes
+                    .iter()
+                    .map(|hash| name_table.display_name(*hash))
+                    .collect();
+                progress.detail(&format!(
+                    "identical ({}): {}",
+                    HumanBytes(group.size),
+                    names.join(", ")
+                ));
+            }
+        }
+        progress.info(&format!(
+            "Found {} duplicate entries in {} groups, sharing their data would save {}.",
+            duplicates
+                .iter()
+                .map(|group| group.hashes.len() - 1)
+                .sum::<usize>(),
+            duplicates.len(),
+            HumanBytes(duplicates.iter().map(|group| group.savings()).sum())
+        ));
+    }
+
+    if stats.size_mismatches > 0 {
+        progress.warn(&format!(
+            "{} entries don't match the size recorded in the source pak, the game install may be corrupted. Verify the integrity of the game files in Steam.",
+            stats.size_mismatches
+        ));
+    }
+    let retried = retried.into_inner();
+    if !retried.is_empty() {
+        progress.warn(&format!(
+            "{} entries were only read after retries, the drive or network share may be unreliable. `--verbose` lists them.",
+            retried.len()
+        ));
+        for (hash, retries) in &retried {
+            progress.detail(&format!(
+                "{}: {retries} retries",
+                name_table.display_name(*hash)
+            ));
+        }
+    }
+    if options.filters_tex_size() {
+        progress.info(&format!(
+            "Skipped {} tex files by size.",
+            stats.skipped_by_size
+        ));
+    }
+    if options.filters_formats() {
+        progress.info(&format!(
+            "Skipped {} tex files by format.",
+            stats.skipped_by_format
+        ));
+    }
+    if previous_output.is_some() {
+        progress.info(&format!(
+            "Reused {} unchanged entries from previous output.",
+            stats.reused_entries
+        ));
+    }
+
+    // the entries never processed would show up as missing
+    let cancelled = err
+        .as_ref()
+        .is_err_and(|e| e.downcast_ref::<Cancelled>().is_some());
+    let verification = if options.verify_sample > 0.0 && !cancelled {
+        progress.begin_phase(
+            &format!("Verifying {}% of entries...", options.verify_sample),
+            None,
+        );
+        let outputs = output_paths
+            .iter()
+            .map(|path| PakFile::open(path))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        // entries left out by the filters aren't in the output, only what was written is sampled
+        let written: Vec<&PakEntry> = entries
+            .iter()
+            .copied()
+            .filter(|entry| source_index.get(entry.hash()).is_some())
+            .collect();
+        let report = verify::verify_sample(
+            &source,
+            &outputs,
+            &written,
+            options.verify_sample,
+            options.verify_seed.unwrap_or_else(SplitMix64::time_seed),
+            &process_kept,
+        )?;
+        progress.end_phase();
+        for hash in &report.missing {
+            progress.warn(&format!(
+                "{} missing from output",
+                name_table.display_name(*hash)
+            ));
+        }
+        for hash in &report.mismatched {
+            progress.warn(&format!(
+                "{} doesn't match the source",
+                name_table.display_name(*hash)
+            ));
+        }
+        progress.info(&format!(
+            "Verified {} sampled entries (seed {:016x}), {} mismatched, {} missing.",
+            report.sampled.len(),
+            report.seed,
+            report.mismatched.len(),
+            report.missing.len()
+        ));
+        Some(report)
+    } else {
+        None
+    };
+
+    // written after verification, so it records the outcome even if that failed
+    if options.manifest {
+        progress.begin_phase("Writing manifest...", None);
+        let manifest_path = manifest::manifest_path(&output_path);
+        let audit = Audit {
+            actions: &actions.into_inner(),
+            verification: verification.as_ref(),
+        };
+        manifest::write(
+            &manifest_path,
+            options,
+            &output_paths,
+            &checksums.into_inner(),
+            &audit,
+            name_table,
+        )?;
+        progress.end_phase();
+        progress.info(&format!("Manifest: {}", util::display_path(&manifest_path)));
+        if let Some(key) = &signing_key {
+            let signature_path = signing::sign_file(&manifest_path, key)?;
+            progress.info(&format!(
+                "Signature: {}, public key {}",
+                util::display_path(&signature_path),
+                signing::public_key_hex(key)
+            ));
+        }
+    }
+
+    if verification.is_some_and(|report| !report.is_ok()) {
+        eyre::bail!("sample verification failed.");
+    }
+
+    // an incomplete output isn't worth installing
+    if let (Some(info), Ok(())) = (&options.fluffy, &err) {
+        progress.begin_phase("Packaging for Fluffy Mod Manager...", None);
+        let zip_path = fluffy::package(&output_paths, info)?;
+        progress.end_phase();
+        progress.info(&format!("Mod package: {}", util::display_path(&zip_path)));
+    }
+
+    Ok(Summary {
+        output_paths,
+        entries_written: source_index.len(),
+        bytes_written: stats.bytes_out,
+        processing_error: err.err(),
+    })
+}
+
+/// Patches the headers of the finished parts, appends the trailing block to the first and moves
+/// them into place.
+fn complete_output(
+    paths: &[PathBuf],
+    source_header: PakHeader,
+    options: &Options,
+    trailing_block: Option<&TrailingBlock>,
+) -> eyre::Result<()> {
+    for path in paths {
+        let temp_path = output::temp_path(path);
+        if let Some(alignment) = options.align {
+            alignment::align_entries(&temp_path, alignment)?;
+        }
+        let header = PakHeader::for_output(
+            PakHeader::read(&temp_path)?,
+            source_header,
+            options.pak_version,
+            options.pak_features,
+        );
+        header.write_to(&temp_path)?;
+    }
+    if let Some(trailing_block) = trailing_block {
+        trailing_block.append_to(&output::temp_path(&paths[0]))?;
+    }
+    output::commit(paths)
+}
+
+/// Applies the `include` overrides to the selected entries, keeping the source order.
+fn apply_included_overrides<'a>(
+    source: &'a PakFile,
+    selected: Vec<&'a PakEntry>,
+    overrides: &Overrides,
+    name_table: &NameTable,
+    progress: &dyn Progress,
+) -> Vec<&'a PakEntry> {
+    let selected: HashSet<u64> = selected.iter().map(|entry| entry.hash()).collect();
+    let entries: Vec<&PakEntry> = source
+        .entries()
+        .par_iter()
+        .filter(|entry| {
+            overrides
+                .get(entry.hash(), name_table)
+                .include
+                .unwrap_or_else(|| selected.contains(&entry.hash()))
+        })
+        .collect();
+    progress.info(&format!(
+        "Selected {} entries after overrides.",
+        entries.len()
+    ));
+    entries
+}
+
+/// Reports the source entries whose hash isn't in the file name table, to help completing it.
+fn report_unknown_hashes(
+    source: &PakFile,
+    options: &Options,
+    name_table: &NameTable,
+    progress: &dyn Progress,
+) -> eyre::Result<()> {
+    let mut unknown: Vec<&PakEntry> = source
+        .entries()
+        .iter()
+        .filter(|entry| !name_table.contains(entry.hash()))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort_by_key(|entry| entry.hash());
+
+    if progress.wants_details() {
+        for entry in &unknown {
+            progress.detail(&format!(
+                "unknown hash {:016X} ({})",
+                entry.hash(),
+                HumanBytes(entry.uncompressed_size())
+            ));
+        }
+    }
+    progress.info(&format!(
+        "{} entries ({}) aren't in the file name table.",
+        unknown.len(),
+        HumanBytes(unknown.iter().map(|entry| entry.uncompressed_size()).sum())
+    ));
+    if let Some(list_path) = &options.unknown_list {
+        let lines: Vec<String> = unknown
+            .iter()
+            .map(|entry| format!("{:016X} {}", entry.hash(), entry.uncompressed_size()))
+            .collect();
+        let mut list = lines.join("\n");
+        list.push('\n');
+        fs::write(list_path, list)?;
+        progress.info(&format!(
+            "Listed unknown hashes in {}.",
+            util::display_path(list_path)
+        ));
+    }
+    Ok(())
+}
+
+/// Writes the name of every source entry, sorted, then the hashes of the unknown ones, in hex
+/// like `--list-unknown`.
+fn dump_names(source: &PakFile, path: &Path, name_table: &NameTable) -> eyre::Result<()> {
+    let mut names = vec![];
+    let mut unknown = vec![];
+    for entry in source.entries() {
+        match name_table.get_name(entry.hash()) {
+            Some(name) => names.push(name),
+            None => unknown.push(entry.hash()),
+        }
+    }
+    names.sort_unstable();
+    unknown.sort_unstable();
+
+    let mut list = String::new();
+    for name in names {
+        list.push_str(name);
+        list.push('\n');
+    }
+    for hash in unknown {
+        list.push_str(&format!("{hash:016X}\n"));
+    }
+    fs::write(path, list)?;
+    Ok(())
+}
+
+/// Keeps the `top` textures with the highest streaming cost and the copied non-tex entries.
+fn select_top_impact<'a>(
+    source: &PakFile,
+    entries: Vec<&'a PakEntry>,
+    top: usize,
+    options: &Options,
+    name_table: &NameTable,
+    progress: &dyn Progress,
+) -> eyre::Result<Vec<&'a PakEntry>> {
+    progress.begin_phase("Scoring textures", Some(entries.len() as u64));
+    let scores = entries
+        .par_iter()
+        .inspect(|_| progress.inc(1))
+        .map(|entry| impact::score_entry(source, entry, name_table))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    progress.end_phase();
+
+    let mut ranked: Vec<(usize, f64)> = scores
+        .iter()
+        .enumerate()
+        .filter_map(|(i, score)| Some((i, (*score)?)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let selected: HashSet<usize> = ranked.iter().take(top).map(|(i, _)| *i).collect();
+    progress.info(&format!(
+        "Selected the {} of {} textures with the highest streaming cost.",
+        selected.len(),
+        ranked.len()
+    ));
+
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .filter(|(i, entry)| {
+            selected.contains(i) || options.copies_non_tex(name_table, entry.hash())
+        })
+        .map(|(_, entry)| entry)
+        .collect())
+}
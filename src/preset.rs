@@ -0,0 +1,59 @@
+//! Named bundles of decompression options for users who don't want to learn every flag.
+
+use clap::ValueEnum;
+
+use crate::{category::Category, cli::DecompressArgs, transcode::TranscodeFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Skip the decoder where possible and memory-map the input, which the workers then read
+    /// without the read-ahead thread (`--header-fast-path --mmap --read-ahead 0`).
+    Fast,
+    /// Smallest output: BC7 transcoded to BC1, non-tex entries stay compressed
+    /// (`--transcode bc1 --keep-compression`).
+    Smallest,
+    /// Closest to the source: all files, non-tex entries compressed and feature flags cloned as
+    /// in the source, no texture changes (full package, `--keep-compression`).
+    Compat,
+    /// Only UI and HUD textures, the fix for a blurry HUD (`--category ui`).
+    Ui,
+}
+
+impl Preset {
+    /// Whether the preset packages all files, including non-tex files.
+    pub fn full_package(self) -> bool {
+        self == Self::Compat
+    }
+
+    /// Fills in the options of the preset that are still at their defaults, options given on the
+    /// command line win.
+    pub fn apply(self, args: &mut DecompressArgs) {
+        match self {
+            Self::Fast => {
+                args.header_fast_path = true;
+                // another backend given on its own leaves the read-ahead to it
+                if args.storage.is_none() {
+                    args.mmap = true;
+                    args.read_ahead.get_or_insert(0);
+                }
+            }
+            Self::Smallest => {
+                args.transcode.get_or_insert(TranscodeFormat::Bc1);
+                keep_compression(args);
+            }
+            Self::Compat => keep_compression(args),
+            Self::Ui => {
+                if args.category.is_empty() {
+                    args.category = vec![Category::Ui];
+                }
+            }
+        }
+    }
+}
+
+/// Keeps non-tex entries compressed, unless `--compression` gives them a codec.
+fn keep_compression(args: &mut DecompressArgs) {
+    if args.compression.codec(false).is_none() {
+        args.keep_compression = true;
+    }
+}
@@ -0,0 +1,102 @@
+//! Decodes a mip of an uncompressed tex file to RGBA pixels, for PNG thumbnails.
+
+use crate::tex_header::TexHeader;
+
+const DXGI_R8G8B8A8_UNORM: u32 = 28;
+const DXGI_R8G8B8A8_UNORM_SRGB: u32 = 29;
+const DXGI_B8G8R8A8_UNORM: u32 = 87;
+const DXGI_B8G8R8A8_UNORM_SRGB: u32 = 91;
+
+/// Block formats bcdec decodes to RGBA8, with their block size.
+fn block_decoder(format: u32) -> Option<(fn(&[u8], &mut [u8], usize), usize)> {
+    match format {
+        // BC1
+        71 | 72 => Some((bcdec_rs::bc1, 8)),
+        // BC2
+        74 | 75 => Some((bcdec_rs::bc2, 16)),
+        // BC3
+        77 | 78 => Some((bcdec_rs::bc3, 16)),
+        // BC7
+        98 | 99 => Some((bcdec_rs::bc7, 16)),
+        _ => None,
+    }
+}
+
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+/// Decodes the largest mip of the first image whose larger side is at most `max_size`, or the
+/// smallest mip if none is. `None` if the format can't be decoded.
+pub fn decode_mip(data: &[u8], max_size: u32) -> eyre::Result<Option<Image>> {
+    let header = TexHeader::parse(data)?;
+    if header.mip_count == 0 {
+        eyre::bail!("texture has no mips");
+    }
+    let size_of = |level: usize| {
+        (
+            ((header.width as u32) >> level).max(1),
+            ((header.height as u32) >> level).max(1),
+        )
+    };
+    let level = (0..header.mip_count as usize)
+        .find(|&level| {
+            let (width, height) = size_of(level);
+            width.max(height) <= max_size
+        })
+        .unwrap_or(header.mip_count as usize - 1);
+    let (width, height) = size_of(level);
+    let mip = &header.mips[level];
+    let bytes = header.mip_data(data, level)?;
+
+    let rgba = match header.format {
+        DXGI_R8G8B8A8_UNORM | DXGI_R8G8B8A8_UNORM_SRGB => crop(bytes, mip.pitch, width, height, 4),
+        DXGI_B8G8R8A8_UNORM | DXGI_B8G8R8A8_UNORM_SRGB => {
+            let mut rgba = crop(bytes, mip.pitch, width, height, 4);
+            for pixel in rgba.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            rgba
+        }
+        format => {
+            let Some((decode, block_size)) = block_decoder(format) else {
+                return Ok(None);
+            };
+            // the pitch includes row padding, fall back to the mip width if there is none
+            let blocks_per_row = if mip.pitch > 0 {
+                mip.pitch as usize / block_size
+            } else {
+                (width as usize).div_ceil(4)
+            };
+            let padded_width = blocks_per_row * 4;
+            let rows = bytes.len() / (blocks_per_row * block_size).max(1);
+            let mut padded = vec![0u8; padded_width * rows * 4 * 4];
+            for (index, block) in bytes.chunks_exact(block_size).enumerate() {
+                let (row, col) = (index / blocks_per_row, index % blocks_per_row);
+                let offset = (row * 4 * padded_width + col * 4) * 4;
+                decode(block, &mut padded[offset..], padded_width * 4);
+            }
+            crop(&padded, (padded_width * 4) as u32, width, height, 4)
+        }
+    };
+    Ok(Some(Image {
+        width,
+        height,
+        rgba,
+    }))
+}
+
+/// Copies the `width` x `height` pixels out of rows that are `pitch` bytes apart.
+fn crop(bytes: &[u8], pitch: u32, width: u32, height: u32, pixel_size: usize) -> Vec<u8> {
+    let row_size = width as usize * pixel_size;
+    let pitch = if pitch > 0 { pitch as usize } else { row_size };
+    let mut out = vec![0u8; row_size * height as usize];
+    for (row, out_row) in out.chunks_exact_mut(row_size).enumerate() {
+        if let Some(src) = bytes.get(row * pitch..row * pitch + row_size) {
+            out_row.copy_from_slice(src);
+        }
+    }
+    out
+}
@@ -0,0 +1,26 @@
+//! `--low-priority`: lets the OS scheduler prefer the game and other work over the run. Threads
+//! started afterwards, the worker pool included, inherit the priority.
+
+/// Lowers the priority of the process to below normal.
+#[cfg(windows)]
+pub fn lower() -> eyre::Result<()> {
+    use windows_sys::Win32::System::Threading::{
+        BELOW_NORMAL_PRIORITY_CLASS, GetCurrentProcess, SetPriorityClass,
+    };
+
+    // SAFETY: the pseudo handle of the current process is always valid
+    if unsafe { SetPriorityClass(GetCurrentProcess(), BELOW_NORMAL_PRIORITY_CLASS) } == 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Lowers the priority of the calling thread to nice 10, threads inherit it on Linux.
+#[cfg(unix)]
+pub fn lower() -> eyre::Result<()> {
+    // SAFETY: plain syscall, `0` is the calling process (the calling thread on Linux)
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 10) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use clap::ValueEnum;
+use ree_pak_core::{filename::FileNameExt, pak::PakEntry, write::FileOptions};
+
+use crate::{
+    compression::Codec,
+    handler::EntryHandler,
+    names::NameTable,
+    tex_header::{TEX_MAGIC, TexHeader},
+    transcode::{TranscodeFormat, TranscodeQuality},
+};
+
+/// Changes to tex files on top of decompression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TexTransform {
+    /// Number of the largest mip levels to discard.
+    pub drop_top_mips: u8,
+    /// Discard mips whose larger side is below this, 0 to keep all.
+    pub min_mip_size: u16,
+    /// Re-encode BC7 textures to this format.
+    pub transcode: Option<TranscodeFormat>,
+    pub transcode_quality: TranscodeQuality,
+    /// Only rewrite the header of tex files whose mips are already stored uncompressed.
+    pub header_fast_path: bool,
+}
+
+impl TexTransform {
+    pub fn trims_mips(&self) -> bool {
+        self.drop_top_mips > 0 || self.min_mip_size > 1
+    }
+}
+
+/// Lists the settings that differ from plain decompression, empty if none do.
+impl fmt::Display for TexTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = vec![];
+        if self.drop_top_mips > 0 {
+            settings.push(format!("drop_top_mips={}", self.drop_top_mips));
+        }
+        if self.min_mip_size > 1 {
+            settings.push(format!("min_mip_size={}", self.min_mip_size));
+        }
+        if let Some(format) = self.transcode {
+            settings.push(format!("transcode={format}/{}", self.transcode_quality));
+        }
+        if self.header_fast_path {
+            settings.push("header_fast_path".to_string());
+        }
+        write!(f, "{}", settings.join(" "))
+    }
+}
+
+/// Parses the settings written by [`fmt::Display`], e.g. from a manifest.
+impl FromStr for TexTransform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut transform = Self::default();
+        for setting in s.split_whitespace() {
+            let invalid = || format!("invalid tex transform setting `{setting}`");
+            let (key, value) = setting.split_once('=').unwrap_or((setting, ""));
+            match key {
+                "drop_top_mips" => {
+                    transform.drop_top_mips = value.parse().map_err(|_| invalid())?
+                }
+                "min_mip_size" => transform.min_mip_size = value.parse().map_err(|_| invalid())?,
+                "transcode" => {
+                    let (format, quality) = value.split_once('/').ok_or_else(invalid)?;
+                    transform.transcode = Some(TranscodeFormat::from_str(format, true)?);
+                    transform.transcode_quality = TranscodeQuality::from_str(quality, true)?;
+                }
+                "header_fast_path" => transform.header_fast_path = true,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(transform)
+    }
+}
+
+/// Whether the entry is a tex file, falling back to its content if the name is unknown to us.
+pub fn is_tex_entry(names: &NameTable, hash: u64, data: &[u8]) -> bool {
+    names
+        .is_tex_file(hash)
+        .unwrap_or_else(|| data.starts_with(TEX_MAGIC))
+}
+
+/// Estimated size of a tex entry after decompression, falls back to its raw size.
+pub fn estimate_tex_size(raw: &[u8]) -> u64 {
+    TexHeader::parse(raw)
+        .map(|header| header.decompressed_size())
+        .unwrap_or(raw.len() as u64)
+}
+
+/// Transforms the raw data of a source entry into the data written to the output pak with the
+/// handler of its file type, see [`crate::handler::find`].
+pub fn process_entry(
+    data: Vec<u8>,
+    handler: Option<&dyn EntryHandler>,
+    transform: &TexTransform,
+) -> eyre::Result<Vec<u8>> {
+    match handler {
+        Some(handler) => handler.process(data, transform),
+        // plain file, just copy
+        None => Ok(data),
+    }
+}
+
+/// How an entry is stored in the output pak.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreOptions {
+    /// Clone the feature flags (attributes) of the source entry.
+    pub feature_clone: bool,
+    /// Compress the data like the source entry instead of storing it uncompressed.
+    pub keep_compression: bool,
+    /// Compress the data with this codec, takes precedence over `keep_compression`.
+    pub codec: Option<Codec>,
+}
+
+pub fn write_to_pak<W>(
+    writer: &mut ree_pak_core::write::PakWriter<W>,
+    entry: &PakEntry,
+    file_name: impl FileNameExt,
+    data: &[u8],
+    store: StoreOptions,
+) -> eyre::Result<usize>
+where
+    W: io::Write + io::Seek,
+{
+    let mut file_options = FileOptions::default();
+    if store.feature_clone {
+        file_options = file_options.with_unk_attr(*entry.unk_attr())
+    }
+    match store.codec {
+        Some(codec) => file_options = file_options.with_compression_type(codec.compression_type()),
+        None if store.keep_compression => {
+            file_options = file_options.with_compression_type(*entry.compression_type())
+        }
+        None => {}
+    }
+    writer.start_file(file_name, file_options)?;
+    writer.write_all(data)?;
+    Ok(data.len())
+}
@@ -0,0 +1,46 @@
+//! Catches runs on an output of the tool, a common mistake: its entries are already decompressed,
+//! so the run only writes a copy of the same size.
+
+use std::path::Path;
+
+use indicatif::HumanBytes;
+
+use crate::{exit_code::InvalidInput, incremental::SourceIndex};
+
+/// Part of the default output names, `*.uncompressed.pak` and its variants and parts.
+const OUTPUT_MARKER: &str = ".uncompressed.";
+
+/// Fails if `path` looks like an output: it has a source index next to it, or the default
+/// output name.
+pub fn check(path: &Path) -> eyre::Result<()> {
+    let index_path = SourceIndex::sidecar_path(path);
+    let file_name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    let problem = if index_path.is_file() {
+        let source = SourceIndex::load(&index_path)
+            .ok()
+            .and_then(|index| index.source().cloned());
+        match source {
+            Some(source) => format!(
+                "this is an output of the tool, built from a {} pak (entry table SHA-256 {})",
+                HumanBytes(source.size),
+                source.toc_sha256
+            ),
+            None => "this is an output of the tool".to_string(),
+        }
+    } else if file_name.contains(OUTPUT_MARKER) {
+        "the name says this is an output of the tool".to_string()
+    } else {
+        return Ok(());
+    };
+    Err(InvalidInput::new(
+        path,
+        format!(
+            "{problem}, its entries are already decompressed. Run it on the game's pak instead, or use `--allow-processed-input` if this is intended."
+        ),
+    )
+    .into())
+}
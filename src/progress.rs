@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use colored::Colorize;
+use console::Term;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use parking_lot::Mutex;
+
+use crate::{stats::StatsSnapshot, util};
+
+/// Receives the progress and log output of a run, so it can be shown in a terminal or a GUI.
+pub trait Progress: Sync {
+    /// An informational line.
+    fn info(&self, message: &str);
+    /// Something went wrong, but the run goes on.
+    fn warn(&self, message: &str);
+    /// Whether per-entry [`detail`](Progress::detail) lines are shown, to skip formatting them.
+    fn wants_details(&self) -> bool;
+    /// A per-entry log line.
+    fn detail(&self, message: &str);
+    /// Starts a phase, `total` is `None` if its length isn't known up front.
+    fn begin_phase(&self, message: &str, total: Option<u64>);
+    /// Advances the current phase.
+    fn inc(&self, delta: u64);
+    /// Shows the latest stats of the run, called periodically from a render thread.
+    fn update_stats(&self, stats: &StatsSnapshot);
+    /// Ends the current phase.
+    fn end_phase(&self);
+}
+
+/// How much the console output shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only warnings and errors, no progress bars.
+    Quiet,
+    #[default]
+    Normal,
+    /// Also a line per processed entry.
+    Verbose,
+}
+
+/// Below this many columns the two line progress bar wraps and garbles the redraw.
+const COMPACT_WIDTH: u16 = 80;
+/// Percent steps printed when progress bars can't be drawn.
+const PLAIN_STEP: u64 = 10;
+
+/// How progress is drawn, depending on the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// Message line above a full width bar.
+    Full,
+    /// A single line with the percentage and the message cut to the width.
+    Compact,
+    /// Not a terminal (redirected, CI logs): a line every few percent instead of redraws.
+    Plain,
+}
+
+impl Layout {
+    fn detect() -> Self {
+        let term = Term::stderr();
+        if !term.is_term() {
+            return Self::Plain;
+        }
+        match term.size_checked() {
+            Some((_, columns)) if columns < COMPACT_WIDTH => Self::Compact,
+            _ => Self::Full,
+        }
+    }
+}
+
+/// Current phase of the [`Layout::Plain`] output.
+struct PlainPhase {
+    message: String,
+    total: u64,
+    position: u64,
+    printed_percent: u64,
+}
+
+/// Progress bars and spinners on the terminal.
+pub struct ConsoleProgress {
+    verbosity: Verbosity,
+    layout: Layout,
+    bar: Mutex<Option<ProgressBar>>,
+    plain_phase: Mutex<Option<PlainPhase>>,
+    /// Lines shown so far, for the diagnostics bundle.
+    log: Mutex<Vec<String>>,
+}
+
+impl ConsoleProgress {
+    pub fn new(verbosity: Verbosity) -> Self {
+        Self {
+            verbosity,
+            layout: Layout::detect(),
+            bar: Mutex::new(None),
+            plain_phase: Mutex::new(None),
+            log: Mutex::new(vec![]),
+        }
+    }
+
+    /// The info, warning and detail lines shown so far.
+    pub fn log(&self) -> Vec<String> {
+        self.log.lock().clone()
+    }
+
+    fn println(&self, line: &str) {
+        match &*self.bar.lock() {
+            Some(bar) => bar.println(line),
+            None => println!("{line}"),
+        }
+    }
+}
+
+impl Progress for ConsoleProgress {
+    fn info(&self, message: &str) {
+        self.log.lock().push(message.to_string());
+        if self.verbosity != Verbosity::Quiet {
+            self.println(message);
+        }
+    }
+
+    fn warn(&self, message: &str) {
+        self.log.lock().push(format!("Warning: {message}"));
+        let line = format!("{}: {}", "Warning".yellow().bold(), message);
+        match &*self.bar.lock() {
+            Some(bar) => bar.println(line),
+            None => eprintln!("{line}"),
+        }
+    }
+
+    fn wants_details(&self) -> bool {
+        self.verbosity == Verbosity::Verbose
+    }
+
+    fn detail(&self, message: &str) {
+        if self.wants_details() {
+            self.log.lock().push(message.to_string());
+            self.println(message);
+        }
+    }
+
+    fn begin_phase(&self, message: &str, total: Option<u64>) {
+        if self.verbosity == Verbosity::Quiet {
+            return;
+        }
+        if self.layout == Layout::Plain {
+            println!("{message}");
+            *self.plain_phase.lock() = total.map(|total| PlainPhase {
+                message: message.to_string(),
+                total,
+                position: 0,
+                printed_percent: 0,
+            });
+            return;
+        }
+        let bar = match total {
+            None => util::spinner(message.to_string()),
+            Some(total) => {
+                let template = match self.layout {
+                    Layout::Compact => "{percent:>3}% {wide_msg}",
+                    _ => "{msg}\n{pos}/{len} {wide_bar}",
+                };
+                let bar = ProgressBar::new(total);
+                bar.set_style(ProgressStyle::default_bar().template(template).unwrap());
+                bar.set_message(message.to_string());
+                bar.enable_steady_tick(Duration::from_millis(200));
+                bar
+            }
+        };
+        if let Some(previous) = self.bar.lock().replace(bar) {
+            previous.finish_and_clear();
+        }
+    }
+
+    fn inc(&self, delta: u64) {
+        if let Some(bar) = &*self.bar.lock() {
+            bar.inc(delta);
+        }
+        if let Some(phase) = &mut *self.plain_phase.lock() {
+            phase.position += delta;
+            let percent = phase.position * 100 / phase.total.max(1);
+            if percent >= phase.printed_percent + PLAIN_STEP {
+                phase.printed_percent = percent - percent % PLAIN_STEP;
+                println!("{} {}%", phase.message, phase.printed_percent);
+            }
+        }
+    }
+
+    fn update_stats(&self, stats: &StatsSnapshot) {
+        if let Some(bar) = &*self.bar.lock() {
+            let mut message = format!(
+                "Read {}, written {} ({}/s), {} entries ({} tex, {} other)",
+                HumanBytes(stats.bytes_in),
+                HumanBytes(stats.bytes_out),
+                HumanBytes(stats.write_rate()),
+                stats.entries_written(),
+                stats.tex_entries,
+                stats.other_entries
+            );
+            if stats.reused_entries > 0 {
+                message.push_str(&format!(", {} reused", stats.reused_entries));
+            }
+            if stats.failures > 0 {
+                message.push_str(&format!(", {} failed", stats.failures));
+            }
+            bar.set_message(message);
+        }
+    }
+
+    fn end_phase(&self) {
+        if let Some(bar) = self.bar.lock().take() {
+            bar.finish_and_clear();
+        }
+        self.plain_phase.lock().take();
+    }
+}
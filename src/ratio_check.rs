@@ -0,0 +1,74 @@
+//! Flags textures whose decompression ratio is far off the typical one of their format, a hint of
+//! corrupted source data or an entry that isn't the texture its name says.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+
+/// Entries smaller than this are dominated by the header, their ratio says nothing.
+const MIN_SIZE: u64 = 64 * 1024;
+/// Formats need this many samples for a typical ratio.
+const MIN_SAMPLES: usize = 20;
+/// How far off the median ratio of the format an entry has to be.
+const TOLERANCE: f64 = 16.0;
+
+#[derive(Default)]
+pub struct RatioCheck {
+    /// Entry hash and ratio by DXGI format.
+    formats: Mutex<HashMap<u32, Vec<(u64, f64)>>>,
+}
+
+/// A texture with an atypical ratio.
+pub struct Anomaly {
+    pub hash: u64,
+    pub format: u32,
+    /// Decompressed size divided by the size in the source pak.
+    pub ratio: f64,
+    /// Median ratio of the format.
+    pub typical: f64,
+}
+
+impl RatioCheck {
+    /// Adds a texture, `compressed` is its size in the source pak.
+    pub fn add(&self, hash: u64, format: u32, compressed: u64, decompressed: u64) {
+        if decompressed < MIN_SIZE || compressed == 0 {
+            return;
+        }
+        let ratio = decompressed as f64 / compressed as f64;
+        self.formats
+            .lock()
+            .entry(format)
+            .or_default()
+            .push((hash, ratio));
+    }
+
+    /// The anomalies, furthest off first.
+    pub fn into_anomalies(self) -> Vec<Anomaly> {
+        let mut anomalies = vec![];
+        for (format, mut samples) in self.formats.into_inner() {
+            if samples.len() < MIN_SAMPLES {
+                continue;
+            }
+            samples.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let typical = samples[samples.len() / 2].1;
+            anomalies.extend(
+                samples
+                    .into_iter()
+                    .filter(|(_, ratio)| {
+                        *ratio > typical * TOLERANCE || *ratio < typical / TOLERANCE
+                    })
+                    .map(|(hash, ratio)| Anomaly {
+                        hash,
+                        format,
+                        ratio,
+                        typical,
+                    }),
+            );
+        }
+        anomalies.sort_by(|a, b| {
+            let off = |anomaly: &Anomaly| (anomaly.ratio / anomaly.typical).ln().abs();
+            off(b).total_cmp(&off(a))
+        });
+        anomalies
+    }
+}
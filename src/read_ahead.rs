@@ -0,0 +1,93 @@
+//! Decouples disk reads from processing: a dedicated reader thread reads the raw entries in order
+//! through one file handle and hands them to the rayon workers through a bounded channel.
+
+use std::{collections::BTreeSet, sync::mpsc, thread};
+
+use parking_lot::{Condvar, Mutex};
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use ree_pak_core::pak::PakEntry;
+
+use crate::pak::PakFile;
+
+/// Entries `f` returned for, to hold the reader back.
+#[derive(Default)]
+struct Finished {
+    /// Index of the first entry `f` didn't return for yet.
+    first_unfinished: usize,
+    /// Finished entries after `first_unfinished`.
+    ahead: BTreeSet<usize>,
+    /// The workers stopped, nothing finishes anymore.
+    stopped: bool,
+}
+
+impl Finished {
+    fn finish(&mut self, index: usize) {
+        self.ahead.insert(index);
+        while self.ahead.remove(&self.first_unfinished) {
+            self.first_unfinished += 1;
+        }
+    }
+}
+
+/// Calls `f` on the current rayon pool for every entry, stopping at the first error.
+///
+/// The raw data of the entries selected by `prefetch` is read by the reader thread, at most
+/// `depth` entries ahead of the workers, the others get `None`. Read errors are passed to `f`.
+///
+/// With a `window`, no entry is handed out that far past the first one `f` hasn't returned for,
+/// which bounds the entries an output written in source order holds back.
+pub fn try_for_each<P, F>(
+    source: &PakFile,
+    entries: &[&PakEntry],
+    depth: usize,
+    window: Option<usize>,
+    prefetch: P,
+    f: F,
+) -> eyre::Result<()>
+where
+    P: Fn(&PakEntry) -> bool + Send,
+    F: Fn(usize, &PakEntry, eyre::Result<Option<Vec<u8>>>) -> eyre::Result<()> + Sync + Send,
+{
+    let mut reader = source.sequential_reader()?;
+    let (sender, receiver) = mpsc::sync_channel(depth);
+    let finished = Mutex::new(Finished::default());
+    let finished_changed = Condvar::new();
+    let (finished, finished_changed) = (&finished, &finished_changed);
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for (index, &entry) in entries.iter().enumerate() {
+                if let Some(window) = window {
+                    let mut finished = finished.lock();
+                    while !finished.stopped && index >= finished.first_unfinished + window {
+                        finished_changed.wait(&mut finished);
+                    }
+                    if finished.stopped {
+                        break;
+                    }
+                }
+                let raw = prefetch(entry)
+                    .then(|| reader.read_entry(entry))
+                    .transpose();
+                // the workers stopped on an error and dropped the receiver
+                if sender.send((index, entry, raw)).is_err() {
+                    break;
+                }
+            }
+        });
+        let result = receiver
+            .into_iter()
+            .par_bridge()
+            .try_for_each(|(index, entry, raw)| {
+                let result = f(index, entry, raw);
+                if window.is_some() {
+                    finished.lock().finish(index);
+                    finished_changed.notify_all();
+                }
+                result
+            });
+        // a reader waiting for the window would wait forever
+        finished.lock().stopped = true;
+        finished_changed.notify_all();
+        result
+    })
+}
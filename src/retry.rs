@@ -0,0 +1,31 @@
+//! `--retries`: reads of an entry that fail are tried again after a growing pause, network shares
+//! and external drives fail reads now and then without the data being damaged.
+
+use std::{thread, time::Duration};
+
+use crate::cancel::Cancelled;
+
+pub const DEFAULT_RETRIES: u32 = 2;
+/// Pause before the first retry, doubled for each following one.
+const FIRST_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Calls `attempt` until it succeeds, at most `retries` more times after the first failure.
+/// Returns the result and the number of retries it took.
+pub fn run<T>(
+    retries: u32,
+    mut attempt: impl FnMut() -> eyre::Result<T>,
+) -> (eyre::Result<T>, u32) {
+    let mut backoff = FIRST_BACKOFF;
+    let mut retried = 0;
+    loop {
+        let result = attempt();
+        match &result {
+            Err(e) if retried < retries && e.downcast_ref::<Cancelled>().is_none() => {
+                thread::sleep(backoff);
+                backoff *= 2;
+                retried += 1;
+            }
+            _ => return (result, retried),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! `--script`: a [Rhai](https://rhai.rs) script deciding per entry whether it's written and what
+//! data, for custom filters and transforms without forking the tool. Both functions are optional:
+//!
+//! ```rhai
+//! // false leaves the entry out, called with the source data before any other processing
+//! fn filter(entry) {
+//!     !entry.name.contains("/Monster/em0150/")
+//! }
+//!
+//! // the data written for the entry, called with the processed (decompressed) data
+//! fn transform(entry, data) {
+//!     data
+//! }
+//! ```
+//!
+//! `entry` is a map of `name` (empty if unknown), `hash` (hex), `size` and `is_tex`, tex files
+//! also have `format` (e.g. `bc7`), `dxgi_format`, `width`, `height`, `mips` and `images`. `data`
+//! is a blob, the tex file with its header.
+
+use std::{fs, path::Path};
+
+use rhai::{AST, Blob, Dynamic, Engine, Map, Scope};
+
+use crate::{
+    names::NameTable,
+    tex_format::TexFormat,
+    tex_header::{TEX_MAGIC, TexHeader},
+    util,
+};
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    has_filter: bool,
+    has_transform: bool,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let source = fs::read_to_string(path)?;
+        let engine = Engine::new();
+        let ast = engine
+            .compile(source)
+            .map_err(|e| eyre::eyre!("{}: {e}", util::display_path(path)))?;
+        let has_function = |name: &str| ast.iter_functions().any(|function| function.name == name);
+        let (has_filter, has_transform) = (has_function("filter"), has_function("transform"));
+        if !has_filter && !has_transform {
+            eyre::bail!(
+                "{} defines neither `filter` nor `transform`.",
+                util::display_path(path)
+            );
+        }
+        Ok(Self {
+            engine,
+            ast,
+            has_filter,
+            has_transform,
+        })
+    }
+
+    /// Whether the script keeps the entry, `raw` is its source data.
+    pub fn filter(&self, names: &NameTable, hash: u64, raw: &[u8]) -> eyre::Result<bool> {
+        if !self.has_filter {
+            return Ok(true);
+        }
+        self.call("filter", (entry_map(names, hash, raw),), names, hash)
+    }
+
+    /// The data the script writes for the entry, `data` is the processed data.
+    pub fn transform(&self, names: &NameTable, hash: u64, data: Vec<u8>) -> eyre::Result<Vec<u8>> {
+        if !self.has_transform {
+            return Ok(data);
+        }
+        let entry = entry_map(names, hash, &data);
+        let data: Blob = self.call("transform", (entry, data), names, hash)?;
+        Ok(data)
+    }
+
+    fn call<T: rhai::Variant + Clone>(
+        &self,
+        function: &str,
+        args: impl rhai::FuncArgs,
+        names: &NameTable,
+        hash: u64,
+    ) -> eyre::Result<T> {
+        self.engine
+            .call_fn(&mut Scope::new(), &self.ast, function, args)
+            .map_err(|e| {
+                eyre::eyre!(
+                    "script `{function}` failed for {}: {e}",
+                    names.display_name(hash)
+                )
+            })
+    }
+}
+
+/// What the script sees of an entry.
+fn entry_map(names: &NameTable, hash: u64, data: &[u8]) -> Map {
+    let mut entry = Map::new();
+    let mut set = |key: &str, value: Dynamic| {
+        entry.insert(key.into(), value);
+    };
+    set("name", names.get_name(hash).unwrap_or_default().into());
+    set("hash", format!("{hash:016X}").into());
+    set("size", (data.len() as i64).into());
+    let header = data
+        .starts_with(TEX_MAGIC)
+        .then(|| TexHeader::parse(data).ok())
+        .flatten();
+    set("is_tex", header.is_some().into());
+    if let Some(header) = header {
+        set("format", TexFormat::of(header.format).to_string().into());
+        set("dxgi_format", (header.format as i64).into());
+        set("width", (header.width as i64).into());
+        set("height", (header.height as i64).into());
+        set("mips", (header.mip_count as i64).into());
+        set("images", (header.image_count as i64).into());
+    }
+    entry
+}
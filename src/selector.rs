@@ -0,0 +1,148 @@
+//! Entry identifiers accepted on the command line: a full path, a path glob or a raw hex hash.
+//!
+//! Paths and globs are matched case-insensitively with `/` separators, the `natives/STM/` root
+//! may be left out. A path ending with `/` selects everything under it. In globs `*` and `?`
+//! stay within a path segment, `**` matches across segments.
+
+use std::{fmt, slice, str::FromStr};
+
+use ree_pak_core::pak::PakEntry;
+
+use crate::{names::NameTable, path_hash};
+
+const NATIVES_ROOT: &str = "natives/stm/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntrySelector {
+    Hash(u64),
+    /// Normalized path, a directory if it ends with `/`.
+    Path(String),
+    /// Normalized glob pattern.
+    Glob(String),
+}
+
+impl EntrySelector {
+    /// Whether the selector needs the file name table to match anything.
+    pub fn needs_names(&self) -> bool {
+        !matches!(self, Self::Hash(_))
+    }
+
+    pub fn matches(&self, hash: u64, names: &NameTable) -> bool {
+        if let Self::Hash(selected) = self {
+            return *selected == hash;
+        }
+        let Some(name) = names.get_name(hash) else {
+            return false;
+        };
+        let name = normalize(&name);
+        let relative = name.strip_prefix(NATIVES_ROOT).unwrap_or(&name);
+        [name.as_str(), relative]
+            .into_iter()
+            .any(|name| self.matches_name(name))
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        match self {
+            Self::Hash(_) => false,
+            Self::Path(path) if path.ends_with('/') => name.starts_with(path.as_str()),
+            Self::Path(path) => name == path,
+            Self::Glob(pattern) => glob_match(pattern.as_bytes(), name.as_bytes()),
+        }
+    }
+}
+
+impl FromStr for EntrySelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty entry identifier".to_string());
+        }
+        let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"));
+        if let Some(hex) = hex.or((s.len() == 16).then_some(s)) {
+            if let Ok(hash) = u64::from_str_radix(hex, 16) {
+                return Ok(Self::Hash(hash));
+            }
+            if hex.len() != s.len() {
+                return Err(format!("invalid hash `{s}`"));
+            }
+        }
+
+        let path = normalize(s);
+        let path = path.trim_start_matches('/').to_string();
+        if path.contains(['*', '?']) {
+            Ok(Self::Glob(path))
+        } else {
+            Ok(Self::Path(path))
+        }
+    }
+}
+
+impl fmt::Display for EntrySelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hash(hash) => write!(f, "{hash:016X}"),
+            Self::Path(path) | Self::Glob(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+/// Fails if any of the selectors needs a file name table and none is loaded.
+pub fn check_names(selectors: &[EntrySelector], names: &NameTable) -> eyre::Result<()> {
+    if names.is_loaded() {
+        return Ok(());
+    }
+    match selectors.iter().find(|selector| selector.needs_names()) {
+        Some(selector) => eyre::bail!(
+            "`{selector}` is a path, addressing entries by path requires a file name table, use its hash instead."
+        ),
+        None => Ok(()),
+    }
+}
+
+/// The single entry of the pak selected by `selector`, fails if none or several match.
+pub fn find_one<'a>(
+    selector: &EntrySelector,
+    entries: &'a [PakEntry],
+    names: &NameTable,
+) -> eyre::Result<&'a PakEntry> {
+    check_names(slice::from_ref(selector), names)?;
+    let mut matches = entries
+        .iter()
+        .filter(|entry| selector.matches(entry.hash(), names));
+    let Some(entry) = matches.next() else {
+        eyre::bail!("no entry matches `{selector}`.");
+    };
+    let more = matches.count();
+    if more > 0 {
+        eyre::bail!(
+            "`{selector}` matches {} entries, select a single one by its full path or hash.",
+            more + 1
+        );
+    }
+    Ok(entry)
+}
+
+fn normalize(path: &str) -> String {
+    path_hash::normalize_path(path).to_lowercase()
+}
+
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern {
+        [] => name.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=name.len()).any(|i| glob_match(rest, &name[i..]))
+        }
+        [b'*', rest @ ..] => (0..=name.len())
+            .take_while(|&i| i == 0 || name[i - 1] != b'/')
+            .any(|i| glob_match(rest, &name[i..])),
+        [b'?', rest @ ..] => name
+            .split_first()
+            .is_some_and(|(c, name)| *c != b'/' && glob_match(rest, name)),
+        [c, rest @ ..] => name
+            .split_first()
+            .is_some_and(|(n, name)| n == c && glob_match(rest, name)),
+    }
+}
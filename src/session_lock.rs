@@ -0,0 +1,69 @@
+//! Lock file next to the output, so two runs (e.g. after double-clicking the exe twice) can't
+//! write the same pak at once.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use parking_lot::{Mutex, const_mutex};
+
+use crate::util;
+
+/// Lock files currently held, removed by [`release_all`] on exits that skip destructors.
+static HELD: Mutex<Vec<PathBuf>> = const_mutex(Vec::new());
+
+/// Held for the whole run, the lock file is removed when it's dropped.
+pub struct SessionLock {
+    path: PathBuf,
+}
+
+impl SessionLock {
+    pub fn lock_path(output_path: &Path) -> PathBuf {
+        let mut path = output_path.as_os_str().to_owned();
+        path.push(".lock");
+        PathBuf::from(path)
+    }
+
+    /// Creates the lock file of `output_path`, fails if another run holds it.
+    pub fn acquire(output_path: &Path) -> eyre::Result<Self> {
+        let path = Self::lock_path(output_path);
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let owner = fs::read_to_string(&path).unwrap_or_default();
+                let owner = owner.trim();
+                eyre::bail!(
+                    "another run is in progress writing `{}`{}. If none is (e.g. after a crash), delete `{}` and try again.",
+                    util::display_path(output_path),
+                    if owner.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" (process {owner})")
+                    },
+                    util::display_path(&path)
+                );
+            }
+            Err(e) => return Err(e.into()),
+        };
+        writeln!(file, "{}", std::process::id())?;
+        HELD.lock().push(path.clone());
+        Ok(Self { path })
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        HELD.lock().retain(|path| *path != self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Removes all lock files still held, before exiting without unwinding (a panic or a second
+/// Ctrl+C), so the next run isn't refused.
+pub fn release_all() {
+    for path in HELD.lock().drain(..) {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,96 @@
+//! Ed25519 signatures of manifests, so a team distributing paks can tell its own from tampered
+//! ones. Keys are 32 bytes in hex in a text file, the signature is written in hex to `<file>.sig`.
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::util;
+
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature_path = OsString::from(path.as_os_str());
+    signature_path.push(".sig");
+    PathBuf::from(signature_path)
+}
+
+fn decode_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    let text = text.trim();
+    if text.len() != N * 2 || !text.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Reads the secret key from a file with its 32 bytes in hex, e.g. made with
+/// `openssl rand -hex 32`.
+pub fn read_signing_key(path: &Path) -> eyre::Result<SigningKey> {
+    let text = fs::read_to_string(path)?;
+    let Some(bytes) = decode_hex::<32>(&text) else {
+        eyre::bail!(
+            "`{}` isn't a signing key, it should hold 64 hex digits.",
+            util::display_path(path)
+        );
+    };
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// The public key to give to the people verifying, in hex.
+pub fn public_key_hex(key: &SigningKey) -> String {
+    encode_hex(key.verifying_key().as_bytes())
+}
+
+/// A public key in hex, or the path of a file holding it.
+pub fn parse_public_key(key: &str) -> eyre::Result<VerifyingKey> {
+    let path = Path::new(key);
+    let text = if path.is_file() {
+        fs::read_to_string(path)?
+    } else {
+        key.to_string()
+    };
+    let Some(bytes) = decode_hex::<32>(&text) else {
+        eyre::bail!("`{key}` isn't a public key, expected 64 hex digits or a file holding them.");
+    };
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Signs the file, returns the path of the signature.
+pub fn sign_file(path: &Path, key: &SigningKey) -> eyre::Result<PathBuf> {
+    let signature = key.sign(&fs::read(path)?);
+    let signature_path = signature_path(path);
+    fs::write(
+        &signature_path,
+        format!("{}\n", encode_hex(&signature.to_bytes())),
+    )?;
+    Ok(signature_path)
+}
+
+/// Checks the file against its `.sig`.
+pub fn verify_file(path: &Path, key: &VerifyingKey) -> eyre::Result<()> {
+    let signature_path = signature_path(path);
+    let text = fs::read_to_string(&signature_path)?;
+    let Some(bytes) = decode_hex::<64>(&text) else {
+        eyre::bail!(
+            "`{}` isn't a signature file.",
+            util::display_path(&signature_path)
+        );
+    };
+    key.verify(&fs::read(path)?, &Signature::from_bytes(&bytes))
+        .map_err(|_| {
+            eyre::eyre!(
+                "the signature of `{}` doesn't match, the file was changed or signed with another key.",
+                util::display_path(path)
+            )
+        })
+}
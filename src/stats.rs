@@ -0,0 +1,127 @@
+//! Counters of a decompression run. Workers only bump atomics, a render thread hands snapshots to
+//! the [`Progress`] at a fixed interval, so new metrics stay off the hot path.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::progress::Progress;
+
+const RENDER_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct Stats {
+    started: Instant,
+    pub bytes_in: AtomicU64,
+    pub bytes_out: AtomicU64,
+    pub tex_entries: AtomicU64,
+    pub other_entries: AtomicU64,
+    pub reused_entries: AtomicU64,
+    pub skipped_by_size: AtomicU64,
+    pub skipped_by_format: AtomicU64,
+    pub size_mismatches: AtomicU64,
+    pub failures: AtomicU64,
+    /// Hash of the entry processed last, 0 before the first.
+    pub last_entry: AtomicU64,
+}
+
+/// Point in time view of the [`Stats`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatsSnapshot {
+    pub elapsed: Duration,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub tex_entries: u64,
+    pub other_entries: u64,
+    pub reused_entries: u64,
+    pub skipped_by_size: u64,
+    pub skipped_by_format: u64,
+    pub size_mismatches: u64,
+    pub failures: u64,
+    pub last_entry: Option<u64>,
+}
+
+impl StatsSnapshot {
+    pub fn entries_written(&self) -> u64 {
+        self.tex_entries + self.other_entries
+    }
+
+    /// Output bytes per second.
+    pub fn write_rate(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            (self.bytes_out as f64 / seconds) as u64
+        } else {
+            0
+        }
+    }
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            tex_entries: AtomicU64::new(0),
+            other_entries: AtomicU64::new(0),
+            reused_entries: AtomicU64::new(0),
+            skipped_by_size: AtomicU64::new(0),
+            skipped_by_format: AtomicU64::new(0),
+            size_mismatches: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            last_entry: AtomicU64::new(0),
+        }
+    }
+
+    pub fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn set_last_entry(&self, hash: u64) {
+        self.last_entry.store(hash, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        StatsSnapshot {
+            elapsed: self.started.elapsed(),
+            bytes_in: load(&self.bytes_in),
+            bytes_out: load(&self.bytes_out),
+            tex_entries: load(&self.tex_entries),
+            other_entries: load(&self.other_entries),
+            reused_entries: load(&self.reused_entries),
+            skipped_by_size: load(&self.skipped_by_size),
+            skipped_by_format: load(&self.skipped_by_format),
+            size_mismatches: load(&self.size_mismatches),
+            failures: load(&self.failures),
+            last_entry: Some(load(&self.last_entry)).filter(|&hash| hash != 0),
+        }
+    }
+
+    /// Runs `f` while a render thread passes snapshots to `progress`.
+    pub fn report_while<T>(&self, progress: &dyn Progress, f: impl FnOnce() -> T) -> T {
+        let done = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    progress.update_stats(&self.snapshot());
+                    thread::sleep(RENDER_INTERVAL);
+                }
+                progress.update_stats(&self.snapshot());
+            });
+            // stop the render thread even if `f` panics, the scope waits for it
+            let _done = SetOnDrop(&done);
+            f()
+        })
+    }
+}
+
+struct SetOnDrop<'a>(&'a AtomicBool);
+
+impl Drop for SetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
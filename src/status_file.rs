@@ -0,0 +1,181 @@
+//! `--status-file`: the progress of the run as JSON, rewritten while it runs, for launchers and
+//! overlays that spawned the tool and can't read its terminal.
+//!
+//! ```json
+//! {"state": "running", "phase": "Processing entries", "position": 1200, "total": 5000,
+//!  "entries_written": 1180, "bytes_written": 123456789, "elapsed_secs": 42.0, "eta_secs": 133,
+//!  "current_entry": "natives/STM/...", "error": null, "outputs": []}
+//! ```
+//!
+//! `state` is `running`, then `done`, `partial`, `cancelled` or `failed`. The file is replaced
+//! atomically, readers never see half of it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use serde_json::{Value, json};
+
+use crate::{
+    cancel::Cancelled, names::NameTable, pipeline::Summary, progress::Progress,
+    stats::StatsSnapshot, util,
+};
+
+/// How often the file is rewritten at most, launchers poll it.
+const WRITE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Passes everything on to the wrapped [`Progress`] and mirrors it to the status file.
+pub struct StatusFile<'a> {
+    inner: &'a dyn Progress,
+    path: PathBuf,
+    names: &'a NameTable,
+    position: AtomicU64,
+    state: Mutex<State>,
+    /// A failing write is reported once, not on every update.
+    warned: AtomicBool,
+}
+
+#[derive(Default)]
+struct State {
+    phase: String,
+    total: Option<u64>,
+    stats: StatsSnapshot,
+    /// Whether the current phase reports stats, only processing does.
+    stats_live: bool,
+    last_write: Option<Instant>,
+}
+
+impl<'a> StatusFile<'a> {
+    pub fn new(path: &Path, inner: &'a dyn Progress, names: &'a NameTable) -> Self {
+        let status_file = Self {
+            inner,
+            path: path.to_path_buf(),
+            names,
+            position: AtomicU64::new(0),
+            state: Mutex::new(State::default()),
+            warned: AtomicBool::new(false),
+        };
+        status_file.write(&mut status_file.state.lock(), "running", None, &[]);
+        status_file
+    }
+
+    /// Writes the final state of the run.
+    pub fn finish(&self, result: &eyre::Result<Summary>) {
+        let mut state = self.state.lock();
+        match result {
+            Ok(summary) => {
+                let (status, error) = match &summary.processing_error {
+                    Some(e) if e.downcast_ref::<Cancelled>().is_some() => ("cancelled", None),
+                    Some(e) => ("partial", Some(format!("{e:#}"))),
+                    None => ("done", None),
+                };
+                self.write(&mut state, status, error, &summary.output_paths);
+            }
+            Err(e) => self.write(&mut state, "failed", Some(format!("{e:#}")), &[]),
+        }
+    }
+
+    /// Rewrites the file if the last write is long enough ago.
+    fn update(&self, state: &mut State) {
+        if state
+            .last_write
+            .is_none_or(|last_write| last_write.elapsed() >= WRITE_INTERVAL)
+        {
+            self.write(state, "running", None, &[]);
+        }
+    }
+
+    fn write(&self, state: &mut State, status: &str, error: Option<String>, outputs: &[PathBuf]) {
+        state.last_write = Some(Instant::now());
+        let position = self.position.load(Ordering::Relaxed);
+        let stats = &state.stats;
+        let elapsed = stats.elapsed.as_secs_f64();
+        let eta = match state.total {
+            Some(total) if state.stats_live && position > 0 && elapsed > 0.0 => Value::from(
+                (elapsed / position as f64 * total.saturating_sub(position) as f64) as u64,
+            ),
+            _ => Value::Null,
+        };
+        let status = json!({
+            "state": status,
+            "phase": state.phase,
+            "position": position,
+            "total": state.total,
+            "entries_written": stats.entries_written(),
+            "bytes_written": stats.bytes_out,
+            "elapsed_secs": elapsed,
+            "eta_secs": eta,
+            "current_entry": stats.last_entry.map(|hash| self.names.display_name(hash).into_owned()),
+            "error": error,
+            "outputs": outputs.iter().map(|path| util::display_path(path)).collect::<Vec<_>>(),
+        });
+        // a launcher polling the file must never read half of it
+        let mut temp_path = self.path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let written = fs::write(&temp_path, status.to_string())
+            .and_then(|()| fs::rename(&temp_path, &self.path));
+        let Err(e) = written else {
+            return;
+        };
+        if !self.warned.swap(true, Ordering::Relaxed) {
+            self.inner.warn(&format!(
+                "Failed to write the status file {}: {e}",
+                util::display_path(&self.path)
+            ));
+        }
+    }
+}
+
+impl Progress for StatusFile<'_> {
+    fn info(&self, message: &str) {
+        self.inner.info(message);
+    }
+
+    fn warn(&self, message: &str) {
+        self.inner.warn(message);
+    }
+
+    fn wants_details(&self) -> bool {
+        self.inner.wants_details()
+    }
+
+    fn detail(&self, message: &str) {
+        self.inner.detail(message);
+    }
+
+    fn begin_phase(&self, message: &str, total: Option<u64>) {
+        self.inner.begin_phase(message, total);
+        let mut state = self.state.lock();
+        state.phase = message.to_string();
+        state.total = total;
+        state.stats_live = false;
+        self.position.store(0, Ordering::Relaxed);
+        self.write(&mut state, "running", None, &[]);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.inner.inc(delta);
+        // per entry, the file is rewritten from the stats updates
+        self.position.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    fn update_stats(&self, stats: &StatsSnapshot) {
+        self.inner.update_stats(stats);
+        let mut state = self.state.lock();
+        state.stats = *stats;
+        state.stats_live = true;
+        self.update(&mut state);
+    }
+
+    fn end_phase(&self) {
+        self.inner.end_phase();
+        let mut state = self.state.lock();
+        state.phase.clear();
+        state.total = None;
+        self.update(&mut state);
+    }
+}
@@ -0,0 +1,39 @@
+//! `-` as the input or output pak. Reading a pak and writing one both need to seek, the streams
+//! are spooled through temp files.
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+pub fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Temp file standing in for a stream, `kind` tells the input and output apart.
+pub fn temp_path(kind: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "{}-{}.{kind}.pak",
+        env!("CARGO_PKG_NAME"),
+        process::id()
+    ))
+}
+
+/// Writes stdin to a temp file and returns its path.
+pub fn spool_stdin() -> eyre::Result<PathBuf> {
+    let path = temp_path("stdin");
+    let mut file = fs::File::create(&path)?;
+    io::copy(&mut io::stdin().lock(), &mut file)?;
+    Ok(path)
+}
+
+/// Copies the finished pak to stdout and removes it.
+pub fn stream_to_stdout(path: &Path) -> eyre::Result<()> {
+    let mut stdout = io::stdout().lock();
+    io::copy(&mut fs::File::open(path)?, &mut stdout)?;
+    stdout.flush()?;
+    fs::remove_file(path)?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Locates the Monster Hunter Wilds install through the Steam library folders.
+
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const GAME_DIR_NAME: &str = "MonsterHunterWilds";
+const APP_ID: u32 = 2246340;
+
+/// Chunk paks of every Monster Hunter Wilds install found, sorted by path.
+pub fn find_chunk_paks() -> Vec<PathBuf> {
+    let mut paks: Vec<PathBuf> = find_game_dirs()
+        .iter()
+        .flat_map(|dir| chunk_paks_in(dir))
+        .collect();
+    paks.sort();
+    paks.dedup();
+    paks
+}
+
+/// Every Steam library folder found, the Steam roots included.
+pub fn all_library_folders() -> Vec<PathBuf> {
+    // a library is listed by every root that knows it, maybe through a link or in another case
+    let mut seen = HashSet::new();
+    steam_roots()
+        .iter()
+        .flat_map(|root| library_folders(root))
+        .filter(|folder| folder.is_dir())
+        .filter(|folder| seen.insert(fs::canonicalize(folder).unwrap_or_else(|_| folder.clone())))
+        .collect()
+}
+
+/// Steam build id of the game install `path` is in, from the app manifest of its library. `None`
+/// for paks outside a Steam install, e.g. copies.
+pub fn build_id(path: &Path) -> Option<String> {
+    let steamapps = path
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == GAME_DIR_NAME))?
+        .parent()?
+        .parent()?;
+    let acf = fs::read_to_string(steamapps.join(format!("appmanifest_{APP_ID}.acf"))).ok()?;
+    vdf_strings(&acf)
+        .windows(2)
+        .find(|pair| pair[0].eq_ignore_ascii_case("buildid"))
+        .map(|pair| pair[1].clone())
+}
+
+fn find_game_dirs() -> Vec<PathBuf> {
+    all_library_folders()
+        .iter()
+        .map(|library| library.join("steamapps").join("common").join(GAME_DIR_NAME))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// `re_chunk_000.pak`, `re_chunk_000.pak.sub_000.pak` and the game's own patch paks, without
+/// anything this tool wrote.
+fn chunk_paks_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_ascii_lowercase())
+                .is_some_and(|name| {
+                    name.starts_with("re_chunk_")
+                        && name.ends_with(".pak")
+                        && !name.contains(".uncompressed.")
+                        && !name.contains(".prev.")
+                })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn steam_roots() -> Vec<PathBuf> {
+    use winreg::{RegKey, enums::HKEY_CURRENT_USER};
+
+    let mut roots = vec![];
+    if let Ok(steam_path) = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(r"Software\Valve\Steam")
+        .and_then(|key| key.get_value::<String, _>("SteamPath"))
+    {
+        roots.push(PathBuf::from(steam_path));
+    }
+    for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+        if let Some(program_files) = env::var_os(var) {
+            roots.push(PathBuf::from(program_files).join("Steam"));
+        }
+    }
+    roots
+}
+
+#[cfg(not(windows))]
+fn steam_roots() -> Vec<PathBuf> {
+    let Some(home) = env::var_os("HOME").map(PathBuf::from) else {
+        return vec![];
+    };
+    vec![
+        home.join(".steam").join("steam"),
+        home.join(".local").join("share").join("Steam"),
+        // flatpak
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ]
+}
+
+/// Library folders listed in `steamapps/libraryfolders.vdf`, the Steam root itself included.
+fn library_folders(steam_root: &Path) -> Vec<PathBuf> {
+    let mut folders = vec![steam_root.to_path_buf()];
+    if let Ok(vdf) = fs::read_to_string(steam_root.join("steamapps").join("libraryfolders.vdf")) {
+        folders.extend(parse_library_paths(&vdf));
+    }
+    folders
+}
+
+/// Values of all `"path"` keys in a `libraryfolders.vdf`.
+///
+/// ```text
+/// "libraryfolders"
+/// {
+///     "0"
+///     {
+///         "path"  "D:\\SteamLibrary"
+///         ...
+/// ```
+fn parse_library_paths(vdf: &str) -> Vec<PathBuf> {
+    let tokens = vdf_strings(vdf);
+    tokens
+        .windows(2)
+        .filter(|pair| pair[0].eq_ignore_ascii_case("path"))
+        .map(|pair| PathBuf::from(&pair[1]))
+        .collect()
+}
+
+/// Quoted strings of a VDF document, unescaped.
+fn vdf_strings(vdf: &str) -> Vec<String> {
+    let mut strings = vec![];
+    let mut chars = vdf.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => string.extend(chars.next()),
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
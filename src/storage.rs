@@ -0,0 +1,105 @@
+//! Where the bytes of an input pak come from. Readers of a [`PakFile`](crate::pak::PakFile) go
+//! through a [`Storage`] picked at runtime, new backends only have to hand out seekable readers.
+
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use memmap2::Mmap;
+
+use crate::open_files;
+
+/// A reader over the whole stored file.
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Backing of an input file, shared by all worker threads.
+pub trait Storage: Send + Sync {
+    /// A new independent reader at the start of the file. Readers are cheap to create for the
+    /// in-memory backends, file backed ones may hold a file handle while alive.
+    fn reader(&self) -> eyre::Result<Box<dyn ReadSeek + '_>>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum StorageKind {
+    /// Read through file handles, one per reader.
+    #[default]
+    Fs,
+    /// Memory-map the file, readers copy out of the page cache.
+    Mmap,
+    /// Read the whole file into memory up front.
+    Memory,
+}
+
+impl StorageKind {
+    pub fn open(self, path: &Path) -> eyre::Result<Box<dyn Storage>> {
+        Ok(match self {
+            Self::Fs => Box::new(FsStorage {
+                path: path.to_path_buf(),
+            }),
+            Self::Mmap => {
+                let file = fs::File::open(path)?;
+                // SAFETY: the source pak is only read, modifying it while we run is unsupported anyway
+                Box::new(MmapStorage(unsafe { Mmap::map(&file)? }))
+            }
+            Self::Memory => Box::new(MemoryStorage(fs::read(path)?)),
+        })
+    }
+
+    /// Whether reads are served from memory, reading ahead of the workers gains nothing then.
+    pub fn is_in_memory(self) -> bool {
+        self != Self::Fs
+    }
+}
+
+struct FsStorage {
+    path: std::path::PathBuf,
+}
+
+/// A buffered file reader that keeps its open file permit.
+struct FsReader {
+    reader: io::BufReader<fs::File>,
+    _permit: open_files::Permit,
+}
+
+impl Read for FsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Seek for FsReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+impl Storage for FsStorage {
+    fn reader(&self) -> eyre::Result<Box<dyn ReadSeek + '_>> {
+        let permit = open_files::acquire();
+        Ok(Box::new(FsReader {
+            reader: io::BufReader::new(fs::File::open(&self.path)?),
+            _permit: permit,
+        }))
+    }
+}
+
+struct MmapStorage(Mmap);
+
+impl Storage for MmapStorage {
+    fn reader(&self) -> eyre::Result<Box<dyn ReadSeek + '_>> {
+        Ok(Box::new(io::Cursor::new(&self.0[..])))
+    }
+}
+
+struct MemoryStorage(Vec<u8>);
+
+impl Storage for MemoryStorage {
+    fn reader(&self) -> eyre::Result<Box<dyn ReadSeek + '_>> {
+        Ok(Box::new(io::Cursor::new(&self.0[..])))
+    }
+}
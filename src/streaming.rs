@@ -0,0 +1,64 @@
+//! Streamed textures come in two entries: the resident tex at `natives/STM/<path>` with the low-res
+//! mips, and the tex at `natives/STM/streaming/<path>` with the full mip chain the game streams
+//! in. An output with only the resident half still streams the high-res mips from the compressed
+//! source, so both halves are selected together.
+
+use std::collections::{HashMap, HashSet};
+
+use ree_pak_core::pak::PakEntry;
+
+use crate::{names::NameTable, path_hash};
+
+const ROOT: &str = "natives/stm/";
+const STREAMING_DIR: &str = "streaming/";
+
+/// Name of the other half of a streamed texture, resident <-> streaming.
+fn counterpart(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    let relative = lower.strip_prefix(ROOT)?;
+    // the hash is case-insensitive, the case of the result doesn't matter
+    match relative.strip_prefix(STREAMING_DIR) {
+        Some(resident) => Some(format!("{ROOT}{resident}")),
+        None => Some(format!("{ROOT}{STREAMING_DIR}{relative}")),
+    }
+}
+
+/// Adds the missing halves of the streamed textures in `selected`, keeping the source order.
+/// Returns the number of streamed textures seen and of entries added.
+pub fn complete_pairs<'a>(
+    selected: &mut Vec<&'a PakEntry>,
+    all: &'a [PakEntry],
+    names: &NameTable,
+) -> (usize, usize) {
+    let positions: HashMap<u64, usize> = all
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| (entry.hash(), position))
+        .collect();
+    let selected_hashes: HashSet<u64> = selected.iter().map(|entry| entry.hash()).collect();
+
+    let mut streamed = 0;
+    let mut added = HashSet::new();
+    for entry in selected.iter() {
+        if names.is_tex_file(entry.hash()) != Some(true) {
+            continue;
+        }
+        let Some(other) = names.get_name(entry.hash()).and_then(counterpart) else {
+            continue;
+        };
+        let other_hash = path_hash::hash_path(&other);
+        if !positions.contains_key(&other_hash) {
+            continue;
+        }
+        streamed += 1;
+        if !selected_hashes.contains(&other_hash) {
+            added.insert(other_hash);
+        }
+    }
+
+    if !added.is_empty() {
+        selected.extend(added.iter().map(|hash| &all[positions[hash]]));
+        selected.sort_by_key(|entry| positions[&entry.hash()]);
+    }
+    (streamed, added.len())
+}
@@ -0,0 +1,47 @@
+//! Pixel format families of textures, the DXGI formats of a family differ in color space or
+//! signedness only.
+
+use std::fmt;
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum TexFormat {
+    Bc1,
+    Bc2,
+    Bc3,
+    Bc4,
+    Bc5,
+    Bc6h,
+    Bc7,
+    Rgba8,
+    Bgra8,
+    R8,
+    /// Any format not listed.
+    Other,
+}
+
+impl TexFormat {
+    /// Family of a DXGI format.
+    pub fn of(dxgi_format: u32) -> Self {
+        match dxgi_format {
+            70..=72 => Self::Bc1,
+            73..=75 => Self::Bc2,
+            76..=78 => Self::Bc3,
+            79..=81 => Self::Bc4,
+            82..=84 => Self::Bc5,
+            94..=96 => Self::Bc6h,
+            97..=99 => Self::Bc7,
+            27..=32 => Self::Rgba8,
+            87 | 90 | 91 => Self::Bgra8,
+            60..=64 => Self::R8,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for TexFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
@@ -0,0 +1,257 @@
+//! Minimal reader for the RE Engine tex header, enough to inspect a texture without decoding it.
+//!
+//! Layout (version > 27): 0x28 byte header followed by `image_count * mip_count` mip headers of
+//! 16 bytes each (`u64` offset, `u32` pitch, `u32` size).
+
+use std::{error::Error, fmt, ops::Range};
+
+pub const TEX_MAGIC: &[u8; 4] = b"TEX\0";
+
+pub const HEADER_SIZE: usize = 0x28;
+pub const MIP_HEADER_SIZE: usize = 0x10;
+
+#[derive(Debug, Clone)]
+pub struct TexHeader {
+    pub width: u16,
+    pub height: u16,
+    pub depth: u16,
+    pub image_count: u8,
+    pub mip_count: u8,
+    /// DXGI format.
+    pub format: u32,
+    /// Mip headers of all images, image-major.
+    pub mips: Vec<MipHeader>,
+}
+
+/// A tex header with values that point outside the file, the header values are included for bug
+/// reports.
+#[derive(Debug)]
+pub struct MalformedTex {
+    pub problem: String,
+    pub header: String,
+}
+
+impl fmt::Display for MalformedTex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "malformed tex: {} (header: {})",
+            self.problem, self.header
+        )
+    }
+}
+
+impl Error for MalformedTex {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MipHeader {
+    /// Offset of the mip data from the start of the file.
+    pub offset: u64,
+    pub pitch: u32,
+    pub size: u32,
+}
+
+impl TexHeader {
+    pub fn parse(data: &[u8]) -> eyre::Result<Self> {
+        if data.len() < HEADER_SIZE || !data.starts_with(TEX_MAGIC) {
+            eyre::bail!("not a tex file");
+        }
+
+        let image_count = data[0x0E];
+        let mip_count = data[0x0F] / MIP_HEADER_SIZE as u8;
+        let mip_total = image_count as usize * mip_count as usize;
+        let mips_end = HEADER_SIZE + mip_total * MIP_HEADER_SIZE;
+        if data.len() < mips_end {
+            eyre::bail!(
+                "tex header truncated: {} mip headers need {} bytes, got {}",
+                mip_total,
+                mips_end,
+                data.len()
+            );
+        }
+
+        let mips = data[HEADER_SIZE..mips_end]
+            .chunks_exact(MIP_HEADER_SIZE)
+            .map(|chunk| MipHeader {
+                offset: read_u64(chunk, 0),
+                pitch: read_u32(chunk, 8),
+                size: read_u32(chunk, 12),
+            })
+            .collect();
+
+        Ok(Self {
+            width: read_u16(data, 0x08),
+            height: read_u16(data, 0x0A),
+            depth: read_u16(data, 0x0C),
+            image_count,
+            mip_count,
+            format: read_u32(data, 0x10),
+            mips,
+        })
+    }
+
+    /// Data of the `index`-th mip of an uncompressed tex file.
+    pub fn mip_data<'a>(&self, data: &'a [u8], index: usize) -> Result<&'a [u8], MalformedTex> {
+        let mip = &self.mips[index];
+        let range = usize::try_from(mip.offset)
+            .ok()
+            .and_then(|start| Some(start..start.checked_add(mip.size as usize)?))
+            .filter(|range| range.end <= data.len());
+        match range {
+            Some(range) => Ok(&data[range]),
+            None => Err(self.malformed(
+                data,
+                format!(
+                    "mip {index} at offset {:#x} with size {:#x} is out of bounds",
+                    mip.offset, mip.size
+                ),
+            )),
+        }
+    }
+
+    /// Error for values of this header that don't fit `data`.
+    pub fn malformed(&self, data: &[u8], problem: String) -> MalformedTex {
+        MalformedTex {
+            problem,
+            header: format!(
+                "{}x{}x{}, format {}, {} images of {} mips, {:#x} bytes",
+                self.width,
+                self.height,
+                self.depth,
+                self.format,
+                self.image_count,
+                self.mip_count,
+                data.len()
+            ),
+        }
+    }
+
+    /// Size of the tex file once all mips are stored uncompressed.
+    pub fn decompressed_size(&self) -> u64 {
+        let mip_data: u64 = self.mips.iter().map(|mip| mip.size as u64).sum();
+        (HEADER_SIZE + self.mips.len() * MIP_HEADER_SIZE) as u64 + mip_data
+    }
+}
+
+/// Whether the mips of `data` are stored uncompressed, i.e. every mip is in bounds and the file is
+/// exactly as large as its decompressed form. Compressed tex files carry a table of compressed mip
+/// chunks and are smaller.
+pub fn is_uncompressed(data: &[u8], header: &TexHeader) -> bool {
+    data.len() as u64 == header.decompressed_size()
+        && (0..header.mips.len()).all(|index| header.mip_data(data, index).is_ok())
+}
+
+/// Rewrites the header of a tex file whose mips are already stored uncompressed, copying the mip
+/// data as is. `None` if the mips are compressed and need the decoder.
+pub fn rewrite_uncompressed(data: &[u8]) -> eyre::Result<Option<Vec<u8>>> {
+    let header = TexHeader::parse(data)?;
+    if !is_uncompressed(data, &header) {
+        return Ok(None);
+    }
+    let mips = header
+        .mips
+        .iter()
+        .enumerate()
+        .map(|(index, mip)| Ok((mip.pitch, header.mip_data(data, index)?)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    Ok(Some(rebuild(data, header.mip_count as usize, &mips)))
+}
+
+/// Discards the `drop_top` largest mips of every image of an uncompressed tex file, and the mips
+/// whose larger side is below `min_size`. At least one mip is kept.
+pub fn trim_mips(data: &[u8], drop_top: u8, min_size: u16) -> eyre::Result<Vec<u8>> {
+    let header = TexHeader::parse(data)?;
+    let mip_count = header.mip_count as usize;
+    if mip_count == 0 {
+        return Ok(data.to_vec());
+    }
+
+    let start = (drop_top as usize).min(mip_count - 1);
+    let largest_side = header.width.max(header.height);
+    let end = (0..mip_count)
+        .take_while(|&mip| (largest_side >> mip).max(1) >= min_size)
+        .count()
+        .max(start + 1);
+    if start == 0 && end == mip_count {
+        return Ok(data.to_vec());
+    }
+    retain_mips(data, &header, start..end)
+}
+
+/// Discards the largest mips of an uncompressed tex file until its larger side is at most
+/// `max_size`.
+pub fn cap_size(data: &[u8], max_size: u16) -> eyre::Result<Vec<u8>> {
+    let header = TexHeader::parse(data)?;
+    let largest_side = header.width.max(header.height);
+    let drop_top = (0..u16::BITS)
+        .take_while(|&mip| largest_side >> mip > max_size)
+        .count();
+    trim_mips(data, drop_top as u8, 0)
+}
+
+/// Rebuilds an uncompressed tex file with only the mips in `keep`. The dimensions shrink with the
+/// dropped top mips.
+fn retain_mips(data: &[u8], header: &TexHeader, keep: Range<usize>) -> eyre::Result<Vec<u8>> {
+    let mip_count = header.mip_count as usize;
+    let mut mips = Vec::with_capacity(header.image_count as usize * keep.len());
+    for image in 0..header.image_count as usize {
+        for mip in keep.clone() {
+            let index = image * mip_count + mip;
+            mips.push((header.mips[index].pitch, header.mip_data(data, index)?));
+        }
+    }
+
+    let dropped = keep.start as u32;
+    let mut out = rebuild(data, keep.len(), &mips);
+    write_u16(&mut out, 0x08, (header.width >> dropped).max(1));
+    write_u16(&mut out, 0x0A, (header.height >> dropped).max(1));
+    write_u16(&mut out, 0x0C, (header.depth >> dropped).max(1));
+    Ok(out)
+}
+
+/// Rebuilds a tex file from the header of `data` and new `(pitch, data)` mips, `mip_count` per
+/// image in image-major order. The mip data is packed right after the mip headers.
+pub fn rebuild(data: &[u8], mip_count: usize, mips: &[(u32, &[u8])]) -> Vec<u8> {
+    let mips_end = HEADER_SIZE + mips.len() * MIP_HEADER_SIZE;
+    let mip_data_size: usize = mips.iter().map(|(_, bytes)| bytes.len()).sum();
+
+    let mut out = Vec::with_capacity(mips_end + mip_data_size);
+    out.extend_from_slice(&data[..HEADER_SIZE]);
+    out[0x0F] = (mip_count * MIP_HEADER_SIZE) as u8;
+    let mut offset = mips_end as u64;
+    for (pitch, bytes) in mips {
+        out.extend_from_slice(&offset.to_le_bytes());
+        out.extend_from_slice(&pitch.to_le_bytes());
+        out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        offset += bytes.len() as u64;
+    }
+    for (_, bytes) in mips {
+        out.extend_from_slice(bytes);
+    }
+    out
+}
+
+/// Sets the DXGI format of a tex file.
+pub fn set_format(data: &mut [u8], format: u32) {
+    write_u32(data, 0x10, format);
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn write_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
@@ -0,0 +1,42 @@
+//! `--io-throttle`: caps the disk bandwidth of a run. Reads and writes of entries take their
+//! share of a global budget, waiting until it allows them, so the game stays responsive.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Mutex, const_mutex};
+
+/// Bytes per second, 0 for no limit.
+static LIMIT: AtomicU64 = AtomicU64::new(0);
+/// When the budget spent so far runs out, later transfers start from there.
+static BUDGET_END: Mutex<Option<Instant>> = const_mutex(None);
+
+/// Sets the limit in bytes per second, `None` to lift it.
+pub fn set_limit(bytes_per_second: Option<u64>) {
+    LIMIT.store(bytes_per_second.unwrap_or(0), Ordering::Relaxed);
+    *BUDGET_END.lock() = None;
+}
+
+/// Accounts for `bytes` transferred, sleeps the calling thread while over the limit.
+pub fn consume(bytes: u64) {
+    let limit = LIMIT.load(Ordering::Relaxed);
+    if limit == 0 {
+        return;
+    }
+    let duration = Duration::from_secs_f64(bytes as f64 / limit as f64);
+    let now = Instant::now();
+    let start = {
+        let mut budget_end = BUDGET_END.lock();
+        let start = budget_end.map_or(now, |end| end.max(now));
+        *budget_end = Some(start + duration);
+        start
+    };
+    // the transfer already happened, wait for the budget it took
+    let wait = start + duration - now;
+    if !wait.is_zero() {
+        thread::sleep(wait);
+    }
+}
@@ -0,0 +1,153 @@
+//! Re-encodes BC7 textures to a cheaper block format, trading quality for disk space.
+
+use std::fmt;
+
+use clap::ValueEnum;
+use texpresso::{Algorithm, Format, Params};
+
+use crate::tex_header::{self, TexHeader};
+
+const DXGI_BC1_UNORM: u32 = 71;
+const DXGI_BC1_UNORM_SRGB: u32 = 72;
+const DXGI_BC3_UNORM: u32 = 77;
+const DXGI_BC3_UNORM_SRGB: u32 = 78;
+const DXGI_BC7_UNORM: u32 = 98;
+const DXGI_BC7_UNORM_SRGB: u32 = 99;
+
+const BC7_BLOCK_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TranscodeFormat {
+    /// Half the size of BC7, 1-bit alpha.
+    Bc1,
+    /// Same size as BC7, keeps smooth alpha.
+    Bc3,
+}
+
+impl TranscodeFormat {
+    fn texpresso_format(self) -> Format {
+        match self {
+            Self::Bc1 => Format::Bc1,
+            Self::Bc3 => Format::Bc3,
+        }
+    }
+
+    fn block_size(self) -> usize {
+        match self {
+            Self::Bc1 => 8,
+            Self::Bc3 => 16,
+        }
+    }
+
+    fn dxgi_format(self, srgb: bool) -> u32 {
+        match (self, srgb) {
+            (Self::Bc1, false) => DXGI_BC1_UNORM,
+            (Self::Bc1, true) => DXGI_BC1_UNORM_SRGB,
+            (Self::Bc3, false) => DXGI_BC3_UNORM,
+            (Self::Bc3, true) => DXGI_BC3_UNORM_SRGB,
+        }
+    }
+}
+
+impl fmt::Display for TranscodeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bc1 => write!(f, "bc1"),
+            Self::Bc3 => write!(f, "bc3"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TranscodeQuality {
+    Fast,
+    #[default]
+    Normal,
+    /// Noticeably slower.
+    Best,
+}
+
+impl TranscodeQuality {
+    fn algorithm(self) -> Algorithm {
+        match self {
+            Self::Fast => Algorithm::RangeFit,
+            Self::Normal => Algorithm::ClusterFit,
+            Self::Best => Algorithm::IterativeClusterFit,
+        }
+    }
+}
+
+impl fmt::Display for TranscodeQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fast => write!(f, "fast"),
+            Self::Normal => write!(f, "normal"),
+            Self::Best => write!(f, "best"),
+        }
+    }
+}
+
+/// Re-encodes an uncompressed BC7 tex file to `format`, other formats are returned unchanged.
+pub fn transcode(
+    data: &[u8],
+    format: TranscodeFormat,
+    quality: TranscodeQuality,
+) -> eyre::Result<Vec<u8>> {
+    let header = TexHeader::parse(data)?;
+    let srgb = match header.format {
+        DXGI_BC7_UNORM => false,
+        DXGI_BC7_UNORM_SRGB => true,
+        _ => return Ok(data.to_vec()),
+    };
+    let params = Params {
+        algorithm: quality.algorithm(),
+        ..Params::default()
+    };
+
+    let mut mips = Vec::with_capacity(header.mips.len());
+    for (i, mip) in header.mips.iter().enumerate() {
+        let bc7 = header.mip_data(data, i)?;
+        // the pitch includes row padding, fall back to the mip width if there is none
+        let level = i % header.mip_count as usize;
+        let blocks_per_row = if mip.pitch > 0 {
+            mip.pitch as usize / BC7_BLOCK_SIZE
+        } else {
+            ((header.width as usize) >> level).max(1).div_ceil(4)
+        };
+        if blocks_per_row == 0 {
+            let problem = format!("mip {i} has a pitch of {:#x}, less than a block", mip.pitch);
+            return Err(header.malformed(data, problem).into());
+        }
+        let row_bytes = blocks_per_row * BC7_BLOCK_SIZE;
+        let rows = bc7.len() / row_bytes;
+        // a partial row would put blocks past the end of the decoded image
+        if bc7.len() != rows * row_bytes {
+            let problem = format!(
+                "mip {i} has {:#x} bytes, not a whole number of {row_bytes:#x} byte block rows",
+                bc7.len()
+            );
+            return Err(header.malformed(data, problem).into());
+        }
+
+        let (width, height) = (blocks_per_row * 4, rows * 4);
+        let mut rgba = vec![0u8; width * height * 4];
+        for (block_index, block) in bc7.chunks_exact(BC7_BLOCK_SIZE).enumerate() {
+            let (row, col) = (block_index / blocks_per_row, block_index % blocks_per_row);
+            let offset = (row * 4 * width + col * 4) * 4;
+            bcdec_rs::bc7(block, &mut rgba[offset..], width * 4);
+        }
+
+        let target = format.texpresso_format();
+        let mut encoded = vec![0u8; target.compressed_size(width, height)];
+        target.compress(&rgba, width, height, params, &mut encoded);
+        mips.push(((blocks_per_row * format.block_size()) as u32, encoded));
+    }
+
+    let mips: Vec<(u32, &[u8])> = mips
+        .iter()
+        .map(|(pitch, encoded)| (*pitch, encoded.as_slice()))
+        .collect();
+    let mut out = tex_header::rebuild(data, header.mip_count as usize, &mips);
+    tex_header::set_format(&mut out, format.dxgi_format(srgb));
+    Ok(out)
+}
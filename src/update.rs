@@ -0,0 +1,108 @@
+//! Opt-in check for a newer release on GitHub. File name lists and tex versions change with game
+//! patches, an old build keeps working but writes outputs the game no longer loads.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::changelog;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/eigeen/mhws-tex-decompressor/releases/latest";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Release {
+    pub tag: String,
+    version: (u32, u32, u32),
+    /// The release page, for the notes and all downloads.
+    pub page_url: String,
+    assets: Vec<Asset>,
+}
+
+pub struct Asset {
+    pub name: String,
+    url: String,
+}
+
+impl Release {
+    pub fn is_newer(&self) -> bool {
+        changelog::parse_version(env!("CARGO_PKG_VERSION"))
+            .is_some_and(|current| self.version > current)
+    }
+
+    /// The download for this platform, guessed from the asset names.
+    pub fn asset_for_platform(&self) -> Option<&Asset> {
+        self.assets.iter().find(|asset| {
+            let name = asset.name.to_ascii_lowercase();
+            if cfg!(windows) {
+                name.ends_with(".exe") || name.contains("windows")
+            } else {
+                name.contains(std::env::consts::OS)
+            }
+        })
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .build()
+}
+
+/// The latest release, drafts and pre-releases aren't considered by GitHub.
+pub fn latest_release() -> eyre::Result<Release> {
+    let response = agent()
+        .get(LATEST_RELEASE_URL)
+        .set("Accept", "application/vnd.github+json")
+        .call()?;
+    let release: Value = serde_json::from_reader(response.into_reader())?;
+    let tag = release["tag_name"].as_str().unwrap_or_default().to_string();
+    let Some(version) = changelog::parse_version(&tag) else {
+        eyre::bail!("the latest release has an unexpected tag `{tag}`.");
+    };
+    let assets = release["assets"]
+        .as_array()
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| {
+                    Some(Asset {
+                        name: asset["name"].as_str()?.to_string(),
+                        url: asset["browser_download_url"].as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Release {
+        version,
+        page_url: release["html_url"].as_str().unwrap_or_default().to_string(),
+        tag,
+        assets,
+    })
+}
+
+/// Downloads the asset into `dir`. A running executable can't replace itself, a file of the same
+/// name there, like the current build, is kept and the download prefixed with the tag.
+pub fn download(release: &Release, asset: &Asset, dir: &Path) -> eyre::Result<PathBuf> {
+    let mut path = dir.join(&asset.name);
+    if path.exists() {
+        path = dir.join(format!("{}-{}", release.tag, asset.name));
+    }
+    let temp_path = path.with_extension("download");
+    let response = agent().get(&asset.url).call()?;
+    let mut file = fs::File::create(&temp_path)?;
+    io::copy(&mut response.into_reader(), &mut file)?;
+    drop(file);
+    fs::rename(&temp_path, &path)?;
+    Ok(path)
+}
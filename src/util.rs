@@ -0,0 +1,167 @@
+use std::{
+    borrow::Cow,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use ree_pak_core::pak::PakArchive;
+
+use crate::exit_code::InvalidInput;
+
+/// Parses a human readable size like `512M`, `20G` or `1.5GiB` into bytes (binary units).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split_at = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split_at);
+    let number: f64 = number.parse().map_err(|_| format!("invalid size `{s}`"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return Err(format!("invalid size unit in `{s}`")),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parses a `u64` given in hex, with or without `0x`.
+pub fn parse_hex_u64(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let hex = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u64::from_str_radix(hex, 16).map_err(|_| format!("invalid hex number `{s}`"))
+}
+
+/// Per-user config directory of the tool (`%APPDATA%` on Windows, XDG config dir elsewhere).
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    Some(base?.join("mhws-tex-decompressor"))
+}
+
+/// Path typed or pasted by the user, without surrounding whitespace and quotes
+/// (Explorer's "Copy as path" quotes them).
+#[cfg(windows)]
+pub fn user_path(input: &str) -> PathBuf {
+    PathBuf::from(input.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Path typed or pasted by the user, without surrounding whitespace and quotes. Windows paths,
+/// e.g. from guides, are mapped to the Steam library or Proton prefix they refer to.
+#[cfg(not(windows))]
+pub fn user_path(input: &str) -> PathBuf {
+    crate::wine::native_path(input.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Makes `path` absolute and, on Windows, turns it into a `\\?\` path, so paths longer than
+/// `MAX_PATH` (deep or localized Steam library folders) can be opened.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    use std::{
+        ffi::OsString,
+        path::{Component, Prefix},
+    };
+
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let Some(Component::Prefix(prefix)) = absolute.components().next() else {
+        return absolute;
+    };
+    match prefix.kind() {
+        Prefix::Disk(_) => {
+            let mut long = OsString::from(r"\\?\");
+            long.push(absolute.as_os_str());
+            PathBuf::from(long)
+        }
+        Prefix::UNC(server, share) => {
+            let mut long = OsString::from(r"\\?\UNC\");
+            long.push(server);
+            long.push(r"\");
+            long.push(share);
+            // skip the prefix and root components
+            PathBuf::from(long).join(absolute.components().skip(2).collect::<PathBuf>())
+        }
+        // already verbatim, or a device path
+        _ => absolute,
+    }
+}
+
+/// Makes `path` absolute, there's no path length limit to work around outside Windows.
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Path for messages, without the `\\?\` prefix added by [`long_path`].
+pub fn display_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{unc}")
+    } else {
+        path.strip_prefix(r"\\?\").unwrap_or(&path).to_string()
+    }
+}
+
+pub fn read_archive_file(path: &Path) -> eyre::Result<PakArchive> {
+    let file = fs::File::open(path)?;
+    let mut reader = io::BufReader::new(file);
+    ree_pak_core::read::read_archive(&mut reader).map_err(|e| InvalidInput::new(path, e).into())
+}
+
+/// Spinner for phases without a known length.
+pub fn spinner(message: impl Into<Cow<'static, str>>) -> ProgressBar {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner} {msg} [{elapsed}]")
+            .unwrap(),
+    );
+    spinner.set_message(message);
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner
+}
+
+/// Small deterministic PRNG (SplitMix64), good enough for picking samples.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seed derived from the current time, for runs that don't specify one.
+    pub fn time_seed() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
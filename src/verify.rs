@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use ree_pak_core::pak::PakEntry;
+
+use crate::{pak::PakFile, util::SplitMix64};
+
+pub struct SampleReport {
+    pub seed: u64,
+    /// Hashes of the sampled entries.
+    pub sampled: Vec<u64>,
+    /// Hashes of entries whose output didn't match the re-processed source.
+    pub mismatched: Vec<u64>,
+    /// Hashes of sampled entries absent from the output.
+    pub missing: Vec<u64>,
+}
+
+impl SampleReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Re-processes a random `percent`% of the written entries with `process`, the processing of the run,
+/// and compares them byte-for-byte with the output.
+pub fn verify_sample(
+    source: &PakFile,
+    outputs: &[PakFile],
+    entries: &[&PakEntry],
+    percent: f64,
+    seed: u64,
+    process: &(dyn Fn(u64, Vec<u8>) -> eyre::Result<Vec<u8>> + Sync),
+) -> eyre::Result<SampleReport> {
+    let mut rng = SplitMix64::new(seed);
+    let ratio = (percent / 100.0).clamp(0.0, 1.0);
+    let sample: Vec<&PakEntry> = entries
+        .iter()
+        .copied()
+        .filter(|_| rng.next_f64() < ratio)
+        .collect();
+
+    let output_entries: HashMap<u64, (&PakFile, &PakEntry)> = outputs
+        .iter()
+        .flat_map(|output| output.entries().iter().map(move |entry| (output, entry)))
+        .map(|(output, entry)| (entry.hash(), (output, entry)))
+        .collect();
+
+    let results = sample
+        .par_iter()
+        .map(|&entry| -> eyre::Result<Option<bool>> {
+            let Some(&(output, output_entry)) = output_entries.get(&entry.hash()) else {
+                return Ok(None);
+            };
+            let raw = source.read_entry(entry)?;
+            let expected = process(entry.hash(), raw)?;
+            let actual = output.read_entry(output_entry)?;
+            Ok(Some(expected == actual))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    let mut report = SampleReport {
+        seed,
+        sampled: sample.iter().map(|entry| entry.hash()).collect(),
+        mismatched: vec![],
+        missing: vec![],
+    };
+    for (entry, result) in sample.iter().zip(results) {
+        match result {
+            None => report.missing.push(entry.hash()),
+            Some(false) => report.mismatched.push(entry.hash()),
+            Some(true) => {}
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,84 @@
+//! Windows paths on Linux: guides and Windows users give paths like
+//! `C:\Program Files (x86)\Steam\steamapps\common\...`, which we map to the Steam library or the
+//! Proton prefix they refer to.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::steam;
+
+/// Steam app id of Monster Hunter Wilds, its Proton prefix is preferred.
+const GAME_APP_ID: &str = "2246340";
+
+/// Maps a Windows path (`X:\...`) to the native path it most likely refers to, other paths are
+/// returned unchanged.
+pub fn native_path(input: &str) -> PathBuf {
+    let Some((drive, rest)) = split_drive(input) else {
+        return PathBuf::from(input);
+    };
+    let components: Vec<&str> = rest
+        .split(['\\', '/'])
+        .filter(|component| !component.is_empty())
+        .collect();
+
+    // Wine maps `Z:` to the root of the file system
+    if drive.eq_ignore_ascii_case(&'z') {
+        return Path::new("/").join(components.join("/"));
+    }
+
+    // anything in a Steam library, wherever the library is on this machine
+    if let Some(steamapps) = components
+        .iter()
+        .position(|component| component.eq_ignore_ascii_case("steamapps"))
+    {
+        let relative = components[steamapps..].join("/");
+        if let Some(path) = steam::all_library_folders()
+            .iter()
+            .map(|library| library.join(&relative))
+            .find(|path| path.exists())
+        {
+            return path;
+        }
+    }
+
+    // `C:` of a Proton prefix
+    if drive.eq_ignore_ascii_case(&'c') {
+        let relative = components.join("/");
+        if let Some(path) = proton_prefixes()
+            .iter()
+            .map(|prefix| prefix.join("drive_c").join(&relative))
+            .find(|path| path.exists())
+        {
+            return path;
+        }
+    }
+
+    PathBuf::from(input.replace('\\', "/"))
+}
+
+fn split_drive(input: &str) -> Option<(char, &str)> {
+    let mut chars = input.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str().strip_prefix(':')?;
+    rest.starts_with(['\\', '/']).then_some((drive, rest))
+}
+
+/// Wine prefixes Proton created in the Steam libraries, the game's first.
+fn proton_prefixes() -> Vec<PathBuf> {
+    let mut prefixes: Vec<PathBuf> = steam::all_library_folders()
+        .iter()
+        .filter_map(|library| fs::read_dir(library.join("steamapps").join("compatdata")).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join("pfx"))
+        .filter(|prefix| prefix.is_dir())
+        .collect();
+    prefixes.sort_by_key(|prefix| {
+        !prefix
+            .parent()
+            .is_some_and(|dir| dir.ends_with(GAME_APP_ID))
+    });
+    prefixes
+}
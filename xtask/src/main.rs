@@ -0,0 +1,219 @@
+//! Release tooling, run with `cargo xtask <command>`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+const BIN_NAME: &str = "mhws-tex-decompressor";
+const DEFAULT_TARGETS: &[&str] = &["x86_64-pc-windows-msvc", "x86_64-unknown-linux-gnu"];
+const USAGE: &str = "\
+Usage: cargo xtask dist [--target <TRIPLE>]... [--list <PATH>]... [--features <FEATURES>]
+
+Builds release binaries into `dist/` for every target and file name list combination.
+
+Options:
+  --target <TRIPLE>      Target to build for, defaults to Windows and Linux x86_64
+  --list <PATH>          File name list (.list.zst) to embed, one binary per list
+                         (defaults to the list in assets/)
+  --features <FEATURES>  Cargo features to enable, e.g. `gui`";
+
+type Result<T> = std::result::Result<T, String>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("dist") => dist(&args[1..]),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct DistArgs {
+    targets: Vec<String>,
+    lists: Vec<PathBuf>,
+    features: Option<String>,
+}
+
+fn parse_dist_args(args: &[String]) -> Result<DistArgs> {
+    let mut dist_args = DistArgs {
+        targets: vec![],
+        lists: vec![],
+        features: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("missing value for `{arg}`"))
+        };
+        match arg.as_str() {
+            "--target" => dist_args.targets.push(value()?),
+            "--list" => dist_args.lists.push(PathBuf::from(value()?)),
+            "--features" => dist_args.features = Some(value()?),
+            _ => return Err(format!("unexpected argument `{arg}`\n\n{USAGE}")),
+        }
+    }
+    if dist_args.targets.is_empty() {
+        dist_args.targets = DEFAULT_TARGETS.iter().map(|s| s.to_string()).collect();
+    }
+    Ok(dist_args)
+}
+
+fn dist(args: &[String]) -> Result<()> {
+    let args = parse_dist_args(args)?;
+    let root = project_root();
+    let version = package_version(&root)?;
+    let dist_dir = root.join("dist");
+    fs::create_dir_all(&dist_dir).map_err(|e| format!("failed to create dist dir: {e}"))?;
+
+    // `None` builds with the default list from build.rs
+    let lists: Vec<Option<&PathBuf>> = if args.lists.is_empty() {
+        vec![None]
+    } else {
+        args.lists.iter().map(Some).collect()
+    };
+
+    for target in &args.targets {
+        for list in &lists {
+            let variant = list.map(|path| list_variant_name(path));
+            let artifact = build(&root, target, *list, variant.as_deref(), &args)?;
+
+            let extension = if target.contains("windows") { ".exe" } else { "" };
+            let mut name = format!("{BIN_NAME}-v{version}-{target}");
+            if let Some(variant) = &variant {
+                name.push('-');
+                name.push_str(variant);
+            }
+            let dest = dist_dir.join(format!("{name}{extension}"));
+            fs::copy(&artifact, &dest)
+                .map_err(|e| format!("failed to copy {}: {e}", artifact.display()))?;
+            println!("Built {}", dest.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn build(
+    root: &Path,
+    target: &str,
+    list: Option<&PathBuf>,
+    variant: Option<&str>,
+    args: &DistArgs,
+) -> Result<PathBuf> {
+    // separate target dirs, so switching the embedded list doesn't rebuild everything each time
+    let target_dir = root
+        .join("target")
+        .join("dist")
+        .join(variant.unwrap_or("default"));
+
+    let mut command = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command
+        .current_dir(root)
+        .args(["build", "--release", "--package", BIN_NAME, "--target", target])
+        .arg("--target-dir")
+        .arg(&target_dir);
+    if let Some(features) = &args.features {
+        command.args(["--features", features]);
+    }
+    if let Some(list) = list {
+        let list = fs::canonicalize(list)
+            .map_err(|e| format!("file name list `{}`: {e}", list.display()))?;
+        command.env("MHWS_NAME_LIST", list);
+    }
+
+    // reproducible builds: no local paths or build times in the binary
+    let rustflags = format!(
+        "{} --remap-path-prefix={}=. --remap-path-prefix={}=cargo",
+        env::var("RUSTFLAGS").unwrap_or_default(),
+        root.display(),
+        cargo_home().display()
+    );
+    command.env("RUSTFLAGS", rustflags.trim());
+    if let Some(epoch) = source_date_epoch(root) {
+        command.env("SOURCE_DATE_EPOCH", epoch);
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| format!("failed to run cargo: {e}"))?;
+    if !status.success() {
+        return Err(format!("build for {target} failed"));
+    }
+
+    let extension = if target.contains("windows") { ".exe" } else { "" };
+    Ok(target_dir
+        .join(target)
+        .join("release")
+        .join(format!("{BIN_NAME}{extension}")))
+}
+
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+fn cargo_home() -> PathBuf {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+        .unwrap_or_default()
+}
+
+fn package_version(root: &Path) -> Result<String> {
+    let manifest = fs::read_to_string(root.join("Cargo.toml"))
+        .map_err(|e| format!("failed to read Cargo.toml: {e}"))?;
+    let package = manifest
+        .split("[package]")
+        .nth(1)
+        .ok_or("no [package] in Cargo.toml")?;
+    package
+        .lines()
+        .find_map(|line| {
+            let value = line.trim().strip_prefix("version")?.trim_start();
+            Some(value.strip_prefix('=')?.trim().trim_matches('"').to_string())
+        })
+        .ok_or_else(|| "no package version in Cargo.toml".to_string())
+}
+
+/// `MHWs_TU1.list.zst` -> `MHWs_TU1`
+fn list_variant_name(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file_name
+        .split('.')
+        .next()
+        .unwrap_or(&file_name)
+        .to_string()
+}
+
+fn source_date_epoch(root: &Path) -> Option<String> {
+    if let Ok(epoch) = env::var("SOURCE_DATE_EPOCH") {
+        return Some(epoch);
+    }
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["log", "-1", "--format=%ct"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}